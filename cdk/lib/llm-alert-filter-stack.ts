@@ -11,6 +11,21 @@ export class LlmAlertFilterStack extends cdk.Stack {
 			type: "String",
 			description: "Slack Channel",
 		});
+		const slackOncallUserGroupId = new cdk.CfnParameter(
+			this,
+			"SlackOncallUserGroupId",
+			{
+				type: "String",
+				description: "Slack user group mentioned on critical alerts",
+				default: "",
+			},
+		);
+		const slackLocale = new cdk.CfnParameter(this, "SlackLocale", {
+			type: "String",
+			description: "Language of the Slack messages",
+			default: "ja",
+			allowedValues: ["ja", "en"],
+		});
 
 		// DynamoDB Table
 		const table = new cdk.aws_dynamodb.Table(this, "FeedbackTable", {
@@ -27,6 +42,114 @@ export class LlmAlertFilterStack extends cdk.Stack {
 				type: cdk.aws_dynamodb.AttributeType.STRING,
 			},
 		});
+		table.addGlobalSecondaryIndex({
+			indexName: "user_id_index",
+			partitionKey: {
+				name: "user_id",
+				type: cdk.aws_dynamodb.AttributeType.STRING,
+			},
+			sortKey: {
+				name: "created_at",
+				type: cdk.aws_dynamodb.AttributeType.NUMBER,
+			},
+		});
+
+		const ruleTable = new cdk.aws_dynamodb.Table(this, "RuleTable", {
+			tableName: "llm_alert_filter_rule",
+			billingMode: cdk.aws_dynamodb.BillingMode.PAY_PER_REQUEST,
+			encryption: cdk.aws_dynamodb.TableEncryption.AWS_MANAGED,
+			partitionKey: { name: "id", type: cdk.aws_dynamodb.AttributeType.STRING },
+			removalPolicy: cdk.RemovalPolicy.DESTROY,
+		});
+		ruleTable.addGlobalSecondaryIndex({
+			indexName: "log_group_index",
+			partitionKey: {
+				name: "log_group",
+				type: cdk.aws_dynamodb.AttributeType.STRING,
+			},
+		});
+
+		const routeTable = new cdk.aws_dynamodb.Table(this, "RouteTable", {
+			tableName: "llm_alert_filter_route",
+			billingMode: cdk.aws_dynamodb.BillingMode.PAY_PER_REQUEST,
+			encryption: cdk.aws_dynamodb.TableEncryption.AWS_MANAGED,
+			partitionKey: { name: "id", type: cdk.aws_dynamodb.AttributeType.STRING },
+			removalPolicy: cdk.RemovalPolicy.DESTROY,
+		});
+
+		const installationTable = new cdk.aws_dynamodb.Table(
+			this,
+			"InstallationTable",
+			{
+				tableName: "llm_alert_filter_installation",
+				billingMode: cdk.aws_dynamodb.BillingMode.PAY_PER_REQUEST,
+				encryption: cdk.aws_dynamodb.TableEncryption.AWS_MANAGED,
+				partitionKey: {
+					name: "team_id",
+					type: cdk.aws_dynamodb.AttributeType.STRING,
+				},
+				removalPolicy: cdk.RemovalPolicy.DESTROY,
+			},
+		);
+
+		const decisionTable = new cdk.aws_dynamodb.Table(this, "DecisionTable", {
+			tableName: "llm_alert_filter_decision",
+			billingMode: cdk.aws_dynamodb.BillingMode.PAY_PER_REQUEST,
+			encryption: cdk.aws_dynamodb.TableEncryption.AWS_MANAGED,
+			partitionKey: { name: "id", type: cdk.aws_dynamodb.AttributeType.STRING },
+			removalPolicy: cdk.RemovalPolicy.DESTROY,
+		});
+		decisionTable.addGlobalSecondaryIndex({
+			indexName: "log_group_index",
+			partitionKey: {
+				name: "log_group",
+				type: cdk.aws_dynamodb.AttributeType.STRING,
+			},
+			sortKey: {
+				name: "created_at",
+				type: cdk.aws_dynamodb.AttributeType.NUMBER,
+			},
+		});
+		decisionTable.addGlobalSecondaryIndex({
+			indexName: "severity_index",
+			partitionKey: {
+				name: "severity",
+				type: cdk.aws_dynamodb.AttributeType.STRING,
+			},
+			sortKey: {
+				name: "created_at",
+				type: cdk.aws_dynamodb.AttributeType.NUMBER,
+			},
+		});
+
+		// Requests to the collector already handled, kept until they are too old to be replayed.
+		const nonceTable = new cdk.aws_dynamodb.Table(this, "NonceTable", {
+			tableName: "llm_alert_filter_nonce",
+			billingMode: cdk.aws_dynamodb.BillingMode.PAY_PER_REQUEST,
+			encryption: cdk.aws_dynamodb.TableEncryption.AWS_MANAGED,
+			partitionKey: {
+				name: "nonce",
+				type: cdk.aws_dynamodb.AttributeType.STRING,
+			},
+			timeToLiveAttribute: "expires_at",
+			removalPolicy: cdk.RemovalPolicy.DESTROY,
+		});
+
+		const muteTable = new cdk.aws_dynamodb.Table(this, "MuteTable", {
+			tableName: "llm_alert_filter_mute",
+			billingMode: cdk.aws_dynamodb.BillingMode.PAY_PER_REQUEST,
+			encryption: cdk.aws_dynamodb.TableEncryption.AWS_MANAGED,
+			partitionKey: { name: "id", type: cdk.aws_dynamodb.AttributeType.STRING },
+			timeToLiveAttribute: "expires_at",
+			removalPolicy: cdk.RemovalPolicy.DESTROY,
+		});
+		muteTable.addGlobalSecondaryIndex({
+			indexName: "log_group_index",
+			partitionKey: {
+				name: "log_group",
+				type: cdk.aws_dynamodb.AttributeType.STRING,
+			},
+		});
 
 		// CloudWatch Log Group
 		const notifierLogGroup = new cdk.aws_logs.LogGroup(
@@ -38,6 +161,15 @@ export class LlmAlertFilterStack extends cdk.Stack {
 			},
 		);
 
+		const compactorLogGroup = new cdk.aws_logs.LogGroup(
+			this,
+			"CompactorLogGroup",
+			{
+				logGroupName: "/aws/lambda/llm-alert-filter-compactor",
+				removalPolicy: cdk.RemovalPolicy.DESTROY,
+			},
+		);
+
 		const collectorLogGroup = new cdk.aws_logs.LogGroup(
 			this,
 			"CollectorLogGroup",
@@ -67,6 +199,7 @@ export class LlmAlertFilterStack extends cdk.Stack {
 				secretObjectValue: {
 					SIGNING_SECRET: cdk.SecretValue.unsafePlainText("dummy"),
 					SLACK_TOKEN: cdk.SecretValue.unsafePlainText("dummy"),
+					TASK_SECRET: cdk.SecretValue.unsafePlainText("dummy"),
 				},
 			},
 		);
@@ -86,7 +219,94 @@ export class LlmAlertFilterStack extends cdk.Stack {
 						new cdk.aws_iam.PolicyStatement({
 							effect: cdk.aws_iam.Effect.ALLOW,
 							actions: ["dynamodb:Query"],
-							resources: [`${table.tableArn}/*`],
+							resources: [
+								`${table.tableArn}/*`,
+								`${ruleTable.tableArn}/*`,
+								`${muteTable.tableArn}/*`,
+							],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:Scan"],
+							resources: [routeTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:PutItem"],
+							resources: [decisionTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:GetItem"],
+							resources: [installationTable.tableArn],
+						}),
+					],
+				}),
+				bedrockPolicy: new cdk.aws_iam.PolicyDocument({
+					statements: [
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["bedrock:InvokeModel"],
+							resources: ["*"],
+						}),
+					],
+				}),
+				secretsManagerPolicy: new cdk.aws_iam.PolicyDocument({
+					statements: [
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["secretsmanager:GetSecretValue"],
+							resources: [notifierSecrets.secretArn],
+						}),
+					],
+				}),
+				logsPolicy: new cdk.aws_iam.PolicyDocument({
+					statements: [
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["logs:GetLogEvents", "logs:FilterLogEvents"],
+							resources: ["*"],
+						}),
+					],
+				}),
+			},
+		});
+
+		const compactorRole = new cdk.aws_iam.Role(this, "CompactorRole", {
+			roleName: "LlmAlertFilterCompactor",
+			assumedBy: new cdk.aws_iam.ServicePrincipal("lambda.amazonaws.com"),
+			managedPolicies: [
+				cdk.aws_iam.ManagedPolicy.fromAwsManagedPolicyName(
+					"service-role/AWSLambdaBasicExecutionRole",
+				),
+			],
+			inlinePolicies: {
+				dynamoDbPolicy: new cdk.aws_iam.PolicyDocument({
+					statements: [
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:Scan"],
+							resources: [table.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:PutItem"],
+							resources: [ruleTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:Query"],
+							resources: [`${table.tableArn}/*`, `${ruleTable.tableArn}/*`],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:Scan"],
+							resources: [routeTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:GetItem"],
+							resources: [installationTable.tableArn],
 						}),
 					],
 				}),
@@ -124,9 +344,67 @@ export class LlmAlertFilterStack extends cdk.Stack {
 					statements: [
 						new cdk.aws_iam.PolicyStatement({
 							effect: cdk.aws_iam.Effect.ALLOW,
-							actions: ["dynamodb:PutItem"],
+							actions: [
+								"dynamodb:PutItem",
+								"dynamodb:GetItem",
+								"dynamodb:UpdateItem",
+								"dynamodb:DeleteItem",
+							],
 							resources: [table.tableArn],
 						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:Query"],
+							resources: [
+								`${table.tableArn}/*`,
+								`${decisionTable.tableArn}/*`,
+								`${muteTable.tableArn}/*`,
+							],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:GetItem", "dynamodb:UpdateItem"],
+							resources: [ruleTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:GetItem", "dynamodb:UpdateItem"],
+							resources: [decisionTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:Scan"],
+							resources: [routeTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: [
+								"dynamodb:PutItem",
+								"dynamodb:GetItem",
+								"dynamodb:DeleteItem",
+								"dynamodb:Scan",
+							],
+							resources: [muteTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:PutItem", "dynamodb:GetItem"],
+							resources: [installationTable.tableArn],
+						}),
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["dynamodb:PutItem", "dynamodb:DeleteItem"],
+							resources: [nonceTable.tableArn],
+						}),
+					],
+				}),
+				bedrockPolicy: new cdk.aws_iam.PolicyDocument({
+					statements: [
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["bedrock:InvokeModel"],
+							resources: ["*"],
+						}),
 					],
 				}),
 				secretsmanagerPolicy: new cdk.aws_iam.PolicyDocument({
@@ -138,6 +416,23 @@ export class LlmAlertFilterStack extends cdk.Stack {
 						}),
 					],
 				}),
+				// The collector invokes itself to finish interactions after answering Slack.
+				lambdaPolicy: new cdk.aws_iam.PolicyDocument({
+					statements: [
+						new cdk.aws_iam.PolicyStatement({
+							effect: cdk.aws_iam.Effect.ALLOW,
+							actions: ["lambda:InvokeFunction"],
+							resources: [
+								this.formatArn({
+									service: "lambda",
+									resource: "function",
+									resourceName: "llm-alert-filter-collector",
+									arnFormat: cdk.ArnFormat.COLON_RESOURCE_NAME,
+								}),
+							],
+						}),
+					],
+				}),
 			},
 		});
 
@@ -151,6 +446,7 @@ export class LlmAlertFilterStack extends cdk.Stack {
 			architecture: cdk.aws_lambda.Architecture.ARM_64,
 			environment: {
 				TABLE_NAME: table.tableName,
+				RULE_TABLE_NAME: ruleTable.tableName,
 				//BEDROCK_MODEL_ID: "us.amazon.nova-lite-v1:0",
 				//BEDROCK_MODEL_ID: "us.amazon.nova-pro-v1:0",
 				//BEDROCK_MODEL_ID: "us.anthropic.claude-3-5-haiku-20241022-v1:0",
@@ -158,7 +454,17 @@ export class LlmAlertFilterStack extends cdk.Stack {
 				BEDROCK_TOP_P: "0.9",
 				BEDROCK_TEMPERATURE: "0.7",
 				SLACK_CHANNEL_ID: slackChannelId.valueAsString,
+				ROUTE_TABLE_NAME: routeTable.tableName,
+				DECISION_TABLE_NAME: decisionTable.tableName,
+				MUTE_TABLE_NAME: muteTable.tableName,
+				SLACK_ONCALL_USER_GROUP_ID: slackOncallUserGroupId.valueAsString,
 				SECRET_ID: notifierSecrets.secretName,
+				FEEDBACK_COMPACTION_THRESHOLD: "32768",
+				SLACK_LOCALE: slackLocale.valueAsString,
+				BEDROCK_EXPLAIN_JUDGEMENT: "false",
+				LOG_CONTEXT_LINES: "0",
+				LOG_CONTEXT_MAX_BYTES: "4096",
+				INSTALLATION_TABLE_NAME: installationTable.tableName,
 			},
 			manifestPath: "../lambda/Cargo.toml",
 			binaryName: "notifier",
@@ -167,6 +473,36 @@ export class LlmAlertFilterStack extends cdk.Stack {
 			},
 		});
 
+		const compactorFunction = new RustFunction(this, "CompactorFunction", {
+			functionName: "llm-alert-filter-compactor",
+			role: compactorRole,
+			logGroup: compactorLogGroup,
+			timeout: cdk.Duration.seconds(900),
+			memorySize: 128,
+			architecture: cdk.aws_lambda.Architecture.ARM_64,
+			environment: {
+				TABLE_NAME: table.tableName,
+				RULE_TABLE_NAME: ruleTable.tableName,
+				BEDROCK_MODEL_ID: "us.anthropic.claude-3-7-sonnet-20250219-v1:0",
+				BEDROCK_TOP_P: "0.9",
+				BEDROCK_TEMPERATURE: "0.7",
+				SLACK_CHANNEL_ID: slackChannelId.valueAsString,
+				SECRET_ID: notifierSecrets.secretName,
+				SLACK_LOCALE: slackLocale.valueAsString,
+				ROUTE_TABLE_NAME: routeTable.tableName,
+				INSTALLATION_TABLE_NAME: installationTable.tableName,
+			},
+			manifestPath: "../lambda/Cargo.toml",
+			binaryName: "compactor",
+			bundling: {
+				cargoLambdaFlags: ["--bin", "compactor", "--release"],
+			},
+		});
+		new cdk.aws_events.Rule(this, "CompactorSchedule", {
+			schedule: cdk.aws_events.Schedule.cron({ minute: "0", hour: "18" }),
+			targets: [new cdk.aws_events_targets.LambdaFunction(compactorFunction)],
+		});
+
 		const collectorFunction = new RustFunction(this, "CollectorFunction", {
 			functionName: "llm-alert-filter-collector",
 			role: collectorRole,
@@ -176,8 +512,17 @@ export class LlmAlertFilterStack extends cdk.Stack {
 			architecture: cdk.aws_lambda.Architecture.ARM_64,
 			environment: {
 				TABLE_NAME: table.tableName,
+				RULE_TABLE_NAME: ruleTable.tableName,
+				DECISION_TABLE_NAME: decisionTable.tableName,
+				MUTE_TABLE_NAME: muteTable.tableName,
+				ROUTE_TABLE_NAME: routeTable.tableName,
+				BEDROCK_MODEL_ID: "us.anthropic.claude-3-7-sonnet-20250219-v1:0",
+				BEDROCK_TOP_P: "0.9",
+				BEDROCK_TEMPERATURE: "0.7",
 				SECRET_ID: collectorSecrets.secretName,
-				SLACK_CHANNEL_ID: slackChannelId.valueAsString,
+				SLACK_LOCALE: slackLocale.valueAsString,
+				INSTALLATION_TABLE_NAME: installationTable.tableName,
+				NONCE_TABLE_NAME: nonceTable.tableName,
 			},
 			manifestPath: "../lambda/Cargo.toml",
 			binaryName: "collector",
@@ -185,9 +530,14 @@ export class LlmAlertFilterStack extends cdk.Stack {
 				cargoLambdaFlags: ["--bin", "collector", "--release"],
 			},
 		});
-		collectorFunction.addFunctionUrl({
+		const collectorUrl = collectorFunction.addFunctionUrl({
 			authType: cdk.aws_lambda.FunctionUrlAuthType.NONE,
 		});
+		// Links to the feedback page take effect once FEEDBACK_LINK_SECRET is set in both secrets.
+		notifierFunction.addEnvironment(
+			"FEEDBACK_PAGE_URL",
+			`${collectorUrl.url}feedback-page`,
+		);
 
 		// Test Resource
 		const test1LogGroup = new cdk.aws_logs.LogGroup(this, "Test1LogGroup", {
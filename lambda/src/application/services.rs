@@ -1,20 +1,101 @@
-use crate::domain::entities::Feedback;
-use crate::domain::repositories::FeedbackRepository;
-use crate::domain::value_objects::{FeedbackId, Timestamp};
-use crate::infrastructure::repositories_impl::FeedbackRepositoryImpl;
-use crate::infrastructure::slack::PrivateMetadata;
+use crate::domain::entities::{
+    most_similar_feedback, AlertCounts, Decision, Feedback, LogContext, LogEvent, LogSource, Mute,
+    ResponseTimes, RoutingTable, Rule,
+};
+use crate::domain::repositories::{
+    DecisionRepository, FeedbackRepository, InstallationRepository, LogEventRepository,
+    MuteRepository, NonceRepository, RouteRepository, RuleRepository,
+};
+use crate::domain::value_objects::Locale;
+use crate::domain::value_objects::{
+    ConflictResolution, DecisionId, FeedbackId, Fingerprint, MuteId, RuleId, RuleStatus, Severity,
+    Timestamp,
+};
+use crate::infrastructure::bedrock::{Judgement, TargetLog};
+use crate::infrastructure::block_kit::{BlockId, CommandResponse, Modal};
+use crate::infrastructure::feedback_link::{FeedbackLinkQuery, FeedbackLinks};
+use crate::infrastructure::feedback_page::{make_feedback_page, make_message_page};
+use crate::infrastructure::i18n::catalog;
+use crate::infrastructure::log_events::LogEventRepositoryImpl;
+use crate::infrastructure::oauth::OAuthSettings;
+use crate::infrastructure::repositories_impl::{
+    DecisionRepositoryImpl, FeedbackRepositoryImpl, InstallationRepositoryImpl, MuteRepositoryImpl,
+    NonceStore, RouteRepositoryImpl, RuleRepositoryImpl,
+};
+use crate::infrastructure::sinks::{Alert, AlertSink, AlertSinks, SlackSink};
+use crate::infrastructure::slack::{
+    make_command_failed_response, make_command_forbidden_response, make_conflict_view,
+    make_feedback_list_response, make_mute_response, make_stats_response, make_status_response,
+    make_test_response, make_usage_response, oncall_mention, ConflictMetadata, PrivateMetadata,
+    MAX_HOME_LOG_GROUPS, MAX_SIMILAR_FEEDBACK,
+};
+use crate::infrastructure::tasks::{Task, TaskAction, TaskQueue, MAX_TASK_AGE};
+use crate::infrastructure::validation::{InputError, InputErrors, ValidationRules};
 use crate::infrastructure::{bedrock, slack};
 use crate::util::now_rfc3339;
 use aws_lambda_events::cloudwatch_logs::LogsEvent;
 use lambda_runtime::LambdaEvent;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use typed_builder::TypedBuilder;
 
+/// Clusters with fewer feedback than this are left as they are by compaction.
+const MIN_CLUSTER_SIZE: usize = 2;
+
+/// Events sharing the timestamp of a log event read in addition to those after it.
+const SAME_TIME_EVENTS: usize = 10;
+
+/// Seconds an event is remembered after it is taken, which covers the retries of Slack.
+const EVENT_NONCE_AGE: i64 = 60 * 60;
+
+/// Period covered by the alert counts of the slash command, and by the reports of the API
+/// unless a period is given.
+pub(crate) const STATS_DAYS: i64 = 7;
+
+/// Upper bound of the events fetched on each side of an alerted event.
+pub const MAX_LOG_CONTEXT_LINES: usize = 50;
+
+/// Total size of the surrounding events kept unless configured otherwise.
+pub const DEFAULT_LOG_CONTEXT_MAX_BYTES: usize = 4096;
+
 #[derive(TypedBuilder)]
 pub struct NotificationService {
     repo: FeedbackRepositoryImpl,
+    rule_repo: RuleRepositoryImpl,
+    route_repo: RouteRepositoryImpl,
+    decision_repo: DecisionRepositoryImpl,
+    mute_repo: MuteRepositoryImpl,
     bedrock_client: bedrock::Client,
     slack_client: slack::Client,
+    /// Channel for log groups not matching any route.
     slack_channel_id: String,
+    /// Slack user group mentioned on critical alerts of log groups whose route has none.
+    #[builder(default)]
+    oncall_user_group: Option<String>,
+    /// Size in bytes of the feedback history above which active rules are sent in place of
+    /// the feedback they were generated from. Rules are never used when unset.
+    #[builder(default)]
+    compaction_threshold: Option<usize>,
+    /// Whether the model explains its judgement on the alert, in the locale of the channel.
+    #[builder(default)]
+    explain_judgement: bool,
+    /// Reads the events around each log event when set.
+    #[builder(default)]
+    log_event_repo: Option<LogEventRepositoryImpl>,
+    /// Number of events fetched on each side of a log event, capped at
+    /// `MAX_LOG_CONTEXT_LINES`. No events are fetched when zero.
+    #[builder(default)]
+    log_context_lines: usize,
+    /// Total size in bytes of the surrounding event messages kept for a log event.
+    #[builder(default = DEFAULT_LOG_CONTEXT_MAX_BYTES)]
+    log_context_max_bytes: usize,
+    /// Sinks the routes can send alerts to besides Slack.
+    #[builder(default)]
+    sinks: AlertSinks,
+    /// Makes the feedback page links of the sinks other than Slack. No links are sent
+    /// when unset.
+    #[builder(default)]
+    feedback_links: Option<FeedbackLinks>,
 }
 
 impl NotificationService {
@@ -22,83 +103,1882 @@ impl NotificationService {
         &self,
         event: LambdaEvent<LogsEvent>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let payload = event.payload;
-        let log_events = payload.aws_logs.data.log_events;
-        let log_group = payload.aws_logs.data.log_group;
+        let data = event.payload.aws_logs.data;
+        let log_events = data.log_events;
+        let log_group = data.log_group;
 
         if !log_events.is_empty() {
-            let feedback = self.repo.list_feedback_by_log_group(&log_group).await?;
+            let feedback: Vec<Feedback> = self
+                .repo
+                .list_feedback_by_log_group(&log_group)
+                .await?
+                .into_iter()
+                .filter(|v| !v.retired())
+                .collect();
+            let (rules, feedback) = self.apply_rules(&log_group, feedback).await?;
+            let routing_table = RoutingTable::new(
+                self.route_repo.list_routes().await?,
+                vec![self.slack_channel_id.clone()],
+            )?;
+            let channels = routing_table.channels(&log_group);
+            let route = routing_table.route(&log_group);
+            // The explanation is written once per log, in the locale of the first channel.
+            let explanation_locale = channels
+                .first()
+                .filter(|_| self.explain_judgement)
+                .map(|v| self.slack_client.locale(v));
+            let slack_client = self
+                .slack_client
+                .for_team(route.and_then(|v| v.team_id()))
+                .await?;
+            // Muted logs are still judged so that the suppressed alerts show up in the stats.
+            let mutes = self.mute_repo.list_mutes_by_log_group(&log_group).await?;
             for log_event in log_events {
+                let source = LogSource::builder()
+                    .log_stream(data.log_stream.clone())
+                    .owner(data.owner.clone())
+                    .subscription_filters(data.subscription_filters.clone())
+                    .event_id(log_event.id.clone())
+                    .event_time(Timestamp::from_millis(log_event.timestamp))
+                    .build();
+                let log_event = LogEvent {
+                    id: Some(log_event.id),
+                    timestamp: log_event.timestamp,
+                    message: log_event.message,
+                };
+                let context = self
+                    .log_context(&log_group, &data.log_stream, &log_event)
+                    .await;
                 let message = log_event.message;
+                let fingerprint = Fingerprint::new(&message);
+                let muted = mutes.iter().any(|v| v.applies_to(&fingerprint));
 
-                if self
+                let judgement = self
                     .bedrock_client
-                    .needs_notification(feedback.clone(), message.clone(), now_rfc3339())
-                    .await?
-                {
-                    self.slack_client
-                        .post_alert(&self.slack_channel_id, &log_group, &message)
+                    .needs_notification(
+                        rules.clone(),
+                        feedback.clone(),
+                        route.and_then(|v| v.severity_guidance()),
+                        explanation_locale,
+                        TargetLog::builder()
+                            .message(message.clone())
+                            .timestamp(now_rfc3339())
+                            .context(context.clone())
+                            .build(),
+                    )
+                    .await?;
+
+                let decision = Decision::builder()
+                    .id(DecisionId::new())
+                    .created_at(Timestamp::new())
+                    .log_group(log_group.clone())
+                    .message(message.clone())
+                    .needs_notification(judgement.needs_notification)
+                    .severity(judgement.severity)
+                    .explanation(judgement.explanation)
+                    .muted(judgement.needs_notification && muted)
+                    .source(Some(source))
+                    .context(context)
+                    .sinks(if judgement.needs_notification && !muted {
+                        route.map(|v| v.sinks().to_vec()).unwrap_or_default()
+                    } else {
+                        vec![]
+                    })
+                    .build();
+                self.decision_repo.add_decision(decision.clone()).await?;
+
+                if judgement.needs_notification && !muted {
+                    let mention = oncall_mention(
+                        judgement.severity,
+                        route.and_then(|v| v.oncall_user_group()),
+                        self.oncall_user_group.as_deref(),
+                    );
+                    let feedback_url = self
+                        .feedback_links
+                        .as_ref()
+                        .map(|v| v.url(decision.id(), &Timestamp::new()));
+                    let alert = Alert {
+                        decision: &decision,
+                        mention_user_group: mention,
+                        feedback_url: feedback_url.as_deref(),
+                    };
+                    for channel_id in channels {
+                        SlackSink {
+                            client: &slack_client,
+                            channel_id,
+                        }
+                        .send_alert(&alert)
                         .await?;
+                    }
+                    for name in decision.sinks() {
+                        self.send_to_sink(name, &alert).await;
+                    }
                 }
             }
         }
 
         Ok(())
     }
+
+    /// Sends the alert to a sink other than Slack. Failures are only logged, so that a
+    /// broken sink does not make the alerts already posted to Slack be sent again.
+    async fn send_to_sink(&self, name: &str, alert: &Alert<'_>) {
+        let Some(sink) = self.sinks.get(name) else {
+            tracing::warn!("Unknown alert sink: {}", name);
+            return;
+        };
+        if let Err(e) = sink.send_alert(alert).await {
+            tracing::warn!("Failed to send the alert to {}: {:?}", name, e);
+        }
+    }
+
+    /// Returns the events around the log event, or `None` when disabled, when there are
+    /// none, or when they could not be read, so that the alert is never held up by them.
+    async fn log_context(
+        &self,
+        log_group: &str,
+        log_stream: &str,
+        log_event: &LogEvent,
+    ) -> Option<LogContext> {
+        let repo = self.log_event_repo.as_ref()?;
+        let lines = self.log_context_lines.min(MAX_LOG_CONTEXT_LINES);
+        if lines == 0 {
+            return None;
+        }
+        match fetch_log_context(
+            repo,
+            log_group,
+            log_stream,
+            log_event,
+            lines,
+            self.log_context_max_bytes,
+        )
+        .await
+        {
+            Ok(context) => Some(context).filter(|v| !v.is_empty()),
+            Err(e) => {
+                tracing::warn!("Failed to fetch the log context: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Returns the active rules and the feedback not covered by them once the feedback
+    /// history exceeds the compaction threshold, or no rules and all feedback otherwise.
+    async fn apply_rules(
+        &self,
+        log_group: &str,
+        feedback: Vec<Feedback>,
+    ) -> Result<(Vec<Rule>, Vec<Feedback>), Box<dyn std::error::Error>> {
+        let Some(threshold) = self.compaction_threshold else {
+            return Ok((vec![], feedback));
+        };
+        if serde_json::to_vec(&feedback)?.len() <= threshold {
+            return Ok((vec![], feedback));
+        }
+
+        let rules: Vec<Rule> = self
+            .rule_repo
+            .list_rules_by_log_group(log_group)
+            .await?
+            .into_iter()
+            .filter(|v| v.status() == RuleStatus::Active)
+            .collect();
+        let covered: HashSet<&FeedbackId> =
+            rules.iter().flat_map(|v| v.source_feedback_ids()).collect();
+        let feedback = feedback
+            .iter()
+            .filter(|v| !covered.contains(v.id()))
+            .cloned()
+            .collect();
+
+        Ok((rules, feedback))
+    }
+}
+
+#[derive(TypedBuilder)]
+pub struct CompactionService {
+    repo: FeedbackRepositoryImpl,
+    rule_repo: RuleRepositoryImpl,
+    route_repo: RouteRepositoryImpl,
+    bedrock_client: bedrock::Client,
+    slack_client: slack::Client,
+    /// Channel of the reviews of the log groups without a route.
+    slack_channel_id: String,
+}
+
+impl CompactionService {
+    pub async fn compact_feedback(
+        &self,
+        _event: LambdaEvent<Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let routing_table = RoutingTable::new(
+            self.route_repo.list_routes().await?,
+            vec![self.slack_channel_id.clone()],
+        )?;
+        for log_group in self.repo.list_log_groups().await? {
+            self.compact_log_group(&routing_table, &log_group).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Proposes rules for the clusters of feedback of the log group, posting their reviews
+    /// to the first channel the log group is routed to, in the workspace of the route.
+    async fn compact_log_group(
+        &self,
+        routing_table: &RoutingTable,
+        log_group: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rules = self.rule_repo.list_rules_by_log_group(log_group).await?;
+        let feedback = self.repo.list_feedback_by_log_group(log_group).await?;
+        let Some(channel_id) = routing_table.channels(log_group).first() else {
+            tracing::warn!("No channel to review the rules of {} in", log_group);
+            return Ok(());
+        };
+        let slack_client = self
+            .slack_client
+            .for_team(routing_table.route(log_group).and_then(|v| v.team_id()))
+            .await?;
+
+        for cluster in clusters_to_propose(feedback, &rules) {
+            let source_feedback_ids = cluster.iter().map(|v| v.id().clone()).collect();
+            let summarized = self.bedrock_client.summarize_feedback(cluster).await?;
+            let rule = Rule::builder()
+                .id(RuleId::new())
+                .created_at(Timestamp::new())
+                .log_group(log_group.to_string())
+                .rule(summarized.rule)
+                .needs_notification(summarized.needs_notification)
+                .source_feedback_ids(source_feedback_ids)
+                .status(RuleStatus::Pending)
+                .build();
+
+            self.rule_repo.add_rule(rule.clone()).await?;
+            slack_client.post_rule_review(channel_id, &rule).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the clusters of feedback to propose rules for, leaving out the feedback covered
+/// by the pending and active rules. The feedback of rejected rules is clustered again, but
+/// only proposed again along with feedback given after the rejection.
+fn clusters_to_propose(feedback: Vec<Feedback>, rules: &[Rule]) -> Vec<Vec<Feedback>> {
+    let covered: HashSet<&FeedbackId> = rules
+        .iter()
+        .filter(|v| v.rejected_at().is_none())
+        .flat_map(|v| v.source_feedback_ids())
+        .collect();
+    let mut rejected_at: HashMap<&FeedbackId, &Timestamp> = HashMap::new();
+    for rule in rules {
+        let Some(at) = rule.rejected_at() else {
+            continue;
+        };
+        for id in rule.source_feedback_ids() {
+            let latest = rejected_at.entry(id).or_insert(at);
+            *latest = (*latest).max(at);
+        }
+    }
+    let feedback = feedback
+        .into_iter()
+        .filter(|v| !v.retired() && !covered.contains(v.id()))
+        .collect();
+
+    cluster_feedback(feedback)
+        .into_iter()
+        .filter(|v| v.len() >= MIN_CLUSTER_SIZE && is_new_since_rejection(v, &rejected_at))
+        .collect()
+}
+
+/// Returns whether the cluster has feedback given after the latest rejection of a rule
+/// summarizing any of its feedback, or none of its feedback was in a rejected rule.
+fn is_new_since_rejection(
+    cluster: &[Feedback],
+    rejected_at: &HashMap<&FeedbackId, &Timestamp>,
+) -> bool {
+    let Some(latest) = cluster.iter().filter_map(|v| rejected_at.get(v.id())).max() else {
+        return true;
+    };
+    cluster.iter().any(|v| v.created_at() > *latest)
+}
+
+/// Groups feedback whose messages are similar to the first feedback of a group.
+fn cluster_feedback(feedback: Vec<Feedback>) -> Vec<Vec<Feedback>> {
+    let mut clusters: Vec<Vec<Feedback>> = vec![];
+    for v in feedback {
+        match clusters
+            .iter_mut()
+            .find(|cluster| cluster[0].is_similar_to(v.message()))
+        {
+            Some(cluster) => cluster.push(v),
+            None => clusters.push(vec![v]),
+        }
+    }
+
+    clusters
 }
 
 #[derive(Clone, TypedBuilder)]
 pub(crate) struct CollectionService {
     repo: FeedbackRepositoryImpl,
+    rule_repo: RuleRepositoryImpl,
+    decision_repo: DecisionRepositoryImpl,
+    mute_repo: MuteRepositoryImpl,
+    route_repo: RouteRepositoryImpl,
+    bedrock_client: bedrock::Client,
     slack_client: slack::Client,
-    slack_channel_id: String,
+    /// Sinks the alerts are resolved on, besides Slack.
+    #[builder(default)]
+    sinks: AlertSinks,
+    /// Queue the follow-ups of the interactions are run from after they are acknowledged.
+    /// They are run before acknowledging when unset.
+    #[builder(default)]
+    tasks: Option<TaskQueue>,
+    /// Where the tasks run are recorded, so that a task delivered again is not run twice.
+    #[builder(default)]
+    nonces: Option<NonceStore>,
+    /// Response URL of the interaction being handled, set by `with_response_url`.
+    #[builder(default)]
+    response_url: Option<String>,
+    /// Rules the values entered in the modals are checked against.
+    #[builder(default)]
+    validation: ValidationRules,
+}
+
+/// Outcome of a modal submission.
+pub(crate) enum SubmissionResult {
+    /// The submission was handled and the modal can be closed.
+    Completed,
+    /// The modal should be replaced with the given view.
+    UpdateView(Modal),
+    /// The whole modal stack should be closed without further action.
+    Clear,
+    /// The modal should stay open with the messages shown under the fields.
+    Errors(HashMap<BlockId, String>),
 }
 
 impl CollectionService {
+    /// Returns the service acting in the workspace, with the bot token installed there.
+    pub(crate) async fn for_team(
+        &self,
+        team_id: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            slack_client: self.slack_client.for_team(team_id).await?,
+            ..self.clone()
+        })
+    }
+
+    /// Returns the service handling an interaction that can be replied to at the URL.
+    pub(crate) fn with_response_url(self, response_url: Option<&str>) -> Self {
+        Self {
+            response_url: response_url.map(|v| v.to_string()),
+            ..self
+        }
+    }
+
+    /// Runs a follow-up deferred by an interaction, telling the user when it fails. A task
+    /// is claimed before it runs, so that it is run once however many times it is delivered.
+    /// It is released when it could not run to the end nor be reported as failed, so that
+    /// it is run again when it is delivered again.
+    pub(crate) async fn run_task(&self, task: &Task) -> Result<(), Box<dyn std::error::Error>> {
+        let nonce = format!("task:{}", task.id());
+        if let Some(nonces) = &self.nonces {
+            let expires_at = Timestamp::from_secs(Timestamp::new().as_secs() + MAX_TASK_AGE);
+            if !nonces.claim_nonce(&nonce, &expires_at).await? {
+                tracing::info!("Task {} has already run", task.id());
+                return Ok(());
+            }
+        }
+
+        // The error is not `Send`, so it is dropped before the task is released.
+        let unfinished = match self.run_claimed_task(task).await {
+            Ok(()) => false,
+            Err(e) => {
+                tracing::error!("Failed to finish task {:?}: {:?}", task, e);
+                true
+            }
+        };
+        if !unfinished {
+            return Ok(());
+        }
+        if let Some(nonces) = &self.nonces {
+            nonces.release_nonce(&nonce).await?;
+        }
+        Err(format!("Task {} did not finish", task.id()).into())
+    }
+
+    /// Runs the task, and tells the user when it fails. The user has been told of a
+    /// failure, so a failed task is not run again either.
+    async fn run_claimed_task(&self, task: &Task) -> Result<(), Box<dyn std::error::Error>> {
+        let service = self.for_team(task.team_id()).await?;
+        // The error is not `Send`, so it is dropped before the report is awaited.
+        let failed = match service.run_task_action(task).await {
+            Ok(()) => false,
+            Err(e) => {
+                tracing::error!("Failed to run task {:?}: {:?}", task, e);
+                true
+            }
+        };
+        if failed {
+            service
+                .slack_client
+                .report_task_failure(task.response_url(), task.channel_id(), task.user_id())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn run_task_action(&self, task: &Task) -> Result<(), Box<dyn std::error::Error>> {
+        match task.action() {
+            TaskAction::UpdateAlert {
+                channel_id,
+                ts,
+                decision_id,
+            } => self.update_alert(channel_id, ts, decision_id).await,
+            TaskAction::CloseRuleReview {
+                channel_id,
+                ts,
+                rule_id,
+            } => {
+                let rule = self
+                    .rule_repo
+                    .get_rule(rule_id)
+                    .await?
+                    .ok_or("Rule not found")?;
+                self.slack_client
+                    .close_rule_review(channel_id, ts, &rule, task.user_id())
+                    .await
+            }
+            TaskAction::PublishHome => {
+                publish_home(
+                    &self.repo,
+                    &self.decision_repo,
+                    &self.slack_client,
+                    task.user_id(),
+                )
+                .await
+            }
+            TaskAction::ResolveAlert { decision_id } => {
+                let decision = self
+                    .decision_repo
+                    .get_decision(decision_id)
+                    .await?
+                    .ok_or("Decision not found")?;
+                self.sinks.resolve_alert(&decision).await;
+                Ok(())
+            }
+            TaskAction::ReplyWithJudgement {
+                channel_id,
+                thread_ts,
+                query,
+                bot_user_id,
+            } => {
+                reply_with_judgement(
+                    &self.repo,
+                    &self.route_repo,
+                    &self.bedrock_client,
+                    &self.slack_client,
+                    channel_id,
+                    thread_ts,
+                    query.as_ref().map(|(a, b)| (a.as_str(), b.as_str())),
+                    bot_user_id.as_deref(),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Runs a follow-up of the interaction of the user once it is acknowledged, or right
+    /// away when there is no queue.
+    async fn defer(
+        &self,
+        user_id: &str,
+        action: TaskAction,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let task = Task::builder()
+            .team_id(self.slack_client.team_id().map(|v| v.to_string()))
+            .user_id(user_id.to_string())
+            .response_url(self.response_url.clone())
+            .action(action)
+            .build();
+        match &self.tasks {
+            Some(tasks) => tasks.enqueue(&task).await,
+            None => self.run_task_action(&task).await,
+        }
+    }
+
+    /// Returns the result keeping the modal open with the errors under its fields, in the
+    /// language of the channel it was opened from, if any.
+    pub(crate) fn reject_input(
+        &self,
+        private_metadata: &str,
+        errors: InputErrors,
+    ) -> SubmissionResult {
+        let locale = match PrivateMetadata::try_from(private_metadata) {
+            Ok(v) => self.slack_client.locale(v.channel_id()),
+            Err(_) => self.slack_client.default_locale(),
+        };
+        let catalog = catalog(locale);
+        SubmissionResult::Errors(
+            errors
+                .into_iter()
+                .map(|(block_id, error)| (block_id, error.message(catalog)))
+                .collect(),
+        )
+    }
+
+    /// Adds the feedback submitted from the modal, once however often the submission is
+    /// received.
     pub(crate) async fn add_feedback(
         &self,
+        view_id: &str,
         private_metadata: &str,
         needs_notification: bool,
+        severity: Option<Severity>,
         reason: Option<String>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        user_id: &str,
+    ) -> Result<SubmissionResult, Box<dyn std::error::Error>> {
+        let errors = self
+            .validation
+            .check_feedback(needs_notification, reason.as_deref());
+        if !errors.is_empty() {
+            return Ok(self.reject_input(private_metadata, errors));
+        }
+        let feedback_id = FeedbackId::from_view(view_id);
+        if self.repo.get_feedback(&feedback_id).await?.is_some() {
+            return Ok(SubmissionResult::Completed);
+        }
         let private_metadata = PrivateMetadata::try_from(private_metadata)?;
+        let decision = self.get_decision(private_metadata.decision_id()).await?;
+
+        let conflicts: Vec<Feedback> = self
+            .repo
+            .list_feedback_by_log_group(decision.log_group())
+            .await?
+            .into_iter()
+            .filter(|v| v.conflicts_with(decision.message(), needs_notification))
+            .collect();
+
+        if !conflicts.is_empty() {
+            let conflict_metadata = ConflictMetadata::builder()
+                .channel_id(private_metadata.channel_id().to_string())
+                .ts(private_metadata.ts().to_string())
+                .decision_id(private_metadata.decision_id().to_string())
+                .needs_notification(needs_notification)
+                .severity(severity)
+                .reason(reason)
+                .user_id(user_id.to_string())
+                .conflicting_ids(conflicts.iter().map(|v| v.id().to_string()).collect())
+                .build()
+                .encode_base64()?;
+            return Ok(SubmissionResult::UpdateView(make_conflict_view(
+                self.slack_client.locale(private_metadata.channel_id()),
+                &conflict_metadata,
+                &conflicts,
+            )));
+        }
 
         let feedback = Feedback::builder()
-            .id(FeedbackId::new())
+            .id(feedback_id)
             .created_at(Timestamp::new())
-            .log_group(private_metadata.log_group().to_string())
-            .message(private_metadata.message().to_string())
+            .log_group(decision.log_group().to_string())
+            .message(decision.message().to_string())
             .needs_notification(needs_notification)
             .reason(reason)
+            .severity(severity)
+            .user_id(Some(user_id.to_string()))
+            .log_stream(decision.source().map(|v| v.log_stream().to_string()))
+            .build();
+        self.save_feedback(
+            private_metadata.channel_id(),
+            private_metadata.ts(),
+            &decision,
+            feedback,
+            user_id,
+        )
+        .await?;
+
+        Ok(SubmissionResult::Completed)
+    }
+
+    /// Adds the feedback held back by a conflict, keyed on the same modal as when it was
+    /// first submitted.
+    pub(crate) async fn resolve_conflict(
+        &self,
+        view_id: &str,
+        private_metadata: &str,
+        resolution: ConflictResolution,
+    ) -> Result<SubmissionResult, Box<dyn std::error::Error>> {
+        let feedback_id = FeedbackId::from_view(view_id);
+        if self.repo.get_feedback(&feedback_id).await?.is_some() {
+            return Ok(SubmissionResult::Completed);
+        }
+        let metadata = ConflictMetadata::try_from(private_metadata)?;
+        let conflicting_ids = metadata
+            .conflicting_ids()
+            .iter()
+            .map(|v| FeedbackId::try_from(v.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match resolution {
+            ConflictResolution::Replace => {
+                for id in &conflicting_ids {
+                    self.repo.delete_feedback(id).await?;
+                }
+            }
+            ConflictResolution::Retire => {
+                for id in &conflicting_ids {
+                    self.repo.retire_feedback(id).await?;
+                }
+            }
+            ConflictResolution::Cancel => return Ok(SubmissionResult::Clear),
+        }
+
+        let decision = self.get_decision(metadata.decision_id()).await?;
+        let feedback = Feedback::builder()
+            .id(feedback_id)
+            .created_at(Timestamp::new())
+            .log_group(decision.log_group().to_string())
+            .message(decision.message().to_string())
+            .needs_notification(metadata.needs_notification())
+            .reason(metadata.reason().cloned())
+            .severity(metadata.severity())
+            .user_id(Some(metadata.user_id().to_string()))
+            .log_stream(decision.source().map(|v| v.log_stream().to_string()))
+            .build();
+        self.save_feedback(
+            metadata.channel_id(),
+            metadata.ts(),
+            &decision,
+            feedback,
+            metadata.user_id(),
+        )
+        .await?;
+
+        Ok(SubmissionResult::Completed)
+    }
+
+    pub(crate) async fn review_rule(
+        &self,
+        rule_id: &str,
+        approved: bool,
+        user_id: &str,
+        channel_id: &str,
+        ts: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rule_id = RuleId::try_from(rule_id)?;
+        let status = if approved {
+            RuleStatus::Active
+        } else {
+            RuleStatus::Rejected
+        };
+
+        self.rule_repo
+            .update_rule_status(&rule_id, status, user_id)
+            .await?;
+
+        self.defer(
+            user_id,
+            TaskAction::CloseRuleReview {
+                channel_id: channel_id.to_string(),
+                ts: ts.to_string(),
+                rule_id,
+            },
+        )
+        .await
+    }
+
+    async fn get_decision(
+        &self,
+        decision_id: &str,
+    ) -> Result<Decision, Box<dyn std::error::Error>> {
+        let decision_id = DecisionId::try_from(decision_id)?;
+        Ok(self
+            .decision_repo
+            .get_decision(&decision_id)
+            .await?
+            .ok_or("Decision not found")?)
+    }
+
+    async fn save_feedback(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        decision: &Decision,
+        feedback: Feedback,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let needs_notification = feedback.needs_notification();
+        // Another delivery of the same submission got here first and does the rest.
+        if !self.repo.create_feedback(feedback).await? {
+            return Ok(());
+        }
+        self.decision_repo.mark_feedback_sent(decision.id()).await?;
+        if !needs_notification {
+            self.defer(
+                user_id,
+                TaskAction::ResolveAlert {
+                    decision_id: decision.id().clone(),
+                },
+            )
+            .await?;
+        }
+
+        self.defer_update_alert(channel_id, ts, decision.id(), user_id)
+            .await
+    }
+
+    /// Records who acknowledged the alert and when, or who resolved it if `resolved` is set.
+    pub(crate) async fn acknowledge(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        decision_id: &str,
+        user_id: &str,
+        resolved: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let decision_id = DecisionId::try_from(decision_id)?;
+        if resolved {
+            self.decision_repo
+                .resolve(&decision_id, user_id, &Timestamp::new())
+                .await?;
+            self.defer(
+                user_id,
+                TaskAction::ResolveAlert {
+                    decision_id: decision_id.clone(),
+                },
+            )
+            .await?;
+        } else {
+            self.decision_repo
+                .acknowledge(&decision_id, user_id, &Timestamp::new())
+                .await?;
+        }
+
+        self.defer_update_alert(channel_id, ts, &decision_id, user_id)
+            .await
+    }
+
+    /// Snoozes the alerts of logs like the decision's, or of its whole log group, until the
+    /// given time. The snooze is shown on the alert it was created from.
+    pub(crate) async fn snooze(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        decision_id: &str,
+        until: Timestamp,
+        whole_log_group: bool,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if until <= Timestamp::new() {
+            return Err("Snooze end time is in the past".into());
+        }
+        let decision = self.get_decision(decision_id).await?;
+        let mute = Mute::builder()
+            .id(MuteId::new())
+            .created_at(Timestamp::new())
+            .log_group(decision.log_group().to_string())
+            .expires_at(until)
+            .created_by(user_id.to_string())
+            .fingerprint((!whole_log_group).then(|| decision.fingerprint()))
+            .decision_id(Some(decision.id().clone()))
             .build();
+        self.mute_repo.add_mute(mute).await?;
+
+        self.defer_update_alert(channel_id, ts, decision.id(), user_id)
+            .await
+    }
+
+    /// Deletes a snooze before it expires, resuming the alerts it suppressed.
+    pub(crate) async fn release_snooze(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        mute_id: &str,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mute_id = MuteId::try_from(mute_id)?;
+        let Some(mute) = self.mute_repo.get_mute(&mute_id).await? else {
+            // The snooze has already expired, so only the alert is refreshed.
+            tracing::info!("Snooze {} not found", mute_id);
+            return Ok(());
+        };
+        self.mute_repo.delete_mute(&mute_id).await?;
+
+        match mute.decision_id() {
+            Some(decision_id) => {
+                self.defer_update_alert(channel_id, ts, decision_id, user_id)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Opens the modal for editing a feedback from the Home tab.
+    pub(crate) async fn open_feedback_editor(
+        &self,
+        trigger_id: &str,
+        feedback_id: &str,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let feedback = self.get_own_feedback(feedback_id, user_id).await?;
+
+        self.slack_client
+            .open_feedback_editor(trigger_id, &feedback)
+            .await
+    }
 
+    /// Saves the values entered in the feedback editor and refreshes the user's Home tab.
+    pub(crate) async fn edit_feedback(
+        &self,
+        private_metadata: &str,
+        needs_notification: bool,
+        severity: Option<Severity>,
+        reason: Option<String>,
+        user_id: &str,
+    ) -> Result<SubmissionResult, Box<dyn std::error::Error>> {
+        let errors = self
+            .validation
+            .check_feedback(needs_notification, reason.as_deref());
+        if !errors.is_empty() {
+            return Ok(self.reject_input(private_metadata, errors));
+        }
+        let mut feedback = self.get_own_feedback(private_metadata, user_id).await?;
+        feedback.revise(needs_notification, severity, reason);
         self.repo.add_feedback(feedback).await?;
 
+        self.defer(user_id, TaskAction::PublishHome).await?;
+        Ok(SubmissionResult::Completed)
+    }
+
+    /// Deletes a feedback from the Home tab and refreshes it.
+    pub(crate) async fn delete_feedback(
+        &self,
+        feedback_id: &str,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let feedback = self.get_own_feedback(feedback_id, user_id).await?;
+        self.repo.delete_feedback(feedback.id()).await?;
+
+        self.defer(user_id, TaskAction::PublishHome).await
+    }
+
+    /// Gets a feedback, failing unless the user gave it.
+    async fn get_own_feedback(
+        &self,
+        feedback_id: &str,
+        user_id: &str,
+    ) -> Result<Feedback, Box<dyn std::error::Error>> {
+        let feedback_id = FeedbackId::try_from(feedback_id)?;
+        let feedback = self
+            .repo
+            .get_feedback(&feedback_id)
+            .await?
+            .ok_or("Feedback not found")?;
+        if !feedback.is_owned_by(user_id) {
+            return Err("Feedback was given by another user")?;
+        }
+        Ok(feedback)
+    }
+
+    pub(crate) async fn open_snooze_modal(
+        &self,
+        trigger_id: &str,
+        private_metadata: PrivateMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let encoded = private_metadata.encode_base64()?;
         self.slack_client
-            .close_feedback_button(
-                &self.slack_channel_id,
-                private_metadata.ts(),
-                private_metadata.log_group(),
-                private_metadata.message(),
-            )
+            .open_snooze_modal(trigger_id, private_metadata.channel_id(), &encoded)
+            .await
+    }
+
+    pub(crate) async fn snooze_until(
+        &self,
+        private_metadata: &str,
+        until: Timestamp,
+        whole_log_group: bool,
+        user_id: &str,
+    ) -> Result<SubmissionResult, Box<dyn std::error::Error>> {
+        if until <= Timestamp::new() {
+            let errors = InputErrors::from([(BlockId::SnoozeUntil, InputError::InPast)]);
+            return Ok(self.reject_input(private_metadata, errors));
+        }
+        let private_metadata = PrivateMetadata::try_from(private_metadata)?;
+        self.snooze(
+            private_metadata.channel_id(),
+            private_metadata.ts(),
+            private_metadata.decision_id(),
+            until,
+            whole_log_group,
+            user_id,
+        )
+        .await?;
+        Ok(SubmissionResult::Completed)
+    }
+
+    async fn defer_update_alert(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        decision_id: &DecisionId,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.defer(
+            user_id,
+            TaskAction::UpdateAlert {
+                channel_id: channel_id.to_string(),
+                ts: ts.to_string(),
+                decision_id: decision_id.clone(),
+            },
+        )
+        .await
+    }
+
+    /// Redraws an alert from the stored decision and the snooze created from it, if any.
+    async fn update_alert(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        decision_id: &DecisionId,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let decision = self
+            .decision_repo
+            .get_decision(decision_id)
+            .await?
+            .ok_or("Decision not found")?;
+        let snooze = self
+            .mute_repo
+            .list_mutes_by_log_group(decision.log_group())
+            .await?
+            .into_iter()
+            .filter(|v| v.decision_id() == Some(decision.id()))
+            .max_by(|a, b| a.expires_at().cmp(b.expires_at()));
+
+        self.slack_client
+            .update_alert(channel_id, ts, &decision, snooze.as_ref())
             .await
     }
 
     pub(crate) async fn open_modal(
         &self,
         trigger_id: &str,
-        ts: String,
-        log_group: String,
-        message: String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let private_metadata = PrivateMetadata::builder()
-            .ts(ts)
-            .log_group(log_group)
-            .message(message)
-            .build()
-            .encode_base64()?;
+        private_metadata: PrivateMetadata,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let decision = self.get_decision(private_metadata.decision_id()).await?;
+        // Retired feedback no longer steers the judgement, so it is not shown as precedent.
+        let feedback: Vec<Feedback> = self
+            .repo
+            .list_feedback_by_log_group(decision.log_group())
+            .await?
+            .into_iter()
+            .filter(|v| !v.retired())
+            .collect();
+        let similar = most_similar_feedback(&feedback, decision.message(), MAX_SIMILAR_FEEDBACK);
+        let encoded = private_metadata.encode_base64()?;
         self.slack_client
-            .open_modal(trigger_id, &private_metadata)
+            .open_modal(
+                trigger_id,
+                private_metadata.channel_id(),
+                &encoded,
+                &decision,
+                &similar,
+            )
+            .await
+    }
+}
+
+/// Publishes the Home tab of a user with the alert counts of the log groups the user has
+/// given feedback on, and the user's feedback.
+async fn publish_home(
+    repo: &FeedbackRepositoryImpl,
+    decision_repo: &DecisionRepositoryImpl,
+    slack_client: &slack::Client,
+    user_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let feedback = repo.list_feedback_by_user(user_id).await?;
+    // Feedback is listed newest first, so the most recently used log groups come first.
+    let mut log_groups: Vec<&str> = vec![];
+    for v in &feedback {
+        if !log_groups.contains(&v.log_group()) {
+            log_groups.push(v.log_group());
+        }
+    }
+
+    let since = Timestamp::new().add(chrono::Duration::days(-STATS_DAYS));
+    let mut counts = vec![];
+    for log_group in log_groups.into_iter().take(MAX_HOME_LOG_GROUPS) {
+        let decisions = decision_repo
+            .list_decisions_by_log_group(log_group, &since)
+            .await?;
+        counts.push((log_group.to_string(), AlertCounts::new(&decisions)));
+    }
+
+    slack_client.publish_home(user_id, &counts, &feedback).await
+}
+
+/// Judges a log pasted by a user as if it had been logged to the log group now, with the
+/// current feedback and without rules.
+async fn judge_pasted_log(
+    repo: &FeedbackRepositoryImpl,
+    route_repo: &RouteRepositoryImpl,
+    bedrock_client: &bedrock::Client,
+    locale: Locale,
+    log_group: &str,
+    message: &str,
+) -> Result<Judgement, Box<dyn std::error::Error>> {
+    let feedback: Vec<Feedback> = repo
+        .list_feedback_by_log_group(log_group)
+        .await?
+        .into_iter()
+        .filter(|v| !v.retired())
+        .collect();
+    let routing_table = RoutingTable::new(route_repo.list_routes().await?, vec![])?;
+
+    bedrock_client
+        .needs_notification(
+            vec![],
+            feedback,
+            routing_table
+                .route(log_group)
+                .and_then(|v| v.severity_guidance()),
+            Some(locale),
+            TargetLog::builder()
+                .message(message.to_string())
+                .timestamp(now_rfc3339())
+                .build(),
+        )
+        .await
+}
+
+/// Replies in the thread with the judgement of the pasted log, or with how to ask for one
+/// if the log group or the message is missing. Log groups routed to another workspace are
+/// refused, like in the slash commands. When the bot is given, the message did not
+/// mention it, so it only replies to a pasted log in a thread where it answered a mention.
+#[allow(clippy::too_many_arguments)]
+async fn reply_with_judgement(
+    repo: &FeedbackRepositoryImpl,
+    route_repo: &RouteRepositoryImpl,
+    bedrock_client: &bedrock::Client,
+    slack_client: &slack::Client,
+    channel_id: &str,
+    thread_ts: &str,
+    query: Option<(&str, &str)>,
+    bot_user_id: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(bot_user_id) = bot_user_id {
+        if query.is_none()
+            || !slack_client
+                .has_answered_mention_in_thread(channel_id, thread_ts, bot_user_id)
+                .await?
+        {
+            return Ok(());
+        }
+    }
+    let Some((log_group, message)) = query else {
+        return slack_client.post_mention_usage(channel_id, thread_ts).await;
+    };
+    let routing_table = RoutingTable::new(route_repo.list_routes().await?, vec![])?;
+    if !slack_client.is_own_team(routing_table.route(log_group).and_then(|v| v.team_id())) {
+        tracing::warn!(
+            "Refused judgement on {} from workspace {:?}",
+            log_group,
+            slack_client.team_id()
+        );
+        return slack_client
+            .post_forbidden_reply(channel_id, thread_ts, log_group)
+            .await;
+    }
+
+    let judgement = judge_pasted_log(
+        repo,
+        route_repo,
+        bedrock_client,
+        slack_client.locale(channel_id),
+        log_group,
+        message,
+    )
+    .await?;
+
+    slack_client
+        .post_judgement_reply(channel_id, thread_ts, &judgement)
+        .await
+}
+
+/// Reads up to `lines` events on each side of the log event from its stream.
+///
+/// Events logged in the same millisecond as the log event are ordered around it by its ID.
+/// When it is not found among them, they are left out.
+async fn fetch_log_context(
+    repo: &impl LogEventRepository,
+    log_group: &str,
+    log_stream: &str,
+    log_event: &LogEvent,
+    lines: usize,
+    max_bytes: usize,
+) -> Result<LogContext, Box<dyn std::error::Error>> {
+    let mut before = repo
+        .list_events_before(log_group, log_stream, log_event.timestamp, lines)
+        .await?;
+    // Read past the events sharing the timestamp, the log event among them.
+    let mut after = repo
+        .list_events_from(
+            log_group,
+            log_stream,
+            log_event.timestamp,
+            lines + SAME_TIME_EVENTS,
+        )
+        .await?;
+    let same_time = after
+        .iter()
+        .position(|v| v.id.is_some() && v.id == log_event.id);
+    if let Some(position) = same_time {
+        before.extend(after.drain(..=position));
+        before.pop();
+    } else {
+        after.retain(|v| v.timestamp > log_event.timestamp);
+    }
+    let excess = before.len().saturating_sub(lines);
+    after.truncate(lines);
+
+    let message = |v: LogEvent| v.message.trim_end().to_string();
+    Ok(LogContext::new(
+        before.into_iter().skip(excess).map(message).collect(),
+        after.into_iter().map(message).collect(),
+        max_bytes,
+    ))
+}
+
+/// Runs the subcommands of the `/alert-filter` slash command.
+#[derive(Clone, TypedBuilder)]
+pub(crate) struct CommandService {
+    repo: FeedbackRepositoryImpl,
+    decision_repo: DecisionRepositoryImpl,
+    route_repo: RouteRepositoryImpl,
+    mute_repo: MuteRepositoryImpl,
+    bedrock_client: bedrock::Client,
+    slack_client: slack::Client,
+}
+
+impl CommandService {
+    /// Returns the service answering a command from the workspace, which only sees the log
+    /// groups routed to it.
+    pub(crate) async fn for_team(
+        &self,
+        team_id: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            slack_client: self.slack_client.for_team(team_id).await?,
+            ..self.clone()
+        })
+    }
+
+    /// Returns whether the routes of a workspace are the ones of the workspace of the
+    /// command.
+    fn is_own_team(&self, team_id: Option<&str>) -> bool {
+        self.slack_client.is_own_team(team_id)
+    }
+
+    /// Returns the answer refusing the command when the log group is routed to another
+    /// workspace than the one of the command.
+    async fn refuse_other_team(
+        &self,
+        channel_id: &str,
+        log_group: &str,
+    ) -> Result<Option<CommandResponse>, Box<dyn std::error::Error>> {
+        let routing_table = RoutingTable::new(self.route_repo.list_routes().await?, vec![])?;
+        if self.is_own_team(routing_table.route(log_group).and_then(|v| v.team_id())) {
+            return Ok(None);
+        }
+        tracing::warn!(
+            "Refused command on {} from workspace {:?}",
+            log_group,
+            self.slack_client.team_id()
+        );
+        Ok(Some(make_command_forbidden_response(
+            self.slack_client.locale(channel_id),
+            log_group,
+        )))
+    }
+
+    pub(crate) fn usage(&self, channel_id: &str) -> CommandResponse {
+        make_usage_response(self.slack_client.locale(channel_id))
+    }
+
+    pub(crate) fn failed(&self, channel_id: &str) -> CommandResponse {
+        make_command_failed_response(self.slack_client.locale(channel_id))
+    }
+
+    pub(crate) async fn status(
+        &self,
+        channel_id: &str,
+    ) -> Result<CommandResponse, Box<dyn std::error::Error>> {
+        let routes = self.route_repo.list_routes().await?;
+        let route_count = routes
+            .iter()
+            .filter(|v| self.is_own_team(v.team_id()))
+            .count();
+        let routing_table = RoutingTable::new(routes, vec![])?;
+        let mut mutes: Vec<Mute> = self
+            .mute_repo
+            .list_mutes()
+            .await?
+            .into_iter()
+            .filter(|v| {
+                self.is_own_team(routing_table.route(v.log_group()).and_then(|v| v.team_id()))
+            })
+            .collect();
+        mutes.sort_by(|a, b| a.expires_at().cmp(b.expires_at()));
+
+        Ok(make_status_response(
+            self.slack_client.locale(channel_id),
+            self.bedrock_client.model_id(),
+            self.bedrock_client.top_p(),
+            self.bedrock_client.temperature(),
+            route_count,
+            &mutes,
+        ))
+    }
+
+    pub(crate) async fn stats(
+        &self,
+        channel_id: &str,
+        log_group: &str,
+    ) -> Result<CommandResponse, Box<dyn std::error::Error>> {
+        if let Some(response) = self.refuse_other_team(channel_id, log_group).await? {
+            return Ok(response);
+        }
+        let now = Timestamp::new();
+        let decisions = self
+            .decision_repo
+            .list_decisions_by_log_group(log_group, &now.add(chrono::Duration::days(-STATS_DAYS)))
+            .await?;
+
+        Ok(make_stats_response(
+            self.slack_client.locale(channel_id),
+            log_group,
+            &decisions,
+            &now.add(chrono::Duration::days(-1)),
+        ))
+    }
+
+    pub(crate) async fn list_feedback(
+        &self,
+        channel_id: &str,
+        log_group: &str,
+    ) -> Result<CommandResponse, Box<dyn std::error::Error>> {
+        if let Some(response) = self.refuse_other_team(channel_id, log_group).await? {
+            return Ok(response);
+        }
+        let feedback = self.repo.list_feedback_by_log_group(log_group).await?;
+
+        Ok(make_feedback_list_response(
+            self.slack_client.locale(channel_id),
+            log_group,
+            &feedback,
+        ))
+    }
+
+    /// Judges the message as if it had been logged to the log group now, without storing
+    /// a decision or posting an alert. The reasoning is always shown.
+    pub(crate) async fn test(
+        &self,
+        channel_id: &str,
+        log_group: &str,
+        message: &str,
+    ) -> Result<CommandResponse, Box<dyn std::error::Error>> {
+        if let Some(response) = self.refuse_other_team(channel_id, log_group).await? {
+            return Ok(response);
+        }
+        let locale = self.slack_client.locale(channel_id);
+        let judgement = judge_pasted_log(
+            &self.repo,
+            &self.route_repo,
+            &self.bedrock_client,
+            locale,
+            log_group,
+            message,
+        )
+        .await?;
+
+        Ok(make_test_response(locale, &judgement))
+    }
+
+    pub(crate) async fn mute(
+        &self,
+        channel_id: &str,
+        user_id: &str,
+        log_group: &str,
+        duration: chrono::Duration,
+    ) -> Result<CommandResponse, Box<dyn std::error::Error>> {
+        if let Some(response) = self.refuse_other_team(channel_id, log_group).await? {
+            return Ok(response);
+        }
+        let now = Timestamp::new();
+        let mute = Mute::builder()
+            .id(MuteId::new())
+            .created_at(now.clone())
+            .log_group(log_group.to_string())
+            .expires_at(now.add(duration))
+            .created_by(user_id.to_string())
+            .build();
+        self.mute_repo.add_mute(mute.clone()).await?;
+
+        Ok(make_mute_response(
+            self.slack_client.locale(channel_id),
+            &mute,
+        ))
+    }
+}
+
+/// Answers users who ask the bot in a thread whether a log would be alerted, and shows
+/// users their Home tab.
+#[derive(Clone, TypedBuilder)]
+pub(crate) struct EventService {
+    repo: FeedbackRepositoryImpl,
+    decision_repo: DecisionRepositoryImpl,
+    route_repo: RouteRepositoryImpl,
+    bedrock_client: bedrock::Client,
+    slack_client: slack::Client,
+    /// Queue the judgements are run from after the events are answered. They are run
+    /// before answering when unset.
+    #[builder(default)]
+    tasks: Option<TaskQueue>,
+    /// Where the events taken are recorded, so that a retry of one is not handled twice.
+    /// Retries are handled like first deliveries when unset.
+    #[builder(default)]
+    nonces: Option<NonceStore>,
+}
+
+impl EventService {
+    /// Returns the service acting in the workspace, with the bot token installed there.
+    pub(crate) async fn for_team(
+        &self,
+        team_id: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            slack_client: self.slack_client.for_team(team_id).await?,
+            ..self.clone()
+        })
+    }
+
+    /// Takes the event and returns true, or returns false if another delivery of it was
+    /// taken already.
+    pub(crate) async fn claim_event(
+        &self,
+        event_id: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(nonces) = &self.nonces else {
+            return Ok(true);
+        };
+        let expires_at = Timestamp::from_secs(Timestamp::new().as_secs() + EVENT_NONCE_AGE);
+        nonces
+            .claim_nonce(&format!("event:{}", event_id), &expires_at)
+            .await
+    }
+
+    /// Gives the event up after failing to handle it, so that Slack's retry is handled.
+    pub(crate) async fn release_event(
+        &self,
+        event_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(nonces) = &self.nonces else {
+            return Ok(());
+        };
+        nonces.release_nonce(&format!("event:{}", event_id)).await
+    }
+
+    /// Replies in the thread to the log pasted by the user once the event is answered, or
+    /// right away when there is no queue. See [`TaskAction::ReplyWithJudgement`].
+    pub(crate) async fn reply_with_judgement(
+        &self,
+        user_id: &str,
+        channel_id: &str,
+        thread_ts: &str,
+        query: Option<(&str, &str)>,
+        bot_user_id: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(tasks) = &self.tasks else {
+            return reply_with_judgement(
+                &self.repo,
+                &self.route_repo,
+                &self.bedrock_client,
+                &self.slack_client,
+                channel_id,
+                thread_ts,
+                query,
+                bot_user_id,
+            )
+            .await;
+        };
+
+        let task = Task::builder()
+            .team_id(self.slack_client.team_id().map(|v| v.to_string()))
+            .user_id(user_id.to_string())
+            .action(TaskAction::ReplyWithJudgement {
+                channel_id: channel_id.to_string(),
+                thread_ts: thread_ts.to_string(),
+                query: query
+                    .map(|(log_group, message)| (log_group.to_string(), message.to_string())),
+                bot_user_id: bot_user_id.map(|v| v.to_string()),
+            })
+            .build();
+        tasks.enqueue(&task).await
+    }
+
+    pub(crate) async fn publish_home(
+        &self,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        publish_home(&self.repo, &self.decision_repo, &self.slack_client, user_id).await
+    }
+}
+
+/// Reports on the alert lifecycle served by the API.
+#[derive(Clone, TypedBuilder)]
+pub(crate) struct ReportService {
+    decision_repo: DecisionRepositoryImpl,
+}
+
+impl ReportService {
+    /// Returns the critical alerts of the last `days` days that nobody has acknowledged,
+    /// oldest first.
+    pub(crate) async fn unacknowledged_alerts(
+        &self,
+        days: i64,
+    ) -> Result<Vec<Decision>, Box<dyn std::error::Error>> {
+        let since = Timestamp::new().add(chrono::Duration::days(-days));
+        let mut alerts: Vec<Decision> = self
+            .decision_repo
+            .list_decisions_by_severity(Severity::Critical, &since)
+            .await?
+            .into_iter()
+            .filter(|v| v.alerted() && v.acknowledged_at().is_none())
+            .collect();
+        alerts.sort_by(|a, b| a.created_at().cmp(b.created_at()));
+
+        Ok(alerts)
+    }
+
+    pub(crate) async fn response_times(
+        &self,
+        log_group: &str,
+        days: i64,
+    ) -> Result<ResponseTimes, Box<dyn std::error::Error>> {
+        let since = Timestamp::new().add(chrono::Duration::days(-days));
+        let decisions = self
+            .decision_repo
+            .list_decisions_by_log_group(log_group, &since)
+            .await?;
+
+        Ok(ResponseTimes::new(&decisions))
+    }
+}
+
+/// A page served to the browser, which shows only why when its link is invalid.
+pub(crate) enum HtmlPage {
+    Ok(String),
+    Forbidden(String),
+    Conflict(String),
+}
+
+/// Takes feedback from the page linked by the alert sinks other than Slack.
+#[derive(Clone, TypedBuilder)]
+pub(crate) struct FeedbackPageService {
+    repo: FeedbackRepositoryImpl,
+    decision_repo: DecisionRepositoryImpl,
+    feedback_links: FeedbackLinks,
+    locale: Locale,
+    #[builder(default)]
+    sinks: AlertSinks,
+}
+
+impl FeedbackPageService {
+    /// Returns the page with the alert and a form to send feedback on it.
+    pub(crate) async fn show(
+        &self,
+        query: &FeedbackLinkQuery,
+    ) -> Result<HtmlPage, Box<dyn std::error::Error>> {
+        let Some(decision) = self.get_linked_decision(query).await? else {
+            return Ok(self.forbidden());
+        };
+
+        Ok(HtmlPage::Ok(make_feedback_page(
+            self.locale,
+            &decision,
+            query,
+        )))
+    }
+
+    /// Adds the feedback sent from the page, once per link however often the form is sent.
+    /// Feedback contradicting earlier feedback is refused, since the page has no way to ask
+    /// which to keep.
+    pub(crate) async fn submit(
+        &self,
+        query: &FeedbackLinkQuery,
+        needs_notification: bool,
+        severity: Option<Severity>,
+        reason: Option<String>,
+    ) -> Result<HtmlPage, Box<dyn std::error::Error>> {
+        let Some(decision) = self.get_linked_decision(query).await? else {
+            return Ok(self.forbidden());
+        };
+
+        let catalog = catalog(self.locale);
+        let done = HtmlPage::Ok(make_message_page(
+            self.locale,
+            catalog.feedback_title,
+            catalog.feedback_page_done,
+        ));
+        let id = FeedbackId::from_link(decision.id(), &query.signature);
+        if self.repo.get_feedback(&id).await?.is_some() {
+            return Ok(done);
+        }
+
+        let feedback_list = self
+            .repo
+            .list_feedback_by_log_group(decision.log_group())
+            .await?;
+        if feedback_list
+            .iter()
+            .any(|v| v.conflicts_with(decision.message(), needs_notification))
+        {
+            return Ok(HtmlPage::Conflict(make_message_page(
+                self.locale,
+                catalog.feedback_title,
+                catalog.feedback_page_conflict,
+            )));
+        }
+        let feedback = Feedback::builder()
+            .id(id)
+            .created_at(Timestamp::new())
+            .log_group(decision.log_group().to_string())
+            .message(decision.message().to_string())
+            .needs_notification(needs_notification)
+            .reason(reason)
+            .severity(severity)
+            .log_stream(decision.source().map(|v| v.log_stream().to_string()))
+            .build();
+        // A form sent twice at once is added by whichever gets there first.
+        if !self.repo.create_feedback(feedback).await? {
+            return Ok(done);
+        }
+        self.decision_repo.mark_feedback_sent(decision.id()).await?;
+        if !needs_notification {
+            self.sinks.resolve_alert(&decision).await;
+        }
+
+        Ok(done)
+    }
+
+    /// Returns the decision of the link, or `None` when the link is invalid or expired.
+    async fn get_linked_decision(
+        &self,
+        query: &FeedbackLinkQuery,
+    ) -> Result<Option<Decision>, Box<dyn std::error::Error>> {
+        if !self.feedback_links.verify(query, &Timestamp::new()) {
+            return Ok(None);
+        }
+        let decision_id = DecisionId::try_from(query.decision_id.as_str())?;
+        self.decision_repo.get_decision(&decision_id).await
+    }
+
+    fn forbidden(&self) -> HtmlPage {
+        let catalog = catalog(self.locale);
+        HtmlPage::Forbidden(make_message_page(
+            self.locale,
+            catalog.feedback_title,
+            catalog.feedback_link_invalid,
+        ))
+    }
+}
+
+/// Installs the app to workspaces through the OAuth v2 flow.
+#[derive(Clone, TypedBuilder)]
+pub(crate) struct InstallationService {
+    repo: InstallationRepositoryImpl,
+    slack_client: slack::Client,
+    oauth: OAuthSettings,
+    locale: Locale,
+}
+
+impl InstallationService {
+    /// Returns the Slack page to send the user to, and the state to bind to the browser.
+    pub(crate) fn start_install(&self) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let state = self.oauth.new_state(&Timestamp::new());
+        Ok((self.oauth.authorize_url(&state)?, state))
+    }
+
+    /// Saves the installation granted by the redirect from Slack. `code` is missing when
+    /// the user cancelled the installation.
+    pub(crate) async fn complete_install(
+        &self,
+        state: &str,
+        cookie_state: Option<&str>,
+        code: Option<&str>,
+    ) -> Result<HtmlPage, Box<dyn std::error::Error>> {
+        let catalog = catalog(self.locale);
+        if cookie_state != Some(state) || !self.oauth.verify_state(state, &Timestamp::new()) {
+            return Ok(HtmlPage::Forbidden(make_message_page(
+                self.locale,
+                catalog.install_title,
+                catalog.install_link_invalid,
+            )));
+        }
+        let Some(code) = code else {
+            return Ok(HtmlPage::Ok(make_message_page(
+                self.locale,
+                catalog.install_title,
+                catalog.install_cancelled,
+            )));
+        };
+
+        let installation = self
+            .slack_client
+            .oauth_access(
+                self.oauth.client_id(),
+                self.oauth.client_secret(),
+                code,
+                self.oauth.redirect_url(),
+            )
+            .await?;
+        let team = installation
+            .team_name()
+            .unwrap_or(installation.team_id())
+            .to_string();
+        tracing::info!("Installed to {}", installation.team_id());
+        self.repo.save_installation(installation).await?;
+
+        Ok(HtmlPage::Ok(make_message_page(
+            self.locale,
+            catalog.install_title,
+            &(catalog.installed)(&team),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::log_events::FakeLogEventRepository;
+
+    fn event(id: &str, timestamp: i64) -> LogEvent {
+        LogEvent {
+            id: Some(id.to_string()),
+            timestamp,
+            message: format!("{}\n", id),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_log_context() {
+        let repo = FakeLogEventRepository {
+            events: vec![
+                event("a", 1_000),
+                event("b", 2_000),
+                event("c", 3_000),
+                event("d", 3_000),
+                event("e", 3_000),
+                event("f", 4_000),
+                event("g", 5_000),
+            ],
+        };
+
+        let context = fetch_log_context(&repo, "group", "stream", &event("d", 3_000), 2, 100)
             .await
+            .unwrap();
+        assert_eq!(context.before(), ["b", "c"]);
+        assert_eq!(context.after(), ["e", "f"]);
+
+        let context = fetch_log_context(&repo, "group", "stream", &event("a", 1_000), 2, 100)
+            .await
+            .unwrap();
+        assert!(context.before().is_empty());
+        assert_eq!(context.after(), ["b", "c"]);
+
+        // Events in the same millisecond are left out when the log event is not found.
+        let context = fetch_log_context(&repo, "group", "stream", &event("x", 3_000), 2, 100)
+            .await
+            .unwrap();
+        assert_eq!(context.before(), ["a", "b"]);
+        assert_eq!(context.after(), ["f", "g"]);
+
+        let context = fetch_log_context(&repo, "group", "stream", &event("d", 3_000), 2, 1)
+            .await
+            .unwrap();
+        assert_eq!(context.before(), ["c"]);
+        assert!(context.after().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reply_with_judgement_refuses_other_team() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/{method}",
+            axum::routing::post(
+                move |axum::extract::Path(method): axum::extract::Path<String>,
+                      axum::Json(body): axum::Json<Value>| async move {
+                    sender.send((method, body)).unwrap();
+                    axum::Json(serde_json::json!({ "ok": true }))
+                },
+            ),
+        );
+        let base_url = crate::util::test_server(app).await;
+        // The AWS clients are never called, as the log group is refused first.
+        let dynamodb_client = aws_sdk_dynamodb::Client::from_conf(
+            aws_sdk_dynamodb::Config::builder()
+                .behavior_version(aws_sdk_dynamodb::config::BehaviorVersion::latest())
+                .build(),
+        );
+        let repo = FeedbackRepositoryImpl::builder()
+            .client(dynamodb_client.clone())
+            .table_name("feedback".to_string())
+            .build();
+        let route_repo = RouteRepositoryImpl::builder()
+            .client(dynamodb_client)
+            .routes_json(Some(
+                r#"[{"kind":"glob","pattern":"/aws/lambda/a-*","channels":["CA"],"team_id":"TA"}]"#
+                    .to_string(),
+            ))
+            .build();
+        let bedrock_client = bedrock::Client::builder()
+            .inner_client(aws_sdk_bedrockruntime::Client::from_conf(
+                aws_sdk_bedrockruntime::Config::builder()
+                    .behavior_version(aws_sdk_bedrockruntime::config::BehaviorVersion::latest())
+                    .build(),
+            ))
+            .model_id("model".to_string())
+            .top_p(1.0)
+            .temperature(0.0)
+            .build();
+        let slack_client = slack::Client::builder()
+            .inner_client(reqwest::Client::new())
+            .token("token".to_string())
+            .base_url(base_url)
+            .team_id(Some("TB".to_string()))
+            .installed(true)
+            .build();
+
+        reply_with_judgement(
+            &repo,
+            &route_repo,
+            &bedrock_client,
+            &slack_client,
+            "CB",
+            "1.0",
+            Some(("/aws/lambda/a-api", "ERROR request failed")),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let (method, body) = receiver.recv().await.unwrap();
+        assert_eq!(method, "chat.postMessage");
+        assert_eq!(body["channel"], "CB");
+        assert_eq!(body["thread_ts"], "1.0");
+        assert_eq!(
+            body["text"],
+            "/aws/lambda/a-api はこのワークスペースのロググループではありません。"
+        );
+    }
+
+    #[test]
+    fn test_is_new_since_rejection() {
+        let feedback = |created_at: i64| {
+            Feedback::builder()
+                .id(FeedbackId::new())
+                .created_at(Timestamp::from_secs(created_at))
+                .log_group("/aws/lambda/my-function".to_string())
+                .message("ERROR: connection reset by peer".to_string())
+                .needs_notification(false)
+                .reason(None)
+                .build()
+        };
+        let rule = |status: RuleStatus, feedback: &[&Feedback]| {
+            Rule::builder()
+                .id(RuleId::new())
+                .created_at(Timestamp::from_secs(1_500))
+                .log_group("/aws/lambda/my-function".to_string())
+                .rule("Ignore connection resets".to_string())
+                .needs_notification(false)
+                .source_feedback_ids(feedback.iter().map(|v| v.id().clone()).collect())
+                .status(status)
+                .build()
+        };
+        let a = feedback(1_000);
+        let b = feedback(1_100);
+        let c = feedback(2_000);
+        let rejected = rule(RuleStatus::Rejected, &[&a, &b]);
+        let rejected_at: HashMap<&FeedbackId, &Timestamp> = rejected
+            .source_feedback_ids()
+            .iter()
+            .map(|v| (v, rejected.rejected_at().unwrap()))
+            .collect();
+
+        // The rejected proposal is not made again until feedback is given after it.
+        assert!(!is_new_since_rejection(
+            &[a.clone(), b.clone()],
+            &rejected_at
+        ));
+        assert!(is_new_since_rejection(&[a, b, c.clone()], &rejected_at));
+        assert!(is_new_since_rejection(&[c], &HashMap::new()));
+        assert_eq!(rule(RuleStatus::Pending, &[]).rejected_at(), None);
+    }
+
+    #[test]
+    fn test_clusters_to_propose() {
+        let feedback = |created_at: i64, message: &str| {
+            Feedback::builder()
+                .id(FeedbackId::new())
+                .created_at(Timestamp::from_secs(created_at))
+                .log_group("/aws/lambda/my-function".to_string())
+                .message(message.to_string())
+                .needs_notification(false)
+                .reason(None)
+                .build()
+        };
+        let rule = |status: RuleStatus, feedback: &[&Feedback]| {
+            Rule::builder()
+                .id(RuleId::new())
+                .created_at(Timestamp::from_secs(1_500))
+                .log_group("/aws/lambda/my-function".to_string())
+                .rule("Ignore connection resets".to_string())
+                .needs_notification(false)
+                .source_feedback_ids(feedback.iter().map(|v| v.id().clone()).collect())
+                .status(status)
+                .build()
+        };
+        let a = feedback(1_000, "ERROR: connection reset by peer");
+        let b = feedback(1_100, "ERROR: connection reset by peer");
+        let c = feedback(2_000, "ERROR: connection reset by peer");
+        let ids = |clusters: Vec<Vec<Feedback>>| {
+            clusters
+                .iter()
+                .map(|v| v.iter().map(|v| v.id().clone()).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        // Without rules, the cluster is proposed.
+        assert_eq!(
+            ids(clusters_to_propose(vec![a.clone(), b.clone()], &[])),
+            [[a.id().clone(), b.id().clone()]]
+        );
+        // Pending and active rules cover their feedback.
+        for status in [RuleStatus::Pending, RuleStatus::Active] {
+            let rules = [rule(status, &[&a, &b])];
+            assert!(clusters_to_propose(vec![a.clone(), b.clone(), c.clone()], &rules).is_empty());
+        }
+        // A rejected rule covers its feedback until newer feedback joins the cluster.
+        let rules = [rule(RuleStatus::Rejected, &[&a, &b])];
+        assert!(clusters_to_propose(vec![a.clone(), b.clone()], &rules).is_empty());
+        assert_eq!(
+            ids(clusters_to_propose(
+                vec![a.clone(), b.clone(), c.clone()],
+                &rules
+            )),
+            [[a.id().clone(), b.id().clone(), c.id().clone()]]
+        );
     }
 }
@@ -1,37 +1,214 @@
 use aws_config::BehaviorVersion;
 use axum::Router;
-use lambda::infrastructure::{secrets, slack};
-use lambda::interface::middleware::create_auth_layer;
-use lambda::interface::routers::create_feedback_router;
+use lambda::config::{Config, Function, TaskQueueSettings};
+use lambda::infrastructure::feedback_link::FeedbackLinks;
+use lambda::infrastructure::repositories_impl::{
+    InMemoryNonceRepository, InstallationRepositoryImpl, NonceRepositoryImpl, NonceStore,
+    RouteRepositoryImpl,
+};
+use lambda::infrastructure::tasks::{LambdaInvoker, TaskQueue};
+use lambda::infrastructure::{bedrock, secrets, slack};
+use lambda::interface::middleware::{
+    create_api_auth_layer, create_auth_layer, create_task_auth_layer,
+};
+use lambda::interface::routers::{
+    create_api_router, create_command_router, create_event_router, create_feedback_page_router,
+    create_feedback_router, create_install_router,
+};
+use lambda::interface::socket_mode::SocketModeClient;
 use lambda_http::{run, tracing, Error};
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     tracing::init_default_subscriber();
 
-    let secret_id = std::env::var("SECRET_ID").expect("SECRET_ID is not set");
-    let table_name = std::env::var("TABLE_NAME").expect("TABLE_NAME is not set");
-    let slack_channel_id = std::env::var("SLACK_CHANNEL_ID").expect("SLACK_CHANNEL is not set");
-
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
+    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
     let secrets_client = secrets::Client::builder()
-        .inner(aws_sdk_secretsmanager::Client::new(&config))
+        .inner(aws_sdk_secretsmanager::Client::new(&sdk_config))
         .build();
+    let Some(config) = Config::load(Function::Collector, &sdk_config, &secrets_client).await?
+    else {
+        return Ok(());
+    };
 
-    secrets_client.load_secrets(&secret_id).await?;
-    let signing_secret = std::env::var("SIGNING_SECRET").expect("SIGNING_SECRET is not set");
-    let slack_token = std::env::var("SLACK_TOKEN").expect("SLACK_TOKEN is not set");
+    let dynamodb_client = aws_sdk_dynamodb::Client::new(&sdk_config);
+    let bedrock_client = bedrock::Client::builder()
+        .inner_client(aws_sdk_bedrockruntime::Client::new(&sdk_config))
+        .model_id(config.bedrock_model_id)
+        .top_p(config.bedrock_top_p)
+        .temperature(config.bedrock_temperature)
+        .build();
+    // Slack is reached over Socket Mode instead of HTTP when an app-level token is given.
+    let app_token = config.slack_app_token;
+    // Workspaces installed through OAuth use their own tokens, kept encrypted in a table.
+    let installations = match (config.installation_table_name, config.token_cipher) {
+        (Some(table_name), Some(cipher)) => Some(
+            InstallationRepositoryImpl::builder()
+                .client(dynamodb_client.clone())
+                .table_name(table_name)
+                .cipher(cipher)
+                .build(),
+        ),
+        _ => None,
+    };
 
     let slack_client = slack::Client::builder()
         .inner_client(reqwest::Client::new())
-        .token(slack_token)
+        .token(config.slack_token)
+        .installations(installations.clone())
+        .locales(config.locales)
+        .aws_region(sdk_config.region().map(|v| v.to_string()))
+        // Slack expects interactions to be answered within 3 seconds.
+        .deadline(Duration::from_secs(2))
         .build();
 
-    let feedback =
-        create_feedback_router(dynamodb_client, table_name, slack_client, slack_channel_id).await;
-    let auth = create_auth_layer(signing_secret);
-    let app = Router::new().nest("/feedback", feedback).layer(auth);
+    let route_repo = RouteRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.route_table_name)
+        .routes_json(config.slack_channel_routes)
+        .build();
+    // Interactions are answered before their follow-ups run, as Slack expects an answer
+    // within 3 seconds. In Lambda the function invokes itself to run them, signing them with
+    // the secret of the tasks, and elsewhere a worker in the process does.
+    let (tasks, task_worker, task_secret) = match config.task_queue {
+        TaskQueueSettings::Lambda {
+            function_name,
+            task_secret,
+        } => {
+            let invoker = LambdaInvoker::builder()
+                .client(aws_sdk_lambda::Client::new(&sdk_config))
+                .function_name(function_name)
+                .build();
+            (
+                TaskQueue::lambda(invoker, task_secret.clone()),
+                None,
+                Some(task_secret),
+            )
+        }
+        TaskQueueSettings::InProcess => {
+            let (tasks, worker) = TaskQueue::in_process();
+            (tasks, Some(worker), None)
+        }
+    };
+    // Requests already handled are remembered in a table shared by all the instances when
+    // one is configured, and otherwise only by the instance that handled them. So are the
+    // tasks already run and the events already taken.
+    let nonces = match config.nonce_table_name {
+        Some(table_name) => NonceStore::DynamoDb(
+            NonceRepositoryImpl::builder()
+                .client(dynamodb_client.clone())
+                .table_name(table_name)
+                .build(),
+        ),
+        None => {
+            // The configuration requires the table in Lambda, so this is a single process.
+            // Socket Mode requests are not signed, so there are no replays to refuse.
+            if app_token.is_none() {
+                tracing::warn!(
+                    "NONCE_TABLE_NAME is not set, so replays are only refused by this process"
+                );
+            }
+            NonceStore::InMemory(InMemoryNonceRepository::default())
+        }
+    };
+    let socket_mode_slack_client = slack_client.clone();
+    let feedback_page_slack_client = slack_client.clone();
+    let install_slack_client = slack_client.clone();
+
+    let (feedback, task_routes) = create_feedback_router(
+        dynamodb_client.clone(),
+        config.table_name.clone(),
+        config.rule_table_name,
+        config.decision_table_name.clone(),
+        config.mute_table_name.clone(),
+        route_repo.clone(),
+        bedrock_client.clone(),
+        slack_client.clone(),
+        config.sinks.clone(),
+        Some(tasks.clone()),
+        Some(nonces.clone()),
+        config.validation,
+    )
+    .await;
+    let command = create_command_router(
+        dynamodb_client.clone(),
+        config.table_name.clone(),
+        config.decision_table_name.clone(),
+        config.mute_table_name,
+        route_repo.clone(),
+        bedrock_client.clone(),
+        slack_client.clone(),
+    )
+    .await;
+    let events = create_event_router(
+        dynamodb_client.clone(),
+        config.table_name.clone(),
+        config.decision_table_name.clone(),
+        route_repo,
+        bedrock_client,
+        slack_client,
+        Some(tasks),
+        Some(nonces.clone()),
+    )
+    .await;
+    let slack_routes = Router::new()
+        .nest("/feedback", feedback)
+        .nest("/command", command)
+        .nest("/events", events);
+
+    if let Some(app_token) = app_token {
+        // Both aws-lc-rs and ring end up enabled in rustls, so it cannot pick one itself.
+        rustls::crypto::ring::default_provider()
+            .install_default()
+            .expect("Failed to install the TLS crypto provider");
+        let socket_mode = SocketModeClient::builder()
+            .slack_client(socket_mode_slack_client)
+            .app_token(app_token)
+            .router(slack_routes.clone())
+            .build();
+        if let Some(task_worker) = task_worker {
+            tokio::spawn(task_worker.run(task_routes));
+        }
+        socket_mode.run().await;
+        return Ok(());
+    }
 
+    let auth = create_auth_layer(
+        config.signing_secrets,
+        config.max_request_body_bytes,
+        Some(nonces),
+    );
+    let mut app = slack_routes.layer(auth);
+    // Tasks sent through Lambda arrive as requests to the function, like Slack's, but are
+    // checked against their own secret. Tasks run in the process are not served at all.
+    if let Some(task_worker) = task_worker {
+        tokio::spawn(task_worker.run(task_routes.clone()));
+    }
+    if let Some(task_secret) = task_secret {
+        app = app.merge(task_routes.layer(create_task_auth_layer(task_secret)));
+    }
+    if let Some(api_token) = config.api_token {
+        let api =
+            create_api_router(dynamodb_client.clone(), config.decision_table_name.clone()).await;
+        app = app.nest("/api", api.layer(create_api_auth_layer(api_token)));
+    }
+    if let Some(secret) = config.feedback_link_secret {
+        let feedback_links = FeedbackLinks::builder().secret(secret).build();
+        let feedback_page = create_feedback_page_router(
+            dynamodb_client,
+            config.table_name,
+            config.decision_table_name,
+            feedback_links,
+            feedback_page_slack_client,
+            config.sinks,
+        )
+        .await;
+        app = app.nest("/feedback-page", feedback_page);
+    }
+    if let (Some(installations), Some(oauth)) = (installations, config.oauth) {
+        let install = create_install_router(installations, oauth, install_slack_client).await;
+        app = app.nest("/slack", install);
+    }
     run(app).await
 }
@@ -0,0 +1,73 @@
+use aws_config::BehaviorVersion;
+use lambda::application::services::CompactionService;
+use lambda::config::{Config, Function};
+use lambda::infrastructure::repositories_impl::{
+    FeedbackRepositoryImpl, InstallationRepositoryImpl, RouteRepositoryImpl, RuleRepositoryImpl,
+};
+use lambda::infrastructure::{bedrock, secrets, slack};
+use lambda_runtime::{run, service_fn, tracing, Error};
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing::init_default_subscriber();
+
+    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    let secrets_client = secrets::Client::builder()
+        .inner(aws_sdk_secretsmanager::Client::new(&sdk_config))
+        .build();
+    let Some(config) = Config::load(Function::Compactor, &sdk_config, &secrets_client).await?
+    else {
+        return Ok(());
+    };
+
+    let dynamodb_client = aws_sdk_dynamodb::Client::new(&sdk_config);
+    let bedrock_client = bedrock::Client::builder()
+        .inner_client(aws_sdk_bedrockruntime::Client::new(&sdk_config))
+        .model_id(config.bedrock_model_id)
+        .top_p(config.bedrock_top_p)
+        .temperature(config.bedrock_temperature)
+        .build();
+
+    // Rules are reviewed in the channel of their log group, in the workspace of its route.
+    let installations = match (config.installation_table_name, config.token_cipher) {
+        (Some(table_name), Some(cipher)) => Some(
+            InstallationRepositoryImpl::builder()
+                .client(dynamodb_client.clone())
+                .table_name(table_name)
+                .cipher(cipher)
+                .build(),
+        ),
+        _ => None,
+    };
+
+    let slack_client = slack::Client::builder()
+        .inner_client(reqwest::Client::new())
+        .token(config.slack_token)
+        .installations(installations)
+        .locales(config.locales)
+        .build();
+
+    let repo = FeedbackRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.table_name)
+        .build();
+    let rule_repo = RuleRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.rule_table_name)
+        .build();
+    let route_repo = RouteRepositoryImpl::builder()
+        .client(dynamodb_client)
+        .table_name(config.route_table_name)
+        .routes_json(config.slack_channel_routes)
+        .build();
+    let srv = CompactionService::builder()
+        .repo(repo)
+        .rule_repo(rule_repo)
+        .route_repo(route_repo)
+        .bedrock_client(bedrock_client)
+        .slack_client(slack_client)
+        .slack_channel_id(config.slack_channel_id)
+        .build();
+
+    run(service_fn(|event| srv.compact_feedback(event))).await
+}
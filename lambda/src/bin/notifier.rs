@@ -1,6 +1,12 @@
 use aws_config::BehaviorVersion;
 use lambda::application::services::NotificationService;
-use lambda::infrastructure::repositories_impl::FeedbackRepositoryImpl;
+use lambda::config::{Config, Function};
+use lambda::infrastructure::feedback_link::FeedbackLinks;
+use lambda::infrastructure::log_events::LogEventRepositoryImpl;
+use lambda::infrastructure::repositories_impl::{
+    DecisionRepositoryImpl, FeedbackRepositoryImpl, InstallationRepositoryImpl, MuteRepositoryImpl,
+    RouteRepositoryImpl, RuleRepositoryImpl,
+};
 use lambda::infrastructure::{bedrock, secrets, slack};
 use lambda_runtime::{run, service_fn, tracing, Error};
 
@@ -8,48 +14,93 @@ use lambda_runtime::{run, service_fn, tracing, Error};
 async fn main() -> Result<(), Error> {
     tracing::init_default_subscriber();
 
-    let table_name = std::env::var("TABLE_NAME").expect("TABLE_NAME is not set");
-    let model_id = std::env::var("BEDROCK_MODEL_ID").expect("BEDROCK_MODEL_ID is not set");
-    let top_p: f32 = std::env::var("BEDROCK_TOP_P")
-        .expect("BEDROCK_TOP_P is not set")
-        .parse()
-        .expect("BEDROCK_TOP_P is not a valid float");
-    let temperature: f32 = std::env::var("BEDROCK_TEMPERATURE")
-        .expect("BEDROCK_TEMPERATURE is not set")
-        .parse()
-        .expect("BEDROCK_TEMPERATURE is not a valid float");
-    let slack_channel_id = std::env::var("SLACK_CHANNEL_ID").expect("SLACK_CHANNEL is not set");
-    let secret_id = std::env::var("SECRET_ID").expect("SECRET_ID is not set");
-
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let dynamodb_client = aws_sdk_dynamodb::Client::new(&config);
-    let bedrock_client = bedrock::Client::builder()
-        .inner_client(aws_sdk_bedrockruntime::Client::new(&config))
-        .model_id(model_id)
-        .top_p(top_p)
-        .temperature(temperature)
-        .build();
+    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
     let secrets_client = secrets::Client::builder()
-        .inner(aws_sdk_secretsmanager::Client::new(&config))
+        .inner(aws_sdk_secretsmanager::Client::new(&sdk_config))
         .build();
+    let Some(config) = Config::load(Function::Notifier, &sdk_config, &secrets_client).await? else {
+        return Ok(());
+    };
 
-    secrets_client.load_secrets(&secret_id).await?;
-    let slack_token = std::env::var("SLACK_TOKEN").expect("SLACK_TOKEN is not set");
+    let dynamodb_client = aws_sdk_dynamodb::Client::new(&sdk_config);
+    let bedrock_client = bedrock::Client::builder()
+        .inner_client(aws_sdk_bedrockruntime::Client::new(&sdk_config))
+        .model_id(config.bedrock_model_id)
+        .top_p(config.bedrock_top_p)
+        .temperature(config.bedrock_temperature)
+        .build();
+    // Routes with a `team_id` post with the token of the workspace installed through OAuth.
+    let installations = match (config.installation_table_name, config.token_cipher) {
+        (Some(table_name), Some(cipher)) => Some(
+            InstallationRepositoryImpl::builder()
+                .client(dynamodb_client.clone())
+                .table_name(table_name)
+                .cipher(cipher)
+                .build(),
+        ),
+        _ => None,
+    };
+    let feedback_links = match (config.feedback_link_secret, config.feedback_page_url) {
+        (Some(secret), Some(base_url)) => Some(
+            FeedbackLinks::builder()
+                .secret(secret)
+                .base_url(base_url)
+                .build(),
+        ),
+        _ => None,
+    };
 
     let slack_client = slack::Client::builder()
         .inner_client(reqwest::Client::new())
-        .token(slack_token)
+        .token(config.slack_token)
+        .installations(installations)
+        .locales(config.locales)
+        .aws_region(sdk_config.region().map(|v| v.to_string()))
         .build();
 
     let repo = FeedbackRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.table_name)
+        .build();
+    let rule_repo = RuleRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.rule_table_name)
+        .build();
+    let decision_repo = DecisionRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.decision_table_name)
+        .build();
+    let mute_repo = MuteRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(config.mute_table_name)
+        .build();
+    let route_repo = RouteRepositoryImpl::builder()
         .client(dynamodb_client)
-        .table_name(table_name)
+        .table_name(config.route_table_name)
+        .routes_json(config.slack_channel_routes)
         .build();
+    let log_event_repo = (config.log_context_lines > 0).then(|| {
+        LogEventRepositoryImpl::builder()
+            .client(aws_sdk_cloudwatchlogs::Client::new(&sdk_config))
+            .build()
+    });
     let srv = NotificationService::builder()
         .repo(repo)
+        .rule_repo(rule_repo)
+        .route_repo(route_repo)
+        .decision_repo(decision_repo)
+        .mute_repo(mute_repo)
         .bedrock_client(bedrock_client)
         .slack_client(slack_client)
-        .slack_channel_id(slack_channel_id)
+        .slack_channel_id(config.slack_channel_id)
+        .oncall_user_group(config.slack_oncall_user_group_id)
+        .compaction_threshold(config.compaction_threshold)
+        .explain_judgement(config.bedrock_explain_judgement)
+        .log_event_repo(log_event_repo)
+        .log_context_lines(config.log_context_lines)
+        .log_context_max_bytes(config.log_context_max_bytes)
+        .sinks(config.sinks)
+        .feedback_links(feedback_links)
         .build();
 
     run(service_fn(|event| srv.slack_notification(event))).await
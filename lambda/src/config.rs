@@ -0,0 +1,741 @@
+//! Settings of the functions, read in layers that each override the ones before: the
+//! defaults, a TOML file named by `CONFIG_FILE`, the environment, and the Secrets Manager
+//! secret named by `SECRET_ID`.
+//!
+//! Settings are named after their environment variables. The file and the secret may also
+//! use the names in lower case, and the file may write the settings taking JSON, such as
+//! `ALERT_SINKS`, as TOML tables and arrays.
+
+use crate::application::services::DEFAULT_LOG_CONTEXT_MAX_BYTES;
+use crate::domain::entities::Route;
+use crate::infrastructure::i18n::LocaleSettings;
+use crate::infrastructure::oauth::OAuthSettings;
+use crate::infrastructure::secrets;
+use crate::infrastructure::sinks::AlertSinks;
+use crate::infrastructure::token_cipher::TokenCipher;
+use crate::infrastructure::validation::ValidationRules;
+use crate::interface::middleware::DEFAULT_MAX_BODY_BYTES;
+use aws_config::SdkConfig;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Every setting, in the order they are printed.
+const KEYS: [&str; 36] = [
+    "SECRET_ID",
+    "TABLE_NAME",
+    "RULE_TABLE_NAME",
+    "DECISION_TABLE_NAME",
+    "MUTE_TABLE_NAME",
+    "ROUTE_TABLE_NAME",
+    "INSTALLATION_TABLE_NAME",
+    "NONCE_TABLE_NAME",
+    "BEDROCK_MODEL_ID",
+    "BEDROCK_TOP_P",
+    "BEDROCK_TEMPERATURE",
+    "BEDROCK_EXPLAIN_JUDGEMENT",
+    "SLACK_TOKEN",
+    "SLACK_APP_TOKEN",
+    "SLACK_CHANNEL_ID",
+    "SLACK_CHANNEL_ROUTES",
+    "SLACK_ONCALL_USER_GROUP_ID",
+    "SLACK_LOCALE",
+    "SLACK_CHANNEL_LOCALES",
+    "SLACK_CLIENT_ID",
+    "SLACK_CLIENT_SECRET",
+    "SLACK_REDIRECT_URL",
+    "SIGNING_SECRET",
+    "MAX_REQUEST_BODY_BYTES",
+    "TASK_QUEUE",
+    "TASK_SECRET",
+    "AWS_LAMBDA_FUNCTION_NAME",
+    "API_TOKEN",
+    "TOKEN_ENCRYPTION_KEY",
+    "FEEDBACK_LINK_SECRET",
+    "FEEDBACK_PAGE_URL",
+    "FEEDBACK_COMPACTION_THRESHOLD",
+    "FEEDBACK_VALIDATION",
+    "LOG_CONTEXT_LINES",
+    "LOG_CONTEXT_MAX_BYTES",
+    "ALERT_SINKS",
+];
+
+/// Settings hidden when printed. The sinks may carry keys and passwords.
+const SECRET_KEYS: [&str; 9] = [
+    "SLACK_TOKEN",
+    "SLACK_APP_TOKEN",
+    "SLACK_CLIENT_SECRET",
+    "SIGNING_SECRET",
+    "TASK_SECRET",
+    "API_TOKEN",
+    "TOKEN_ENCRYPTION_KEY",
+    "FEEDBACK_LINK_SECRET",
+    "ALERT_SINKS",
+];
+
+/// Argument making a function print its settings and exit.
+const PRINT_CONFIG: &str = "--print-config";
+
+/// The function the settings are read for, which decides the ones required.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Notifier,
+    Collector,
+    Compactor,
+}
+
+/// Layer a setting was taken from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Default,
+    File,
+    Env,
+    Secret,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Default => "default",
+            Self::File => "file",
+            Self::Env => "env",
+            Self::Secret => "secret",
+        })
+    }
+}
+
+/// The raw values of the settings, with the layer each was taken from.
+#[derive(Debug, Default)]
+pub struct Settings {
+    values: BTreeMap<&'static str, (String, Source)>,
+    /// Problems of the layers, reported with those of the values.
+    errors: Vec<String>,
+}
+
+impl Settings {
+    /// Reads every layer, fetching the secret only when `SECRET_ID` is set by the others.
+    pub async fn load(secrets_client: &secrets::Client) -> Result<Self, ConfigError> {
+        let mut settings = Self::defaults();
+        if let Some(path) = std::env::var("CONFIG_FILE").ok().filter(|v| !v.is_empty()) {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| ConfigError::new(format!("Failed to read {}: {}", path, e)))?;
+            settings.add_file(&text)?;
+        }
+        settings.add_env(std::env::vars());
+        if let Some(secret_id) = settings.get("SECRET_ID").map(String::from) {
+            let values = secrets_client.get_secrets(&secret_id).await.map_err(|e| {
+                ConfigError::new(format!("Failed to read secret {}: {}", secret_id, e))
+            })?;
+            settings.add_secret(values);
+        }
+        Ok(settings)
+    }
+
+    fn defaults() -> Self {
+        let mut settings = Self::default();
+        for (key, value) in [
+            ("BEDROCK_TOP_P", "0.9".to_string()),
+            ("BEDROCK_TEMPERATURE", "0.7".to_string()),
+            ("BEDROCK_EXPLAIN_JUDGEMENT", "false".to_string()),
+            ("MAX_REQUEST_BODY_BYTES", DEFAULT_MAX_BODY_BYTES.to_string()),
+            ("TASK_QUEUE", "lambda".to_string()),
+            ("LOG_CONTEXT_LINES", "0".to_string()),
+            (
+                "LOG_CONTEXT_MAX_BYTES",
+                DEFAULT_LOG_CONTEXT_MAX_BYTES.to_string(),
+            ),
+        ] {
+            settings.set(key, value, Source::Default);
+        }
+        settings
+    }
+
+    /// Takes the settings of a TOML file. Settings the file does not know are reported.
+    fn add_file(&mut self, text: &str) -> Result<(), ConfigError> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e| ConfigError::new(format!("Invalid config file: {}", e)))?;
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(v) => v,
+                toml::Value::Table(_) | toml::Value::Array(_) => {
+                    match serde_json::to_string(&value) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            self.errors.push(format!("{} is invalid: {}", key, e));
+                            continue;
+                        }
+                    }
+                }
+                v => v.to_string(),
+            };
+            if !self.set(&key, value, Source::File) {
+                self.errors
+                    .push(format!("Unknown setting in the config file: {}", key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes the variables named after settings, in upper case only.
+    fn add_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) {
+        for (key, value) in vars {
+            if KEYS.contains(&key.as_str()) {
+                self.set(&key, value, Source::Env);
+            }
+        }
+    }
+
+    /// Takes the values of the secret. Other values the secret holds are left alone.
+    fn add_secret(&mut self, values: HashMap<String, String>) {
+        for (key, value) in values {
+            self.set(&key, value, Source::Secret);
+        }
+    }
+
+    /// Sets a value over the one of the layers before, and returns whether the setting
+    /// exists. Empty values count as unset, so they do not override.
+    fn set(&mut self, key: &str, value: String, source: Source) -> bool {
+        let Some(key) = KEYS.iter().find(|v| v.eq_ignore_ascii_case(key)) else {
+            return false;
+        };
+        if !value.trim().is_empty() {
+            self.values.insert(key, (value, source));
+        }
+        true
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|(v, _)| v.as_str())
+    }
+
+    /// Returns the settings as a TOML file naming the layer of each, with the secrets
+    /// hidden.
+    pub fn redacted(&self) -> String {
+        KEYS.iter()
+            .filter_map(|key| {
+                let (value, source) = self.values.get(key)?;
+                let value = if SECRET_KEYS.contains(key) {
+                    "<redacted>"
+                } else {
+                    value
+                };
+                Some(format!(
+                    "{} = {} # {}\n",
+                    key.to_lowercase(),
+                    toml::Value::String(value.to_string()),
+                    source
+                ))
+            })
+            .collect()
+    }
+}
+
+/// How the collector runs the follow-ups of interactions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskTransport {
+    /// By invoking the function itself asynchronously.
+    Lambda,
+    /// By a worker in the process, which Socket Mode always uses.
+    InProcess,
+}
+
+impl FromStr for TaskTransport {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "lambda" => Ok(Self::Lambda),
+            "in_process" => Ok(Self::InProcess),
+            _ => Err(format!("expected lambda or in_process, got {}", value)),
+        }
+    }
+}
+
+/// How the collector runs the follow-ups of interactions, with what the way needs.
+#[derive(Clone)]
+pub enum TaskQueueSettings {
+    /// By invoking the function itself asynchronously, with the tasks signed by the secret.
+    Lambda {
+        function_name: String,
+        task_secret: String,
+    },
+    /// By a worker in the process, which is also what the functions other than the
+    /// collector get, as they have no tasks.
+    InProcess,
+}
+
+/// The settings of a function, checked. Required text settings that the function does not
+/// use are left empty.
+pub struct Config {
+    pub table_name: String,
+    pub rule_table_name: String,
+    pub decision_table_name: String,
+    pub mute_table_name: String,
+    pub route_table_name: Option<String>,
+    /// Table of the workspaces installed through OAuth, used along with `token_cipher`.
+    pub installation_table_name: Option<String>,
+    pub nonce_table_name: Option<String>,
+    pub bedrock_model_id: String,
+    pub bedrock_top_p: f32,
+    pub bedrock_temperature: f32,
+    pub bedrock_explain_judgement: bool,
+    /// Empty when every workspace is installed through OAuth.
+    pub slack_token: String,
+    /// Token switching the collector to Socket Mode.
+    pub slack_app_token: Option<String>,
+    pub slack_channel_id: String,
+    pub slack_channel_routes: Option<String>,
+    pub slack_oncall_user_group_id: Option<String>,
+    pub locales: LocaleSettings,
+    pub oauth: Option<OAuthSettings>,
+    /// Secrets of the Slack requests.
+    pub signing_secrets: Vec<String>,
+    pub max_request_body_bytes: usize,
+    pub task_queue: TaskQueueSettings,
+    pub api_token: Option<String>,
+    pub token_cipher: Option<TokenCipher>,
+    pub feedback_link_secret: Option<String>,
+    pub feedback_page_url: Option<String>,
+    pub compaction_threshold: Option<usize>,
+    pub validation: ValidationRules,
+    pub log_context_lines: usize,
+    pub log_context_max_bytes: usize,
+    pub sinks: AlertSinks,
+}
+
+impl Config {
+    /// Reads and checks the settings of the function. When the function is run with
+    /// `--print-config`, prints them with the secrets hidden instead, and returns `None`
+    /// once they are checked.
+    pub async fn load(
+        function: Function,
+        sdk_config: &SdkConfig,
+        secrets_client: &secrets::Client,
+    ) -> Result<Option<Self>, ConfigError> {
+        let settings = Settings::load(secrets_client).await?;
+        if std::env::args().any(|v| v == PRINT_CONFIG) {
+            print!("{}", settings.redacted());
+            Self::new(&settings, function, sdk_config)?;
+            return Ok(None);
+        }
+        Self::new(&settings, function, sdk_config).map(Some)
+    }
+
+    /// Checks the settings, and the AWS configuration where the settings need it,
+    /// reporting every problem at once.
+    pub fn new(
+        settings: &Settings,
+        function: Function,
+        sdk_config: &SdkConfig,
+    ) -> Result<Self, ConfigError> {
+        let mut reader = Reader {
+            settings,
+            errors: settings.errors.clone(),
+        };
+        let is_collector = function == Function::Collector;
+
+        let installation_table_name = reader.optional("INSTALLATION_TABLE_NAME");
+        let token_cipher = reader.with("TOKEN_ENCRYPTION_KEY", TokenCipher::new);
+        // Workspaces installed through OAuth use their own tokens.
+        let installs = installation_table_name.is_some() && token_cipher.is_some();
+        let slack_app_token = reader.optional("SLACK_APP_TOKEN");
+        let signing_secrets: Vec<String> = reader
+            .optional("SIGNING_SECRET")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(String::from)
+            .collect();
+        let task_transport = reader.parse("TASK_QUEUE").unwrap_or(TaskTransport::Lambda);
+        if is_collector && slack_app_token.is_none() && signing_secrets.is_empty() {
+            reader.missing("SIGNING_SECRET");
+        }
+        // Instances of a Lambda function do not share their memory, so only a table lets
+        // one of them refuse a request another has handled.
+        let in_lambda = reader.optional("AWS_LAMBDA_FUNCTION_NAME").is_some();
+        let nonce_table_name = reader.optional("NONCE_TABLE_NAME");
+        if is_collector && slack_app_token.is_none() && in_lambda && nonce_table_name.is_none() {
+            reader.missing("NONCE_TABLE_NAME");
+        }
+        // Socket Mode always runs the tasks in the process.
+        let task_queue =
+            if is_collector && slack_app_token.is_none() && task_transport == TaskTransport::Lambda
+            {
+                let function_name = reader.required("AWS_LAMBDA_FUNCTION_NAME", true);
+                let task_secret = reader.required("TASK_SECRET", true);
+                // The function is invoked with the Lambda client of the shared SDK settings.
+                if sdk_config.region().is_none() {
+                    reader
+                        .errors
+                        .push("AWS region is not configured".to_string());
+                }
+                if sdk_config.credentials_provider().is_none() {
+                    reader
+                        .errors
+                        .push("AWS credentials are not configured".to_string());
+                }
+                TaskQueueSettings::Lambda {
+                    function_name,
+                    task_secret,
+                }
+            } else {
+                TaskQueueSettings::InProcess
+            };
+
+        let oauth = match (
+            reader.optional("SLACK_CLIENT_ID"),
+            reader.optional("SLACK_CLIENT_SECRET"),
+            reader.optional("SLACK_REDIRECT_URL"),
+        ) {
+            (Some(client_id), Some(client_secret), Some(redirect_url)) => Some(
+                OAuthSettings::builder()
+                    .client_id(client_id)
+                    .client_secret(client_secret)
+                    .redirect_url(redirect_url)
+                    .build(),
+            ),
+            (None, None, None) => None,
+            _ => {
+                if is_collector {
+                    reader.errors.push(
+                        "SLACK_CLIENT_ID, SLACK_CLIENT_SECRET and SLACK_REDIRECT_URL must be \
+                         set together"
+                            .to_string(),
+                    );
+                }
+                None
+            }
+        };
+
+        let bedrock_top_p = reader.fraction("BEDROCK_TOP_P");
+        let bedrock_temperature = reader.fraction("BEDROCK_TEMPERATURE");
+        let slack_channel_routes = reader.optional("SLACK_CHANNEL_ROUTES");
+        let routes = match slack_channel_routes
+            .as_deref()
+            .map(serde_json::from_str::<Vec<Route>>)
+        {
+            Some(Ok(routes)) => routes,
+            Some(Err(e)) => {
+                reader.invalid("SLACK_CHANNEL_ROUTES", e);
+                vec![]
+            }
+            None => vec![],
+        };
+        let slack_channel_id = reader.required("SLACK_CHANNEL_ID", !is_collector);
+        // The default channel and the routes naming no workspace are posted to with the
+        // configured token, even when other workspaces are installed.
+        let needs_token = !installs
+            || !slack_channel_id.is_empty()
+            || routes.iter().any(|v| v.team_id().is_none());
+        let locales = match LocaleSettings::new(
+            reader.optional("SLACK_LOCALE").as_deref(),
+            reader.optional("SLACK_CHANNEL_LOCALES").as_deref(),
+        ) {
+            Ok(locales) => locales,
+            Err(e) => {
+                reader.invalid("SLACK_LOCALE or SLACK_CHANNEL_LOCALES", e);
+                LocaleSettings::default()
+            }
+        };
+
+        let config = Self {
+            table_name: reader.required("TABLE_NAME", true),
+            rule_table_name: reader.required("RULE_TABLE_NAME", true),
+            decision_table_name: reader
+                .required("DECISION_TABLE_NAME", function != Function::Compactor),
+            mute_table_name: reader.required("MUTE_TABLE_NAME", function != Function::Compactor),
+            route_table_name: reader.optional("ROUTE_TABLE_NAME"),
+            installation_table_name,
+            nonce_table_name,
+            bedrock_model_id: reader.required("BEDROCK_MODEL_ID", true),
+            bedrock_top_p,
+            bedrock_temperature,
+            bedrock_explain_judgement: reader
+                .parse("BEDROCK_EXPLAIN_JUDGEMENT")
+                .unwrap_or_default(),
+            slack_token: reader.required("SLACK_TOKEN", needs_token),
+            slack_app_token,
+            slack_channel_id,
+            slack_channel_routes,
+            slack_oncall_user_group_id: reader.optional("SLACK_ONCALL_USER_GROUP_ID"),
+            locales,
+            oauth,
+            signing_secrets,
+            max_request_body_bytes: reader
+                .parse("MAX_REQUEST_BODY_BYTES")
+                .unwrap_or(DEFAULT_MAX_BODY_BYTES),
+            task_queue,
+            api_token: reader.optional("API_TOKEN"),
+            token_cipher,
+            feedback_link_secret: reader.optional("FEEDBACK_LINK_SECRET"),
+            feedback_page_url: reader.optional("FEEDBACK_PAGE_URL"),
+            compaction_threshold: reader.parse("FEEDBACK_COMPACTION_THRESHOLD"),
+            validation: reader
+                .with("FEEDBACK_VALIDATION", |v| ValidationRules::new(Some(v)))
+                .unwrap_or_default(),
+            log_context_lines: reader.parse("LOG_CONTEXT_LINES").unwrap_or_default(),
+            log_context_max_bytes: reader
+                .parse("LOG_CONTEXT_MAX_BYTES")
+                .unwrap_or(DEFAULT_LOG_CONTEXT_MAX_BYTES),
+            sinks: reader
+                .with("ALERT_SINKS", |v| AlertSinks::new(Some(v)))
+                .unwrap_or_default(),
+        };
+
+        if reader.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(reader.errors))
+        }
+    }
+}
+
+/// Reads the values of the settings, collecting the problems instead of stopping at the
+/// first.
+struct Reader<'a> {
+    settings: &'a Settings,
+    errors: Vec<String>,
+}
+
+impl Reader<'_> {
+    fn optional(&self, key: &str) -> Option<String> {
+        self.settings.get(key).map(String::from)
+    }
+
+    fn required(&mut self, key: &str, needed: bool) -> String {
+        let value = self.optional(key);
+        if needed && value.is_none() {
+            self.missing(key);
+        }
+        value.unwrap_or_default()
+    }
+
+    fn parse<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.settings.get(key)?;
+        match value.trim().parse() {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.invalid(key, e);
+                None
+            }
+        }
+    }
+
+    /// Parses a value between 0 and 1, such as the sampling parameters of the model.
+    fn fraction(&mut self, key: &str) -> f32 {
+        match self.parse::<f32>(key) {
+            Some(v) if (0.0..=1.0).contains(&v) => v,
+            Some(v) => {
+                self.invalid(key, format!("{} is not between 0 and 1", v));
+                0.0
+            }
+            None => 0.0,
+        }
+    }
+
+    fn with<T>(
+        &mut self,
+        key: &str,
+        parse: impl FnOnce(&str) -> Result<T, Box<dyn std::error::Error>>,
+    ) -> Option<T> {
+        let value = self.settings.get(key)?;
+        match parse(value) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.invalid(key, e);
+                None
+            }
+        }
+    }
+
+    fn missing(&mut self, key: &str) {
+        self.errors.push(format!("{} is not set", key));
+    }
+
+    fn invalid(&mut self, key: &str, error: impl fmt::Display) {
+        self.errors.push(format!("{} is invalid: {}", key, error));
+    }
+}
+
+/// Every problem found in the settings.
+pub struct ConfigError(Vec<String>);
+
+impl ConfigError {
+    fn new(message: String) -> Self {
+        Self(vec![message])
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+// Shown as is when returned from `main`, which prints errors with `Debug`.
+impl fmt::Debug for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine;
+
+    fn settings(file: &str, env: &[(&str, &str)], secret: &[(&str, &str)]) -> Settings {
+        let mut settings = Settings::defaults();
+        settings.add_file(file).unwrap();
+        settings.add_env(env.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        settings.add_secret(
+            secret
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        settings
+    }
+
+    #[test]
+    fn test_layers() {
+        let settings = settings(
+            r#"
+            table_name = "from_file"
+            rule_table_name = "rules"
+            bedrock_top_p = 0.5
+            alert_sinks = [{ name = "ops", kind = "teams", url = "https://example.com/teams" }]
+            "#,
+            &[
+                ("TABLE_NAME", "from_env"),
+                ("DECISION_TABLE_NAME", "decisions"),
+                ("MUTE_TABLE_NAME", "mutes"),
+                ("BEDROCK_MODEL_ID", "model"),
+                ("SLACK_CHANNEL_ID", "C0123"),
+                ("SLACK_TOKEN", "xoxb-env"),
+                ("HOME", "/root"),
+            ],
+            &[("SLACK_TOKEN", "xoxb-secret"), ("OTHER", "ignored")],
+        );
+        let config =
+            Config::new(&settings, Function::Notifier, &SdkConfig::builder().build()).unwrap();
+
+        assert_eq!(config.table_name, "from_env");
+        assert_eq!(config.rule_table_name, "rules");
+        assert_eq!(config.bedrock_top_p, 0.5);
+        assert_eq!(config.bedrock_temperature, 0.7);
+        assert_eq!(config.slack_token, "xoxb-secret");
+        assert_eq!(config.log_context_max_bytes, DEFAULT_LOG_CONTEXT_MAX_BYTES);
+
+        let printed = settings.redacted();
+        assert!(printed.contains("table_name = \"from_env\" # env\n"));
+        assert!(printed.contains("bedrock_top_p = \"0.5\" # file\n"));
+        assert!(printed.contains("bedrock_temperature = \"0.7\" # default\n"));
+        assert!(printed.contains("slack_token = \"<redacted>\" # secret\n"));
+        assert!(printed.contains("alert_sinks = \"<redacted>\" # file\n"));
+        assert!(!printed.contains("xoxb"));
+        assert!(!printed.contains("example.com"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let settings = settings(
+            "tabel_name = \"feedback\"",
+            &[
+                ("BEDROCK_TOP_P", "high"),
+                ("BEDROCK_TEMPERATURE", "1.5"),
+                ("MAX_REQUEST_BODY_BYTES", "1MB"),
+                ("TASK_QUEUE", "sqs"),
+                ("SLACK_CLIENT_ID", "123.456"),
+                ("AWS_LAMBDA_FUNCTION_NAME", "collector"),
+            ],
+            &[],
+        );
+        let errors = match Config::new(
+            &settings,
+            Function::Collector,
+            &SdkConfig::builder().build(),
+        ) {
+            Ok(_) => panic!("settings should be invalid"),
+            Err(e) => e.0,
+        };
+
+        for expected in [
+            "Unknown setting in the config file: tabel_name",
+            "TABLE_NAME is not set",
+            "RULE_TABLE_NAME is not set",
+            "SLACK_TOKEN is not set",
+            "SIGNING_SECRET is not set",
+            "TASK_SECRET is not set",
+            "NONCE_TABLE_NAME is not set",
+            "AWS region is not configured",
+            "AWS credentials are not configured",
+            "SLACK_CLIENT_ID, SLACK_CLIENT_SECRET and SLACK_REDIRECT_URL must be set together",
+            "BEDROCK_TEMPERATURE is invalid: 1.5 is not between 0 and 1",
+        ] {
+            assert!(errors.iter().any(|v| v == expected), "{}", expected);
+        }
+        for key in ["BEDROCK_TOP_P", "MAX_REQUEST_BODY_BYTES", "TASK_QUEUE"] {
+            assert!(
+                errors
+                    .iter()
+                    .any(|v| v.starts_with(&format!("{} is invalid", key))),
+                "{}",
+                key
+            );
+        }
+        // The channel is only required by the functions posting alerts.
+        assert!(!errors.iter().any(|v| v.starts_with("SLACK_CHANNEL_ID")));
+    }
+
+    #[test]
+    fn test_token_with_installations() {
+        let key = BASE64_STANDARD.encode([7u8; 32]);
+        let errors = |env: &[(&str, &str)]| {
+            let mut vars = vec![
+                ("TABLE_NAME", "feedback"),
+                ("RULE_TABLE_NAME", "rules"),
+                ("BEDROCK_MODEL_ID", "model"),
+                ("INSTALLATION_TABLE_NAME", "installations"),
+                ("TOKEN_ENCRYPTION_KEY", key.as_str()),
+            ];
+            vars.extend_from_slice(env);
+            match Config::new(
+                &settings("", &vars, &[]),
+                Function::Compactor,
+                &SdkConfig::builder().build(),
+            ) {
+                Ok(_) => vec![],
+                Err(e) => e.0,
+            }
+        };
+        let missing = "SLACK_TOKEN is not set".to_string();
+
+        // Installed workspaces use their own tokens.
+        assert!(!errors(&[(
+            "SLACK_CHANNEL_ROUTES",
+            r#"[{"kind":"glob","pattern":"*","channels":["C1"],"team_id":"T1"}]"#
+        )])
+        .contains(&missing));
+        // The default channel and routes naming no workspace need the configured token.
+        assert!(errors(&[("SLACK_CHANNEL_ID", "C0123")]).contains(&missing));
+        assert!(errors(&[(
+            "SLACK_CHANNEL_ROUTES",
+            r#"[{"kind":"glob","pattern":"*","channels":["C1"]}]"#
+        )])
+        .contains(&missing));
+    }
+}
@@ -1,4 +1,9 @@
-use crate::domain::value_objects::{FeedbackId, Timestamp};
+use crate::domain::value_objects::{
+    DecisionId, FeedbackId, Fingerprint, LogGroupPattern, MuteId, RuleId, RuleStatus, Severity,
+    Timestamp,
+};
+use crate::util::similarity;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
@@ -10,13 +15,39 @@ pub(crate) struct Feedback {
     message: String,
     needs_notification: bool,
     reason: Option<String>,
+    /// Severity the operator considers appropriate, when corrected in the feedback.
+    #[serde(default)]
+    #[builder(default)]
+    severity: Option<Severity>,
+    /// Also the key of the user index, so it is left out rather than written as null.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    retired: bool,
+    /// Log stream of the alert the feedback was given on, when known.
+    #[serde(default)]
+    #[builder(default)]
+    log_stream: Option<String>,
 }
 
+/// Messages at least this similar are treated as the same kind of log.
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+
 impl Feedback {
+    pub(crate) fn id(&self) -> &FeedbackId {
+        &self.id
+    }
+
     pub(crate) fn created_at(&self) -> &Timestamp {
         &self.created_at
     }
 
+    pub(crate) fn log_group(&self) -> &str {
+        &self.log_group
+    }
+
     pub(crate) fn message(&self) -> &str {
         &self.message
     }
@@ -28,6 +59,560 @@ impl Feedback {
     pub(crate) fn reason(&self) -> Option<&String> {
         self.reason.as_ref()
     }
+
+    pub(crate) fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    pub(crate) fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    pub(crate) fn retired(&self) -> bool {
+        self.retired
+    }
+
+    /// Returns whether the user may edit or delete this feedback.
+    pub(crate) fn is_owned_by(&self, user_id: &str) -> bool {
+        self.user_id.as_deref() == Some(user_id)
+    }
+
+    /// Replaces the verdict of this feedback, keeping the message it was given for.
+    pub(crate) fn revise(
+        &mut self,
+        needs_notification: bool,
+        severity: Option<Severity>,
+        reason: Option<String>,
+    ) {
+        self.needs_notification = needs_notification;
+        self.severity = severity;
+        self.reason = reason;
+    }
+
+    pub(crate) fn is_similar_to(&self, message: &str) -> bool {
+        similarity(&self.message, message) >= SIMILARITY_THRESHOLD
+    }
+
+    /// Returns whether this feedback is active and gives the opposite verdict for a similar message.
+    pub(crate) fn conflicts_with(&self, message: &str, needs_notification: bool) -> bool {
+        !self.retired
+            && self.needs_notification != needs_notification
+            && self.is_similar_to(message)
+    }
+}
+
+/// Returns up to `limit` active feedback entries, from the one given for the message most
+/// similar to `message`.
+pub(crate) fn most_similar_feedback<'a>(
+    feedback: &'a [Feedback],
+    message: &str,
+    limit: usize,
+) -> Vec<&'a Feedback> {
+    let mut scored: Vec<(f64, &Feedback)> = feedback
+        .iter()
+        .filter(|v| !v.retired)
+        .map(|v| (similarity(&v.message, message), v))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(limit).map(|(_, v)| v).collect()
+}
+
+/// A rule distilled by the model from a cluster of similar feedback.
+#[derive(Clone, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct Rule {
+    id: RuleId,
+    created_at: Timestamp,
+    log_group: String,
+    rule: String,
+    needs_notification: bool,
+    source_feedback_ids: Vec<FeedbackId>,
+    status: RuleStatus,
+    #[serde(default)]
+    #[builder(default)]
+    reviewed_by: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    reviewed_at: Option<Timestamp>,
+}
+
+impl Rule {
+    pub(crate) fn id(&self) -> &RuleId {
+        &self.id
+    }
+
+    pub(crate) fn created_at(&self) -> &Timestamp {
+        &self.created_at
+    }
+
+    pub(crate) fn log_group(&self) -> &str {
+        &self.log_group
+    }
+
+    pub(crate) fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub(crate) fn needs_notification(&self) -> bool {
+        self.needs_notification
+    }
+
+    pub(crate) fn source_feedback_ids(&self) -> &[FeedbackId] {
+        &self.source_feedback_ids
+    }
+
+    pub(crate) fn status(&self) -> RuleStatus {
+        self.status
+    }
+
+    /// Returns when the rule was rejected, or `None` if it was not. Rules rejected before
+    /// the time of the review was recorded are taken as rejected when they were proposed.
+    pub(crate) fn rejected_at(&self) -> Option<&Timestamp> {
+        (self.status == RuleStatus::Rejected)
+            .then(|| self.reviewed_at.as_ref().unwrap_or(&self.created_at))
+    }
+}
+
+/// Where a log event came from, as delivered by the CloudWatch Logs subscription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct LogSource {
+    log_stream: String,
+    /// Account the log group belongs to.
+    owner: String,
+    subscription_filters: Vec<String>,
+    event_id: String,
+    /// When the event was logged.
+    event_time: Timestamp,
+}
+
+impl LogSource {
+    pub(crate) fn log_stream(&self) -> &str {
+        &self.log_stream
+    }
+
+    pub(crate) fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub(crate) fn event_time(&self) -> &Timestamp {
+        &self.event_time
+    }
+}
+
+/// A log event read back from CloudWatch Logs.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LogEvent {
+    /// Absent on events read from the end of a stream, which CloudWatch Logs returns
+    /// without IDs.
+    pub(crate) id: Option<String>,
+    /// When the event was logged, in milliseconds.
+    pub(crate) timestamp: i64,
+    pub(crate) message: String,
+}
+
+/// The events logged just before and after an alerted event in the same log stream, oldest
+/// first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LogContext {
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+impl LogContext {
+    /// Keeps the events closest to the alerted one whose messages fit in `max_bytes` in
+    /// total.
+    pub(crate) fn new(before: Vec<String>, after: Vec<String>, max_bytes: usize) -> Self {
+        let mut before = before.into_iter().rev().peekable();
+        let mut after = after.into_iter().peekable();
+        let mut context = Self::default();
+        let mut remaining = max_bytes;
+        loop {
+            let previous = before.next_if(|v| v.len() <= remaining);
+            if let Some(line) = &previous {
+                remaining -= line.len();
+            }
+            let next = after.next_if(|v| v.len() <= remaining);
+            if let Some(line) = &next {
+                remaining -= line.len();
+            }
+            if previous.is_none() && next.is_none() {
+                break;
+            }
+            context.before.extend(previous);
+            context.after.extend(next);
+        }
+        context.before.reverse();
+        context
+    }
+
+    pub(crate) fn before(&self) -> &[String] {
+        &self.before
+    }
+
+    pub(crate) fn after(&self) -> &[String] {
+        &self.after
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.after.is_empty()
+    }
+}
+
+/// The judgement made by the model for a single log event.
+#[derive(Clone, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct Decision {
+    id: DecisionId,
+    created_at: Timestamp,
+    log_group: String,
+    message: String,
+    needs_notification: bool,
+    severity: Severity,
+    /// The model's explanation of the judgement, present only when it was requested.
+    #[serde(default)]
+    #[builder(default)]
+    explanation: Option<String>,
+    /// Whether the alert was not posted because the log group was muted.
+    #[serde(default)]
+    #[builder(default)]
+    muted: bool,
+    #[serde(default)]
+    #[builder(default)]
+    feedback_sent: bool,
+    // The lifecycle attributes are left out while unset, so that the first acknowledgement
+    // can be recorded with `if_not_exists`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    acknowledged_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    acknowledged_at: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    resolved_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    resolved_at: Option<Timestamp>,
+    /// Absent on decisions stored before the source was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    source: Option<LogSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    context: Option<LogContext>,
+    /// Names of the sinks other than Slack the alert was sent to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    sinks: Vec<String>,
+}
+
+impl Decision {
+    pub(crate) fn id(&self) -> &DecisionId {
+        &self.id
+    }
+
+    pub(crate) fn created_at(&self) -> &Timestamp {
+        &self.created_at
+    }
+
+    pub(crate) fn log_group(&self) -> &str {
+        &self.log_group
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub(crate) fn needs_notification(&self) -> bool {
+        self.needs_notification
+    }
+
+    pub(crate) fn muted(&self) -> bool {
+        self.muted
+    }
+
+    pub(crate) fn feedback_sent(&self) -> bool {
+        self.feedback_sent
+    }
+
+    pub(crate) fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::new(&self.message)
+    }
+
+    pub(crate) fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub(crate) fn explanation(&self) -> Option<&str> {
+        self.explanation.as_deref()
+    }
+
+    pub(crate) fn source(&self) -> Option<&LogSource> {
+        self.source.as_ref()
+    }
+
+    /// Returns the surrounding events, present only when they were fetched.
+    pub(crate) fn context(&self) -> Option<&LogContext> {
+        self.context.as_ref()
+    }
+
+    pub(crate) fn sinks(&self) -> &[String] {
+        &self.sinks
+    }
+
+    /// Returns whether the decision was posted as an alert.
+    pub(crate) fn alerted(&self) -> bool {
+        self.needs_notification && !self.muted
+    }
+
+    pub(crate) fn acknowledged_by(&self) -> Option<&str> {
+        self.acknowledged_by.as_deref()
+    }
+
+    pub(crate) fn acknowledged_at(&self) -> Option<&Timestamp> {
+        self.acknowledged_at.as_ref()
+    }
+
+    pub(crate) fn resolved_by(&self) -> Option<&str> {
+        self.resolved_by.as_deref()
+    }
+
+    pub(crate) fn resolved_at(&self) -> Option<&Timestamp> {
+        self.resolved_at.as_ref()
+    }
+}
+
+/// Mean time to acknowledge and to resolve a set of alerts, measured from when they were
+/// posted. The means are `None` when no alert was acknowledged or resolved.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct ResponseTimes {
+    pub(crate) alerts: usize,
+    pub(crate) acknowledged: usize,
+    pub(crate) mtta_seconds: Option<i64>,
+    pub(crate) resolved: usize,
+    pub(crate) mttr_seconds: Option<i64>,
+}
+
+impl ResponseTimes {
+    pub(crate) fn new(decisions: &[Decision]) -> Self {
+        let alerts: Vec<&Decision> = decisions.iter().filter(|v| v.alerted()).collect();
+        let elapsed = |at: fn(&Decision) -> Option<&Timestamp>| -> Vec<i64> {
+            alerts
+                .iter()
+                .filter_map(|v| at(v).map(|at| at.as_secs() - v.created_at.as_secs()))
+                .collect()
+        };
+        let mean = |values: &[i64]| {
+            (!values.is_empty()).then(|| values.iter().sum::<i64>() / values.len() as i64)
+        };
+        let acknowledged = elapsed(|v| v.acknowledged_at());
+        let resolved = elapsed(|v| v.resolved_at());
+
+        Self {
+            alerts: alerts.len(),
+            acknowledged: acknowledged.len(),
+            mtta_seconds: mean(&acknowledged),
+            resolved: resolved.len(),
+            mttr_seconds: mean(&resolved),
+        }
+    }
+}
+
+/// Numbers of logs of a log group that were alerted, suppressed by the judge, and judged
+/// worth alerting but muted.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct AlertCounts {
+    pub(crate) alerted: usize,
+    pub(crate) suppressed: usize,
+    pub(crate) muted: usize,
+}
+
+impl AlertCounts {
+    pub(crate) fn new(decisions: &[Decision]) -> Self {
+        Self {
+            alerted: decisions.iter().filter(|v| v.alerted()).count(),
+            suppressed: decisions.iter().filter(|v| !v.needs_notification()).count(),
+            muted: decisions.iter().filter(|v| v.muted()).count(),
+        }
+    }
+}
+
+/// Suppression of the alerts of a log group until a time, either for all of its logs or
+/// for the logs with a fingerprint.
+#[derive(Clone, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct Mute {
+    id: MuteId,
+    created_at: Timestamp,
+    log_group: String,
+    /// Also used as the time to live of the item.
+    expires_at: Timestamp,
+    created_by: String,
+    /// Fingerprint of the muted logs, or `None` when the whole log group is muted.
+    #[serde(default)]
+    #[builder(default)]
+    fingerprint: Option<Fingerprint>,
+    /// Decision of the alert the mute was created from, when snoozed from an alert.
+    #[serde(default)]
+    #[builder(default)]
+    decision_id: Option<DecisionId>,
+}
+
+impl Mute {
+    pub(crate) fn id(&self) -> &MuteId {
+        &self.id
+    }
+
+    pub(crate) fn log_group(&self) -> &str {
+        &self.log_group
+    }
+
+    pub(crate) fn expires_at(&self) -> &Timestamp {
+        &self.expires_at
+    }
+
+    pub(crate) fn created_by(&self) -> &str {
+        &self.created_by
+    }
+
+    pub(crate) fn fingerprint(&self) -> Option<&Fingerprint> {
+        self.fingerprint.as_ref()
+    }
+
+    pub(crate) fn decision_id(&self) -> Option<&DecisionId> {
+        self.decision_id.as_ref()
+    }
+
+    /// Returns whether logs of the mute's log group with the fingerprint are muted.
+    pub(crate) fn applies_to(&self, fingerprint: &Fingerprint) -> bool {
+        self.fingerprint.as_ref().is_none_or(|v| v == fingerprint)
+    }
+}
+
+/// Settings applied to the log groups matching a pattern.
+#[derive(Clone, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct Route {
+    #[serde(flatten)]
+    pattern: LogGroupPattern,
+    channels: Vec<String>,
+    /// Guidance given to the model for classifying the severity of the logs.
+    #[serde(default)]
+    #[builder(default)]
+    severity_guidance: Option<String>,
+    /// Slack user group mentioned on critical alerts, overriding the default one.
+    #[serde(default)]
+    #[builder(default)]
+    oncall_user_group: Option<String>,
+    /// Names of the alert sinks used besides the Slack channels.
+    #[serde(default)]
+    #[builder(default)]
+    sinks: Vec<String>,
+    /// Workspace of the channels, when the app is installed to several. The configured
+    /// token is used when unset.
+    #[serde(default)]
+    #[builder(default)]
+    team_id: Option<String>,
+    /// Routes with a lower priority are matched first.
+    #[serde(default)]
+    #[builder(default)]
+    priority: i64,
+}
+
+impl Route {
+    pub(crate) fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    pub(crate) fn severity_guidance(&self) -> Option<&str> {
+        self.severity_guidance.as_deref()
+    }
+
+    pub(crate) fn oncall_user_group(&self) -> Option<&str> {
+        self.oncall_user_group.as_deref()
+    }
+
+    pub(crate) fn sinks(&self) -> &[String] {
+        &self.sinks
+    }
+
+    pub(crate) fn team_id(&self) -> Option<&str> {
+        self.team_id.as_deref()
+    }
+}
+
+/// A workspace the app was installed to through OAuth, with the bot token issued for it.
+/// The token is stored encrypted, so the entity is not serialized as is.
+#[derive(Clone, TypedBuilder)]
+pub(crate) struct Installation {
+    team_id: String,
+    #[builder(default)]
+    team_name: Option<String>,
+    #[builder(default)]
+    bot_user_id: Option<String>,
+    bot_token: String,
+    #[builder(default)]
+    installed_by: Option<String>,
+    installed_at: Timestamp,
+}
+
+impl Installation {
+    pub(crate) fn team_id(&self) -> &str {
+        &self.team_id
+    }
+
+    pub(crate) fn team_name(&self) -> Option<&str> {
+        self.team_name.as_deref()
+    }
+
+    pub(crate) fn bot_user_id(&self) -> Option<&str> {
+        self.bot_user_id.as_deref()
+    }
+
+    pub(crate) fn bot_token(&self) -> &str {
+        &self.bot_token
+    }
+
+    pub(crate) fn installed_by(&self) -> Option<&str> {
+        self.installed_by.as_deref()
+    }
+
+    pub(crate) fn installed_at(&self) -> &Timestamp {
+        &self.installed_at
+    }
+}
+
+/// Routes log groups to the first matching [`Route`], or to the default channels.
+pub(crate) struct RoutingTable {
+    routes: Vec<(Regex, Route)>,
+    default_channels: Vec<String>,
+}
+
+impl RoutingTable {
+    pub(crate) fn new(
+        mut routes: Vec<Route>,
+        default_channels: Vec<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        routes.sort_by_key(|v| v.priority);
+        let routes = routes
+            .into_iter()
+            .map(|v| Ok((v.pattern.to_regex()?, v)))
+            .collect::<Result<Vec<_>, regex::Error>>()?;
+
+        Ok(Self {
+            routes,
+            default_channels,
+        })
+    }
+
+    pub(crate) fn route(&self, log_group: &str) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|(regex, _)| regex.is_match(log_group))
+            .map(|(_, route)| route)
+    }
+
+    pub(crate) fn channels(&self, log_group: &str) -> &[String] {
+        self.route(log_group)
+            .map(|v| v.channels())
+            .unwrap_or(&self.default_channels)
+    }
 }
 
 #[cfg(test)]
@@ -52,5 +637,182 @@ mod tests {
         assert_eq!(feedback.message, "Hello, world!");
         assert!(feedback.needs_notification);
         assert_eq!(feedback.reason, Some("Just because".to_string()));
+        assert_eq!(feedback.user_id, None);
+        assert!(!feedback.retired);
+    }
+
+    #[test]
+    fn test_feedback_conflicts_with() {
+        let feedback = Feedback::builder()
+            .id(FeedbackId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/my-function".to_string())
+            .message("ERROR: connection reset by peer".to_string())
+            .needs_notification(false)
+            .reason(None)
+            .build();
+
+        assert!(feedback.conflicts_with("ERROR: connection reset by peer", true));
+        assert!(!feedback.conflicts_with("ERROR: connection reset by peer", false));
+        assert!(!feedback.conflicts_with("disk full", true));
+    }
+
+    #[test]
+    fn test_most_similar_feedback() {
+        let feedback = |message: &str, retired: bool| {
+            Feedback::builder()
+                .id(FeedbackId::new())
+                .created_at(Timestamp::new())
+                .log_group("/aws/lambda/my-function".to_string())
+                .message(message.to_string())
+                .needs_notification(false)
+                .reason(None)
+                .retired(retired)
+                .build()
+        };
+        let feedback = vec![
+            feedback("disk full", false),
+            feedback("ERROR: connection reset by peer", true),
+            feedback("ERROR: connection reset", false),
+            feedback("ERROR: connection refused", false),
+        ];
+
+        let similar = most_similar_feedback(&feedback, "ERROR: connection reset by peer", 2);
+        assert_eq!(
+            similar.iter().map(|v| v.message()).collect::<Vec<_>>(),
+            vec!["ERROR: connection reset", "ERROR: connection refused"]
+        );
+        assert!(most_similar_feedback(&[], "ERROR", 5).is_empty());
+    }
+
+    #[test]
+    fn test_log_context() {
+        let lines = |v: &[&str]| v.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        let context = LogContext::new(lines(&["a1", "b2", "c3"]), lines(&["d4", "e5"]), 100);
+        assert_eq!(context.before(), lines(&["a1", "b2", "c3"]));
+        assert_eq!(context.after(), lines(&["d4", "e5"]));
+
+        // The events farthest from the alerted one are dropped first, alternating sides.
+        let context = LogContext::new(lines(&["a1", "b2", "c3"]), lines(&["d4", "e5"]), 6);
+        assert_eq!(context.before(), lines(&["b2", "c3"]));
+        assert_eq!(context.after(), lines(&["d4"]));
+
+        // An event too large to fit ends its side but not the other.
+        let context = LogContext::new(lines(&["a1", "longer"]), lines(&["d4", "e5"]), 4);
+        assert!(context.before().is_empty());
+        assert_eq!(context.after(), lines(&["d4", "e5"]));
+
+        assert!(LogContext::new(vec![], vec![], 100).is_empty());
+    }
+
+    #[test]
+    fn test_response_times() {
+        let decision = |acknowledged_after: Option<i64>, resolved_after: Option<i64>| {
+            let created_at = Timestamp::from_secs(1_000);
+            Decision::builder()
+                .id(DecisionId::new())
+                .created_at(created_at.clone())
+                .log_group("/aws/lambda/my-function".to_string())
+                .message("ERROR".to_string())
+                .needs_notification(true)
+                .severity(Severity::Critical)
+                .acknowledged_at(acknowledged_after.map(|v| Timestamp::from_secs(1_000 + v)))
+                .resolved_at(resolved_after.map(|v| Timestamp::from_secs(1_000 + v)))
+                .build()
+        };
+        let decisions = vec![
+            decision(Some(60), Some(600)),
+            decision(Some(120), None),
+            decision(None, None),
+        ];
+
+        assert_eq!(
+            ResponseTimes::new(&decisions),
+            ResponseTimes {
+                alerts: 3,
+                acknowledged: 2,
+                mtta_seconds: Some(90),
+                resolved: 1,
+                mttr_seconds: Some(600),
+            }
+        );
+        assert_eq!(ResponseTimes::new(&[]), ResponseTimes::default());
+    }
+
+    #[test]
+    fn test_alert_counts() {
+        let decision = |needs_notification: bool, muted: bool| {
+            Decision::builder()
+                .id(DecisionId::new())
+                .created_at(Timestamp::new())
+                .log_group("/aws/lambda/my-function".to_string())
+                .message("ERROR".to_string())
+                .needs_notification(needs_notification)
+                .severity(Severity::Medium)
+                .muted(muted)
+                .build()
+        };
+        let decisions = vec![
+            decision(true, false),
+            decision(true, true),
+            decision(false, false),
+            decision(false, false),
+        ];
+
+        assert_eq!(
+            AlertCounts::new(&decisions),
+            AlertCounts {
+                alerted: 1,
+                suppressed: 2,
+                muted: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_mute_applies_to() {
+        let mute = |fingerprint: Option<Fingerprint>| {
+            Mute::builder()
+                .id(MuteId::new())
+                .created_at(Timestamp::new())
+                .log_group("/aws/lambda/my-function".to_string())
+                .expires_at(Timestamp::new())
+                .created_by("U123".to_string())
+                .fingerprint(fingerprint)
+                .build()
+        };
+        let reset = Fingerprint::new("ERROR: connection reset by peer");
+        let full = Fingerprint::new("ERROR: disk full");
+
+        assert!(mute(None).applies_to(&reset));
+        assert!(mute(Some(reset.clone())).applies_to(&reset));
+        assert!(!mute(Some(reset)).applies_to(&full));
+    }
+
+    #[test]
+    fn test_routing_table() {
+        let table = RoutingTable::new(
+            vec![
+                Route::builder()
+                    .pattern(LogGroupPattern::Glob("/aws/lambda/*".into()))
+                    .channels(vec!["C_LAMBDA".into()])
+                    .priority(10)
+                    .build(),
+                Route::builder()
+                    .pattern(LogGroupPattern::Regex("/aws/lambda/payment-.+".into()))
+                    .channels(vec!["C_PAYMENT".into(), "C_ONCALL".into()])
+                    .build(),
+            ],
+            vec!["C_DEFAULT".into()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            table.channels("/aws/lambda/payment-api"),
+            ["C_PAYMENT", "C_ONCALL"]
+        );
+        assert_eq!(table.channels("/aws/lambda/search-api"), ["C_LAMBDA"]);
+        assert_eq!(table.channels("/ecs/search-api"), ["C_DEFAULT"]);
     }
 }
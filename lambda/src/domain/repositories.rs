@@ -1,9 +1,138 @@
-use crate::domain::entities::Feedback;
+use crate::domain::entities::{Decision, Feedback, Installation, LogEvent, Mute, Route, Rule};
+use crate::domain::value_objects::{
+    DecisionId, FeedbackId, MuteId, RuleId, RuleStatus, Severity, Timestamp,
+};
 
 pub(crate) trait FeedbackRepository {
     async fn add_feedback(&self, feedback: Feedback) -> Result<(), Box<dyn std::error::Error>>;
+    /// Adds the feedback unless one with the same id exists, and returns whether it did.
+    async fn create_feedback(&self, feedback: Feedback)
+        -> Result<bool, Box<dyn std::error::Error>>;
+    async fn get_feedback(
+        &self,
+        id: &FeedbackId,
+    ) -> Result<Option<Feedback>, Box<dyn std::error::Error>>;
     async fn list_feedback_by_log_group(
         &self,
         log_group: &str,
     ) -> Result<Vec<Feedback>, Box<dyn std::error::Error>>;
+    /// Lists the feedback given by a user, newest first.
+    async fn list_feedback_by_user(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<Feedback>, Box<dyn std::error::Error>>;
+    async fn list_log_groups(&self) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+    async fn delete_feedback(&self, id: &FeedbackId) -> Result<(), Box<dyn std::error::Error>>;
+    async fn retire_feedback(&self, id: &FeedbackId) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+pub(crate) trait RuleRepository {
+    async fn add_rule(&self, rule: Rule) -> Result<(), Box<dyn std::error::Error>>;
+    async fn get_rule(&self, id: &RuleId) -> Result<Option<Rule>, Box<dyn std::error::Error>>;
+    async fn list_rules_by_log_group(
+        &self,
+        log_group: &str,
+    ) -> Result<Vec<Rule>, Box<dyn std::error::Error>>;
+    async fn update_rule_status(
+        &self,
+        id: &RuleId,
+        status: RuleStatus,
+        reviewed_by: &str,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+pub(crate) trait RouteRepository {
+    async fn list_routes(&self) -> Result<Vec<Route>, Box<dyn std::error::Error>>;
+}
+
+pub(crate) trait DecisionRepository {
+    async fn add_decision(&self, decision: Decision) -> Result<(), Box<dyn std::error::Error>>;
+    async fn get_decision(
+        &self,
+        id: &DecisionId,
+    ) -> Result<Option<Decision>, Box<dyn std::error::Error>>;
+    async fn list_decisions_by_log_group(
+        &self,
+        log_group: &str,
+        since: &Timestamp,
+    ) -> Result<Vec<Decision>, Box<dyn std::error::Error>>;
+    async fn list_decisions_by_severity(
+        &self,
+        severity: Severity,
+        since: &Timestamp,
+    ) -> Result<Vec<Decision>, Box<dyn std::error::Error>>;
+    async fn mark_feedback_sent(&self, id: &DecisionId) -> Result<(), Box<dyn std::error::Error>>;
+    /// Records the first acknowledgement of the alert; later ones are ignored.
+    async fn acknowledge(
+        &self,
+        id: &DecisionId,
+        user_id: &str,
+        at: &Timestamp,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Records the first resolution of the alert, which also acknowledges it if it was not.
+    async fn resolve(
+        &self,
+        id: &DecisionId,
+        user_id: &str,
+        at: &Timestamp,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Mutes are returned only until they expire.
+pub(crate) trait MuteRepository {
+    async fn add_mute(&self, mute: Mute) -> Result<(), Box<dyn std::error::Error>>;
+    async fn get_mute(&self, id: &MuteId) -> Result<Option<Mute>, Box<dyn std::error::Error>>;
+    async fn delete_mute(&self, id: &MuteId) -> Result<(), Box<dyn std::error::Error>>;
+    async fn list_mutes(&self) -> Result<Vec<Mute>, Box<dyn std::error::Error>>;
+    async fn list_mutes_by_log_group(
+        &self,
+        log_group: &str,
+    ) -> Result<Vec<Mute>, Box<dyn std::error::Error>>;
+}
+
+/// Reads the events of a log stream, oldest first.
+pub(crate) trait LogEventRepository {
+    /// Returns up to `limit` of the latest events logged before `end`, in milliseconds.
+    async fn list_events_before(
+        &self,
+        log_group: &str,
+        log_stream: &str,
+        end: i64,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>, Box<dyn std::error::Error>>;
+    /// Returns up to `limit` of the earliest events logged at or after `start`, in
+    /// milliseconds.
+    async fn list_events_from(
+        &self,
+        log_group: &str,
+        log_stream: &str,
+        start: i64,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>, Box<dyn std::error::Error>>;
+}
+
+pub(crate) trait InstallationRepository {
+    /// Saves the installation, replacing the previous one of the workspace.
+    async fn save_installation(
+        &self,
+        installation: Installation,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    async fn get_installation(
+        &self,
+        team_id: &str,
+    ) -> Result<Option<Installation>, Box<dyn std::error::Error>>;
+}
+
+/// Remembers the requests already handled until they could no longer pass as recent.
+pub(crate) trait NonceRepository {
+    /// Records the nonce until `expires_at` and returns whether it was unused. A nonce
+    /// recorded before is unused again once it has expired.
+    async fn claim_nonce(
+        &self,
+        nonce: &str,
+        expires_at: &Timestamp,
+    ) -> Result<bool, Box<dyn std::error::Error>>;
+
+    /// Forgets the nonce, so that it can be claimed again.
+    async fn release_nonce(&self, nonce: &str) -> Result<(), Box<dyn std::error::Error>>;
 }
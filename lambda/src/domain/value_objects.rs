@@ -1,10 +1,13 @@
 use chrono::{DateTime, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt;
 use std::fmt::Formatter;
+use std::sync::LazyLock;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub(crate) struct FeedbackId(Uuid);
 
 impl fmt::Display for FeedbackId {
@@ -13,13 +16,141 @@ impl fmt::Display for FeedbackId {
     }
 }
 
+impl TryFrom<&str> for FeedbackId {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(Self(Uuid::parse_str(value)?))
+    }
+}
+
 impl FeedbackId {
+    /// Feedback is always added with an id derived from its submission, so that it is
+    /// added once, so only tests make random ones.
+    #[cfg(test)]
+    pub(crate) fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Returns the id of the feedback submitted from a modal, the same however often the
+    /// submission is received.
+    pub(crate) fn from_view(view_id: &str) -> Self {
+        Self::from_digest(view_id)
+    }
+
+    /// Returns the id of the feedback submitted from the feedback page through a link,
+    /// the same however often the form is sent.
+    pub(crate) fn from_link(decision_id: &DecisionId, signature: &str) -> Self {
+        Self::from_digest(&format!("{}:{}", decision_id, signature))
+    }
+
+    fn from_digest(key: &str) -> Self {
+        let digest = Sha256::digest(key.as_bytes());
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        Self(Uuid::new_v8(bytes))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RuleId(Uuid);
+
+impl fmt::Display for RuleId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&str> for RuleId {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(Self(Uuid::parse_str(value)?))
+    }
+}
+
+impl RuleId {
+    pub(crate) fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct DecisionId(Uuid);
+
+impl fmt::Display for DecisionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&str> for DecisionId {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(Self(Uuid::parse_str(value)?))
+    }
+}
+
+impl DecisionId {
     pub(crate) fn new() -> Self {
         Self(Uuid::new_v4())
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct MuteId(Uuid);
+
+impl fmt::Display for MuteId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&str> for MuteId {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(Self(Uuid::parse_str(value)?))
+    }
+}
+
+impl MuteId {
+    pub(crate) fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// Parts of a log message that change between occurrences of the same error: UUIDs, hex
+/// strings such as request ids and hashes, and numbers such as timestamps and counts.
+static VARIABLE_PARTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}|\b(?:0x)?[0-9a-f]*[0-9][0-9a-f]*\b|\d+",
+    )
+    .expect("Invalid pattern")
+});
+
+/// Identifies the kind of a log message, so that repeated occurrences of the same error
+/// share a fingerprint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Fingerprint(String);
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Fingerprint {
+    pub(crate) fn new(message: &str) -> Self {
+        let normalized = VARIABLE_PARTS.replace_all(message.trim(), "#");
+        let digest = Sha256::digest(normalized.as_bytes());
+        Self(digest[..8].iter().map(|v| format!("{:02x}", v)).collect())
+    }
+}
+
+/// Unix time in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct Timestamp(i64);
 
 impl fmt::Display for Timestamp {
@@ -40,4 +171,207 @@ impl Timestamp {
     pub(crate) fn new() -> Self {
         Self(chrono::Utc::now().timestamp())
     }
+
+    pub(crate) fn from_secs(secs: i64) -> Self {
+        Self(secs)
+    }
+
+    /// Converts a time in milliseconds, as given by CloudWatch Logs, dropping the fraction.
+    pub(crate) fn from_millis(millis: i64) -> Self {
+        Self(millis.div_euclid(1000))
+    }
+
+    pub(crate) fn as_secs(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns the timestamp the given duration after this one.
+    pub(crate) fn add(&self, duration: chrono::Duration) -> Self {
+        Self(self.0 + duration.num_seconds())
+    }
+}
+
+/// How to treat existing feedback that contradicts a newly submitted one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ConflictResolution {
+    /// Delete the existing feedback and add the new one.
+    Replace,
+    /// Keep the existing feedback for reference but exclude it from decisions, and add the new one.
+    Retire,
+    /// Discard the new feedback.
+    Cancel,
+}
+
+impl TryFrom<&str> for ConflictResolution {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "replace" => Ok(Self::Replace),
+            "retire" => Ok(Self::Retire),
+            "cancel" => Ok(Self::Cancel),
+            _ => Err(format!("Invalid conflict resolution: {}", value).into()),
+        }
+    }
+}
+
+/// Review state of a rule generated from feedback.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RuleStatus {
+    /// Waiting for an operator to review it.
+    Pending,
+    /// Approved and sent to the model in place of its source feedback.
+    Active,
+    /// Rejected by an operator.
+    Rejected,
+}
+
+/// Severity of an error log.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::Critical => "critical",
+            Self::High => "high",
+            Self::Medium => "medium",
+            Self::Low => "low",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl TryFrom<&str> for Severity {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "critical" => Ok(Self::Critical),
+            "high" => Ok(Self::High),
+            "medium" => Ok(Self::Medium),
+            "low" => Ok(Self::Low),
+            _ => Err(format!("Invalid severity: {}", value).into()),
+        }
+    }
+}
+
+/// Language of the messages shown to users.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl TryFrom<&str> for Locale {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "ja" => Ok(Self::Ja),
+            "en" => Ok(Self::En),
+            _ => Err(format!("Invalid locale: {}", value).into()),
+        }
+    }
+}
+
+/// Pattern matched against the whole name of a log group.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", content = "pattern", rename_all = "snake_case")]
+pub(crate) enum LogGroupPattern {
+    /// Shell-style glob where `*` matches any sequence of characters and `?` a single one.
+    Glob(String),
+    Regex(String),
+}
+
+impl LogGroupPattern {
+    pub(crate) fn to_regex(&self) -> Result<Regex, regex::Error> {
+        match self {
+            Self::Glob(glob) => {
+                let mut pattern = String::from("^");
+                for c in glob.chars() {
+                    match c {
+                        '*' => pattern.push_str(".*"),
+                        '?' => pattern.push('.'),
+                        c => pattern.push_str(&regex::escape(&c.to_string())),
+                    }
+                }
+                pattern.push('$');
+                Regex::new(&pattern)
+            }
+            Self::Regex(regex) => Regex::new(&format!("^(?:{})$", regex)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_group_pattern() {
+        let glob = LogGroupPattern::Glob("/aws/lambda/prod-*".into())
+            .to_regex()
+            .unwrap();
+        assert!(glob.is_match("/aws/lambda/prod-api"));
+        assert!(!glob.is_match("/aws/lambda/dev-api"));
+        assert!(!glob.is_match("x/aws/lambda/prod-api"));
+
+        let regex = LogGroupPattern::Regex("/ecs/(api|worker)".into())
+            .to_regex()
+            .unwrap();
+        assert!(regex.is_match("/ecs/api"));
+        assert!(!regex.is_match("/ecs/api-v2"));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let fingerprint = Fingerprint::new(
+            "2025-01-01T00:00:00Z ERROR request 3f2a9c1e-0d4b-4c55-9a7e-1b2c3d4e5f60 failed after 1200ms",
+        );
+
+        assert_eq!(
+            fingerprint,
+            Fingerprint::new(
+                "2025-02-03T12:34:56Z ERROR request 0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d failed after 35ms"
+            )
+        );
+        assert_ne!(
+            fingerprint,
+            Fingerprint::new(
+                "2025-01-01T00:00:00Z ERROR connection 3f2a9c1e-0d4b-4c55-9a7e-1b2c3d4e5f60 refused"
+            )
+        );
+        assert_eq!(fingerprint.to_string().len(), 16);
+    }
+
+    #[test]
+    fn test_feedback_id_from_view() {
+        let id = FeedbackId::from_view("V0123ABCD");
+
+        assert_eq!(id, FeedbackId::from_view("V0123ABCD"));
+        assert_ne!(id, FeedbackId::from_view("V0123ABCE"));
+        // The id is stored as text and read back like any other.
+        assert_eq!(FeedbackId::try_from(id.to_string().as_str()).unwrap(), id);
+    }
+
+    #[test]
+    fn test_feedback_id_from_link() {
+        let decision_id = DecisionId::new();
+        let id = FeedbackId::from_link(&decision_id, "signature");
+
+        assert_eq!(id, FeedbackId::from_link(&decision_id, "signature"));
+        // Links sent again for the same alert carry another signature.
+        assert_ne!(id, FeedbackId::from_link(&decision_id, "other"));
+        assert_ne!(id, FeedbackId::from_link(&DecisionId::new(), "signature"));
+    }
 }
@@ -1,4 +1,6 @@
-use crate::domain::entities::Feedback;
+use crate::domain::entities::{Feedback, LogContext, Rule};
+use crate::domain::value_objects::{Locale, Severity};
+use crate::infrastructure::i18n::catalog;
 use aws_sdk_bedrockruntime::operation::converse::ConverseOutput;
 use aws_sdk_bedrockruntime::types::{
     ContentBlock, ConversationRole, InferenceConfiguration, Message, SystemContentBlock, Tool,
@@ -15,17 +17,29 @@ const SYSTEM_PROMPT: &str = "
 You are a log monitor.
 </role>
 <question>
-Refer to the list of past notification feedback (`feedback`) to determine whether a notification is required for the currently occurring error log (`target_log`).
+Refer to the rules derived from past feedback (`rules`) and the list of past notification feedback (`feedback`) to determine whether a notification is required for the currently occurring error log (`target_log`).
+Also classify the severity of `target_log` following the operator's guidance (`severity_guidance`).
+If `explanation_language` is given, briefly explain the reasoning for the decision in that language.
 </question>
 <data_info>
+- rules: A list of rules summarized from past feedback and approved by the operator (may be empty)
+  - created_at: The date and time when the rule was generated
+  - rule: The description of the logs the rule applies to and why
+  - needs_notification: Whether a notification is required for logs matching the rule
 - feedback: A list of feedback regarding notifications from the operator
   - created_at: The date and time when the feedback was added
   - message: The content of the error log that received feedback
   - needs_notification: Whether a notification is required (`true` means required, `false` means not required)
   - reason: Reasons for necessity or non-necessity (optional)
+  - severity: The severity the operator considers appropriate for the log (optional)
+- severity_guidance: The operator's guidance for classifying severity (may be empty)
+- explanation_language: The language of the explanation (absent if no explanation is needed)
 - target_log: The error log subject to the decision
   - message: The content of the log
   - timestamp: The date and time when the log was generated
+  - context: The logs written just before and after it in the same log stream, oldest first (absent if not available)
+    - before: The logs written before `target_log`
+    - after: The logs written after `target_log`
 </data_info>
 <rule>
 - Think step-by-step.
@@ -33,6 +47,32 @@ Refer to the list of past notification feedback (`feedback`) to determine whethe
 - Treat feedback as similar if the `message` in both `feedback` and `target_log` matches 80% or more.
 - If the referenced `feedback` for inference contains a `reason`, take its content into account.
 - If similar feedback contradict each other, prioritize the feedback with the most recent `created_at` timestamp.
+- If a rule applies to `target_log`, follow it unless more recent similar feedback contradicts it.
+- Use `context` only to understand the circumstances of `target_log`, such as a retry that succeeded right after it; compare only the `message` of `target_log` with feedback.
+- Classify severity as `critical` (service outage or data loss), `high` (major feature impaired), `medium` (degraded but working) or `low` (no user impact), unless `severity_guidance` or the `severity` of similar feedback says otherwise.
+</rule>
+";
+
+const COMPACTION_SYSTEM_PROMPT: &str = "
+<role>
+You are a log monitor.
+</role>
+<question>
+Summarize the list of similar notification feedback (`feedback`) into a single concise rule that can be used in place of the feedback to determine whether a notification is required for future error logs.
+</question>
+<data_info>
+- feedback: A list of feedback regarding notifications from the operator
+  - created_at: The date and time when the feedback was added
+  - message: The content of the error log that received feedback
+  - needs_notification: Whether a notification is required (`true` means required, `false` means not required)
+  - reason: Reasons for necessity or non-necessity (optional)
+</data_info>
+<rule>
+- Think step-by-step.
+- Describe which logs the rule applies to in a way that does not depend on values that change between occurrences, such as IDs and timestamps.
+- Include the reasons given in the feedback when they exist.
+- If the feedback contradict each other, prioritize the feedback with the most recent `created_at` timestamp.
+- Write the rule in the same language as the feedback `reason`, or in English if there is none.
 </rule>
 ";
 
@@ -42,6 +82,7 @@ struct FeedbackDto {
     message: String,
     needs_notification: bool,
     reason: Option<String>,
+    severity: Option<String>,
 }
 
 impl TryFrom<Feedback> for FeedbackDto {
@@ -54,17 +95,56 @@ impl TryFrom<Feedback> for FeedbackDto {
             .message(value.message().into())
             .needs_notification(value.needs_notification())
             .reason(value.reason().map(|r| r.into()))
+            .severity(value.severity().map(|v| v.to_string()))
             .build())
     }
 }
 
 #[derive(Serialize, TypedBuilder)]
-struct TargetLog {
+struct RuleDto {
+    created_at: String,
+    rule: String,
+    needs_notification: bool,
+}
+
+impl TryFrom<Rule> for RuleDto {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: Rule) -> Result<Self, Self::Error> {
+        let created_at = DateTime::<Utc>::try_from(value.created_at().to_owned())?;
+        Ok(Self::builder()
+            .created_at(created_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .rule(value.rule().into())
+            .needs_notification(value.needs_notification())
+            .build())
+    }
+}
+
+/// The model's judgement of a log.
+pub(crate) struct Judgement {
+    pub(crate) needs_notification: bool,
+    pub(crate) severity: Severity,
+    /// Reasoning for the judgement, present only when it was requested.
+    pub(crate) explanation: Option<String>,
+}
+
+/// A rule summarized from feedback by the model.
+pub(crate) struct SummarizedRule {
+    pub(crate) rule: String,
+    pub(crate) needs_notification: bool,
+}
+
+/// The log to be judged.
+#[derive(Serialize, TypedBuilder)]
+pub(crate) struct TargetLog {
     message: String,
     timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    context: Option<LogContext>,
 }
 
-#[derive(TypedBuilder)]
+#[derive(Clone, TypedBuilder)]
 pub struct Client {
     inner_client: aws_sdk_bedrockruntime::Client,
     model_id: String,
@@ -73,28 +153,108 @@ pub struct Client {
 }
 
 impl Client {
+    pub(crate) fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    pub(crate) fn top_p(&self) -> f32 {
+        self.top_p
+    }
+
+    pub(crate) fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
     pub(crate) async fn needs_notification(
         &self,
+        rules: Vec<Rule>,
         feedback: Vec<Feedback>,
-        message: String,
-        timestamp: String,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
+        severity_guidance: Option<&str>,
+        explanation_locale: Option<Locale>,
+        target: TargetLog,
+    ) -> Result<Judgement, Box<dyn std::error::Error>> {
+        let rules = rules
+            .into_iter()
+            .map(|v| v.try_into())
+            .collect::<Result<Vec<RuleDto>, _>>()?;
+        let feedback = feedback
+            .into_iter()
+            .map(|v| v.try_into())
+            .collect::<Result<Vec<FeedbackDto>, _>>()?;
+        let explanation_language = explanation_locale
+            .map(|v| {
+                format!(
+                    "<explanation_language>{}</explanation_language>",
+                    catalog(v).language
+                )
+            })
+            .unwrap_or_default();
         let msg = Message::builder()
             .role(ConversationRole::User)
             .content(ContentBlock::Text(format!(
-                "<feedback>{}</feedback><target_log>{}</target_log>",
+                "<rules>{}</rules><feedback>{}</feedback><severity_guidance>{}</severity_guidance>{}<target_log>{}</target_log>",
+                serde_json::to_string(&rules)?,
+                serde_json::to_string(&feedback)?,
+                severity_guidance.unwrap_or_default(),
+                explanation_language,
+                serde_json::to_string(&target)?
+            )))
+            .build()?;
+
+        let inference_config = InferenceConfiguration::builder()
+            .top_p(self.top_p)
+            .temperature(self.temperature)
+            .build();
+        let tool_config = ToolConfiguration::builder()
+            .tools(Tool::ToolSpec(
+                ToolSpecification::builder()
+                    .name("judge_needs_notification")
+                    .description("Determines if notification is required.")
+                    .input_schema(ToolInputSchema::Json(self.make_tool_schema()))
+                    .build()?,
+            ))
+            .build()?;
+
+        let resp = self
+            .inner_client
+            .converse()
+            .model_id(&self.model_id)
+            .system(SystemContentBlock::Text(SYSTEM_PROMPT.into()))
+            .messages(msg)
+            .inference_config(inference_config)
+            .tool_config(tool_config)
+            .send()
+            .await?;
+
+        let input = self.get_tool_input(resp)?;
+        Ok(Judgement {
+            needs_notification: input
+                .get("needs_notification")
+                .ok_or("needs_notification not found")?
+                .as_bool()
+                .ok_or("needs_notification is not a boolean")?,
+            severity: parse_severity(input.get("severity")),
+            explanation: explanation_locale
+                .and_then(|_| input.get("explanation"))
+                .and_then(|v| v.as_string())
+                .map(|v| v.to_string()),
+        })
+    }
+
+    pub(crate) async fn summarize_feedback(
+        &self,
+        feedback: Vec<Feedback>,
+    ) -> Result<SummarizedRule, Box<dyn std::error::Error>> {
+        let msg = Message::builder()
+            .role(ConversationRole::User)
+            .content(ContentBlock::Text(format!(
+                "<feedback>{}</feedback>",
                 serde_json::to_string(
                     &feedback
                         .into_iter()
                         .map(|v| v.try_into())
                         .collect::<Result<Vec<FeedbackDto>, _>>()?
                 )?,
-                serde_json::to_string(
-                    &TargetLog::builder()
-                        .message(message)
-                        .timestamp(timestamp)
-                        .build()
-                )?
             )))
             .build()?;
 
@@ -105,9 +265,9 @@ impl Client {
         let tool_config = ToolConfiguration::builder()
             .tools(Tool::ToolSpec(
                 ToolSpecification::builder()
-                    .name("judge_needs_notification")
-                    .description("Determines if notification is required.")
-                    .input_schema(ToolInputSchema::Json(self.make_tool_schema()))
+                    .name("summarize_rule")
+                    .description("Records the rule summarized from the feedback.")
+                    .input_schema(ToolInputSchema::Json(self.make_summary_tool_schema()))
                     .build()?,
             ))
             .build()?;
@@ -116,14 +276,27 @@ impl Client {
             .inner_client
             .converse()
             .model_id(&self.model_id)
-            .system(SystemContentBlock::Text(SYSTEM_PROMPT.into()))
+            .system(SystemContentBlock::Text(COMPACTION_SYSTEM_PROMPT.into()))
             .messages(msg)
             .inference_config(inference_config)
             .tool_config(tool_config)
             .send()
             .await?;
 
-        self.get_converse_output(resp)
+        let input = self.get_tool_input(resp)?;
+        Ok(SummarizedRule {
+            rule: input
+                .get("rule")
+                .ok_or("rule not found")?
+                .as_string()
+                .ok_or("rule is not a string")?
+                .to_string(),
+            needs_notification: input
+                .get("needs_notification")
+                .ok_or("needs_notification not found")?
+                .as_bool()
+                .ok_or("needs_notification is not a boolean")?,
+        })
     }
 
     fn make_tool_schema(&self) -> Document {
@@ -142,24 +315,92 @@ impl Client {
                             ),
                         ])),
                     ),
+                    (
+                        "severity".into(),
+                        Document::Object(HashMap::<String, Document>::from([
+                            ("type".into(), Document::String("string".into())),
+                            (
+                                "enum".into(),
+                                Document::Array(vec![
+                                    Document::String("critical".into()),
+                                    Document::String("high".into()),
+                                    Document::String("medium".into()),
+                                    Document::String("low".into()),
+                                ]),
+                            ),
+                            (
+                                "description".into(),
+                                Document::String("The severity of the log.".into()),
+                            ),
+                        ])),
+                    ),
+                    (
+                        "explanation".into(),
+                        Document::Object(HashMap::<String, Document>::from([
+                            ("type".into(), Document::String("string".into())),
+                            (
+                                "description".into(),
+                                Document::String("A brief explanation of the decision in `explanation_language`. Omit if it is not given.".into()),
+                            ),
+                        ])),
+                    ),
                 ])),
             ),
             (
                 "required".into(),
                 Document::Array(vec![
                     Document::String("needs_notification".into()),
+                    Document::String("severity".into()),
                 ]),
             ),
         ]))
     }
 
-    fn get_converse_output(
+    fn make_summary_tool_schema(&self) -> Document {
+        Document::Object(HashMap::<String, Document>::from([
+            ("type".into(), Document::String("object".into())),
+            (
+                "properties".into(),
+                Document::Object(HashMap::<String, Document>::from([
+                    (
+                        "rule".into(),
+                        Document::Object(HashMap::<String, Document>::from([
+                            ("type".into(), Document::String("string".into())),
+                            (
+                                "description".into(),
+                                Document::String("A concise description of the logs the rule applies to and the reason.".into()),
+                            ),
+                        ])),
+                    ),
+                    (
+                        "needs_notification".into(),
+                        Document::Object(HashMap::<String, Document>::from([
+                            ("type".into(), Document::String("boolean".into())),
+                            (
+                                "description".into(),
+                                Document::String("If logs matching the rule need notification, set to true, otherwise set to false.".into()),
+                            ),
+                        ])),
+                    ),
+                ])),
+            ),
+            (
+                "required".into(),
+                Document::Array(vec![
+                    Document::String("rule".into()),
+                    Document::String("needs_notification".into()),
+                ]),
+            ),
+        ]))
+    }
+
+    fn get_tool_input(
         &self,
         resp: ConverseOutput,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
+    ) -> Result<HashMap<String, Document>, Box<dyn std::error::Error>> {
         let output = resp.output.ok_or("Output not found")?;
 
-        let mut needs_notification = None;
+        let mut input = None;
         for content in output
             .as_message()
             .map_err(|_| "Output is not a message")?
@@ -170,18 +411,52 @@ impl Client {
                     let result = tool_use
                         .input()
                         .as_object()
-                        .ok_or("Input is not an object")?
-                        .get("needs_notification")
-                        .ok_or("needs_notification not found")?
-                        .as_bool()
-                        .ok_or("needs_notification is not a boolean")?;
+                        .ok_or("Input is not an object")?;
 
-                    needs_notification = Some(result);
+                    input = Some(result.clone());
                 }
                 Err(_) => continue,
             }
         }
 
-        Ok(needs_notification.ok_or("Failed not found toolUse")?)
+        Ok(input.ok_or("Failed not found toolUse")?)
+    }
+}
+
+/// Parses the severity judged by the model, falling back to high when it is missing or
+/// unknown so that the alert is still sent without paging on-call.
+fn parse_severity(value: Option<&Document>) -> Severity {
+    match value.and_then(|v| v.as_string()).map(Severity::try_from) {
+        Some(Ok(severity)) => severity,
+        _ => {
+            tracing::warn!("Invalid severity {:?}, falling back to high", value);
+            Severity::High
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_severity() {
+        for (value, severity) in [
+            ("critical", Severity::Critical),
+            ("high", Severity::High),
+            ("medium", Severity::Medium),
+            ("low", Severity::Low),
+        ] {
+            assert_eq!(
+                parse_severity(Some(&Document::String(value.to_string()))),
+                severity
+            );
+        }
+        assert_eq!(
+            parse_severity(Some(&Document::String("urgent".to_string()))),
+            Severity::High
+        );
+        assert_eq!(parse_severity(Some(&Document::Bool(true))), Severity::High);
+        assert_eq!(parse_severity(None), Severity::High);
     }
 }
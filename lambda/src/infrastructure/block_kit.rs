@@ -0,0 +1,395 @@
+//! Typed subset of Slack's Block Kit used by the alert messages and modals.
+//!
+//! The same types are used to build the payloads sent to Slack and to parse the payloads
+//! Slack sends back on interactions, so both sides agree on block and action ids.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BlockId {
+    Header,
+    Mention,
+    LogGroupHeader,
+    LogGroup,
+    LogSource,
+    LogLinks,
+    MessageHeader,
+    Message,
+    LogContext,
+    Explanation,
+    Divider,
+    FeedbackButton,
+    FeedbackStatus,
+    SnoozeStatus,
+    AlertStatus,
+    SnoozeUntil,
+    SnoozeScope,
+    SimilarFeedbackHeader,
+    NeedsNotification,
+    Severity,
+    Reason,
+    ConflictHeader,
+    Resolution,
+    RuleHeader,
+    RuleLogGroup,
+    Rule,
+    RuleContext,
+    RuleReview,
+    RuleStatus,
+    /// A block id assigned by Slack or not known to this application.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ActionId {
+    OpenModal,
+    ApproveRule,
+    RejectRule,
+    NeedsNotification,
+    Severity,
+    Reason,
+    Resolution,
+    Acknowledge,
+    Resolve,
+    Snooze,
+    ReleaseSnooze,
+    SnoozeUntil,
+    SnoozeScope,
+    EditFeedback,
+    DeleteFeedback,
+    OpenInCloudWatch,
+    OpenLogsInsights,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CallbackId {
+    SendFeedback,
+    ResolveConflict,
+    Snooze,
+    EditFeedback,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Text {
+    PlainText {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        emoji: Option<bool>,
+    },
+    Mrkdwn {
+        text: String,
+    },
+}
+
+impl Text {
+    pub(crate) fn plain(text: impl Into<String>) -> Self {
+        Self::PlainText {
+            text: text.into(),
+            emoji: None,
+        }
+    }
+
+    pub(crate) fn plain_emoji(text: impl Into<String>) -> Self {
+        Self::PlainText {
+            text: text.into(),
+            emoji: Some(true),
+        }
+    }
+
+    pub(crate) fn mrkdwn(text: impl Into<String>) -> Self {
+        Self::Mrkdwn { text: text.into() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ButtonStyle {
+    Primary,
+    Danger,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SelectOption {
+    pub(crate) text: Text,
+    pub(crate) value: String,
+}
+
+impl SelectOption {
+    pub(crate) fn new(text: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            text: Text::plain(text),
+            value: value.into(),
+        }
+    }
+}
+
+/// Dialog asking the user to confirm an action before it is sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Confirm {
+    pub(crate) title: Text,
+    pub(crate) text: Text,
+    pub(crate) confirm: Text,
+    pub(crate) deny: Text,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) style: Option<ButtonStyle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Element {
+    Button {
+        text: Text,
+        action_id: ActionId,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        style: Option<ButtonStyle>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        confirm: Option<Confirm>,
+        /// Page opened in the browser when clicked. The click is still sent as an action.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    StaticSelect {
+        action_id: ActionId,
+        options: Vec<SelectOption>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initial_option: Option<SelectOption>,
+    },
+    PlainTextInput {
+        action_id: ActionId,
+        #[serde(default)]
+        multiline: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initial_value: Option<String>,
+    },
+    /// A menu of up to five options behind a "more" button.
+    Overflow {
+        action_id: ActionId,
+        options: Vec<SelectOption>,
+    },
+    #[serde(rename = "datetimepicker")]
+    DatetimePicker {
+        action_id: ActionId,
+        /// Unix time in seconds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initial_date_time: Option<i64>,
+    },
+    /// An element type not used by this application.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Block {
+    Header {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_id: Option<BlockId>,
+        text: Text,
+    },
+    Section {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_id: Option<BlockId>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<Text>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        accessory: Option<Element>,
+        /// Whether long text is always shown in full rather than folded behind "see more".
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expand: Option<bool>,
+    },
+    Divider {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_id: Option<BlockId>,
+    },
+    Context {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_id: Option<BlockId>,
+        elements: Vec<Text>,
+    },
+    Actions {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_id: Option<BlockId>,
+        elements: Vec<Element>,
+    },
+    Input {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_id: Option<BlockId>,
+        label: Text,
+        element: Element,
+        #[serde(default)]
+        optional: bool,
+    },
+    /// A block type not used by this application.
+    #[serde(other)]
+    Other,
+}
+
+impl Block {
+    pub(crate) fn section(block_id: Option<BlockId>, text: Text) -> Self {
+        Self::Section {
+            block_id,
+            text: Some(text),
+            accessory: None,
+            expand: None,
+        }
+    }
+
+    pub(crate) fn context(block_id: Option<BlockId>, text: Text) -> Self {
+        Self::Context {
+            block_id,
+            elements: vec![text],
+        }
+    }
+
+    pub(crate) fn divider(block_id: Option<BlockId>) -> Self {
+        Self::Divider { block_id }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "modal")]
+pub(crate) struct Modal {
+    pub(crate) callback_id: CallbackId,
+    #[serde(default)]
+    pub(crate) private_metadata: String,
+    pub(crate) title: Text,
+    pub(crate) blocks: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) close: Option<Text>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) submit: Option<Text>,
+}
+
+/// The Home tab of the app, published per user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "home")]
+pub(crate) struct HomeView {
+    pub(crate) blocks: Vec<Block>,
+}
+
+/// State of an input element reported in `view.state.values`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ElementState {
+    StaticSelect {
+        selected_option: Option<SelectOption>,
+    },
+    PlainTextInput {
+        value: Option<String>,
+    },
+    #[serde(rename = "datetimepicker")]
+    DatetimePicker {
+        selected_date_time: Option<i64>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// `view.state.values` of a view submission, keyed by block id and then by action id.
+pub(crate) type StateValues = HashMap<BlockId, HashMap<ActionId, ElementState>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ResponseType {
+    /// Visible only to the user who ran the command.
+    Ephemeral,
+    InChannel,
+}
+
+/// Response to a slash command, written in mrkdwn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CommandResponse {
+    pub(crate) response_type: ResponseType,
+    pub(crate) text: String,
+}
+
+/// Response to a view submission.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "response_action", rename_all = "snake_case")]
+pub(crate) enum ResponseAction {
+    Update {
+        view: Modal,
+    },
+    Clear,
+    /// Shows the messages under the input blocks with the given ids.
+    Errors {
+        errors: HashMap<BlockId, String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_ids_and_types() {
+        let blocks: Vec<Block> = serde_json::from_value(serde_json::json!([
+            {
+                "type": "section",
+                "block_id": "Xy1z",
+                "text": { "type": "mrkdwn", "text": "hello", "verbatim": false }
+            },
+            {
+                "type": "image",
+                "image_url": "https://example.com/image.png",
+                "alt_text": "image"
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            blocks,
+            vec![
+                Block::section(Some(BlockId::Unknown), Text::mrkdwn("hello")),
+                Block::Other
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_values() {
+        let values: StateValues = serde_json::from_value(serde_json::json!({
+            "severity": {
+                "severity": { "type": "static_select", "selected_option": null }
+            },
+            "reason": {
+                "reason": { "type": "plain_text_input", "value": "because" }
+            },
+            "snooze_until": {
+                "snooze_until": { "type": "datetimepicker", "selected_date_time": 1735689600 }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            values[&BlockId::Severity][&ActionId::Severity],
+            ElementState::StaticSelect {
+                selected_option: None
+            }
+        );
+        assert_eq!(
+            values[&BlockId::Reason][&ActionId::Reason],
+            ElementState::PlainTextInput {
+                value: Some("because".into())
+            }
+        );
+        assert_eq!(
+            values[&BlockId::SnoozeUntil][&ActionId::SnoozeUntil],
+            ElementState::DatetimePicker {
+                selected_date_time: Some(1735689600)
+            }
+        );
+    }
+}
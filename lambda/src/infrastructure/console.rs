@@ -0,0 +1,111 @@
+//! Links into the CloudWatch console for the log an alert was raised for.
+//!
+//! The console keeps its state in the URL fragment with its own escaping, so the links are
+//! built by hand rather than with a URL library.
+
+use crate::domain::value_objects::Timestamp;
+use chrono::{DateTime, Utc};
+
+/// Time before and after the event covered by the links.
+const WINDOW: chrono::Duration = chrono::Duration::minutes(5);
+
+const INSIGHTS_QUERY: &str =
+    "fields @timestamp, @logStream, @message\n| sort @timestamp asc\n| limit 1000";
+
+/// Returns the link to the events of the log stream around the time of the event.
+pub(crate) fn log_events_url(
+    region: &str,
+    log_group: &str,
+    log_stream: &str,
+    time: &Timestamp,
+) -> String {
+    format!(
+        "{}#logsV2:log-groups/log-group/{}/log-events/{}$3Fstart$3D{}$26end$3D{}",
+        base_url(region),
+        escape_path(log_group),
+        escape_path(log_stream),
+        time.add(-WINDOW).as_secs() * 1000,
+        time.add(WINDOW).as_secs() * 1000,
+    )
+}
+
+/// Returns the link to a Logs Insights query over the log group around the time of the
+/// event.
+pub(crate) fn logs_insights_url(region: &str, log_group: &str, time: &Timestamp) -> String {
+    format!(
+        "{}#logsV2:logs-insights$3FqueryDetail$3D~(end~'{}~start~'{}~timeType~'ABSOLUTE~tz~'UTC~editorString~'{}~source~(~'{}))",
+        base_url(region),
+        escape_query_detail(&format_time(&time.add(WINDOW))),
+        escape_query_detail(&format_time(&time.add(-WINDOW))),
+        escape_query_detail(INSIGHTS_QUERY),
+        escape_query_detail(log_group),
+    )
+}
+
+fn base_url(region: &str) -> String {
+    format!(
+        "https://{}.console.aws.amazon.com/cloudwatch/home?region={}",
+        region, region
+    )
+}
+
+fn format_time(time: &Timestamp) -> String {
+    DateTime::<Utc>::from_timestamp(time.as_secs(), 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%S.000Z")
+        .to_string()
+}
+
+/// Escapes a path segment of the fragment, which the console decodes twice.
+fn escape_path(value: &str) -> String {
+    percent_encode(value).replace('%', "$25")
+}
+
+/// Escapes a value of the Logs Insights query detail, where `*` stands for `%`.
+fn escape_query_detail(value: &str) -> String {
+    percent_encode(value).replace('%', "*")
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let time = Timestamp::from_secs(1_735_689_600);
+
+        assert_eq!(
+            log_events_url(
+                "ap-northeast-1",
+                "/aws/lambda/my-function",
+                "2025/01/01/[$LATEST]abc",
+                &time
+            ),
+            "https://ap-northeast-1.console.aws.amazon.com/cloudwatch/home?region=ap-northeast-1\
+            #logsV2:log-groups/log-group/$252Faws$252Flambda$252Fmy-function\
+            /log-events/2025$252F01$252F01$252F$255B$2524LATEST$255Dabc\
+            $3Fstart$3D1735689300000$26end$3D1735689900000"
+        );
+        assert_eq!(
+            logs_insights_url("ap-northeast-1", "/aws/lambda/my-function", &time),
+            "https://ap-northeast-1.console.aws.amazon.com/cloudwatch/home?region=ap-northeast-1\
+            #logsV2:logs-insights$3FqueryDetail$3D~(end~'2025-01-01T00*3A05*3A00.000Z\
+            ~start~'2024-12-31T23*3A55*3A00.000Z~timeType~'ABSOLUTE~tz~'UTC\
+            ~editorString~'fields*20*40timestamp*2C*20*40logStream*2C*20*40message*0A*7C*20sort*20*40timestamp*20asc*0A*7C*20limit*201000\
+            ~source~(~'*2Faws*2Flambda*2Fmy-function))"
+        );
+    }
+}
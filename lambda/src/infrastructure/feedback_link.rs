@@ -0,0 +1,98 @@
+//! Signed links to the feedback page of the collector, sent by the sinks that cannot take
+//! feedback themselves.
+//!
+//! A link carries the decision id and an expiry signed with a secret shared by the
+//! notifier and the collector, so that the page accepts feedback only from recipients of
+//! the alert, and only for a while.
+
+use crate::domain::value_objects::{DecisionId, Timestamp};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use typed_builder::TypedBuilder;
+
+/// How long a link accepts feedback unless configured otherwise.
+const DEFAULT_TTL: chrono::Duration = chrono::Duration::days(7);
+
+/// Query parameters of a link, which the page also posts back with the feedback.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct FeedbackLinkQuery {
+    pub(crate) decision_id: String,
+    pub(crate) expires: i64,
+    pub(crate) signature: String,
+}
+
+#[derive(Clone, TypedBuilder)]
+pub struct FeedbackLinks {
+    secret: String,
+    /// URL of the feedback page. Only needed to make links.
+    #[builder(default)]
+    base_url: String,
+    #[builder(default = DEFAULT_TTL)]
+    ttl: chrono::Duration,
+}
+
+impl FeedbackLinks {
+    /// Returns a link to the feedback page for the decision, valid from `now` for the TTL.
+    pub(crate) fn url(&self, decision_id: &DecisionId, now: &Timestamp) -> String {
+        let decision_id = decision_id.to_string();
+        let expires = now.add(self.ttl).as_secs();
+        format!(
+            "{}?decision_id={}&expires={}&signature={}",
+            self.base_url,
+            decision_id,
+            expires,
+            self.sign(&decision_id, expires)
+        )
+    }
+
+    /// Returns whether the link was made with the secret and has not expired.
+    pub(crate) fn verify(&self, query: &FeedbackLinkQuery, now: &Timestamp) -> bool {
+        if query.expires < now.as_secs() {
+            return false;
+        }
+        let signature = self.sign(&query.decision_id, query.expires);
+        bool::from(query.signature.as_bytes().ct_eq(signature.as_bytes()))
+    }
+
+    fn sign(&self, decision_id: &str, expires: i64) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).expect("HMAC takes any key");
+        mac.update(format!("{}:{}", decision_id, expires).as_bytes());
+        format!("{:x}", mac.finalize().into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback_links() {
+        let links = FeedbackLinks::builder()
+            .secret("secret".into())
+            .base_url("https://example.com/feedback-page".into())
+            .build();
+        let decision_id = DecisionId::new();
+        let now = Timestamp::from_secs(1_735_689_600);
+
+        let url = links.url(&decision_id, &now);
+        let query = url.split_once('?').unwrap().1;
+        let query: FeedbackLinkQuery = serde_urlencoded::from_str(query).unwrap();
+        assert_eq!(query.decision_id, decision_id.to_string());
+        assert!(links.verify(&query, &now));
+
+        // Expired
+        assert!(!links.verify(&query, &now.add(chrono::Duration::days(8))));
+        // Tampered
+        let other = FeedbackLinkQuery {
+            decision_id: DecisionId::new().to_string(),
+            ..query.clone()
+        };
+        assert!(!links.verify(&other, &now));
+        // Signed with another secret
+        let links = FeedbackLinks::builder().secret("other".into()).build();
+        assert!(!links.verify(&query, &now));
+    }
+}
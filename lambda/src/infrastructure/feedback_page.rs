@@ -0,0 +1,141 @@
+//! HTML of the feedback page linked from the alerts sent outside Slack, and of the other
+//! pages the collector serves to browsers.
+
+use crate::domain::entities::Decision;
+use crate::domain::value_objects::{Locale, Severity};
+use crate::infrastructure::feedback_link::FeedbackLinkQuery;
+use crate::infrastructure::i18n::catalog;
+
+/// Returns the page showing the alert with a form to send feedback on it. The form posts
+/// the link's parameters back so that the submission is checked like the link.
+pub(crate) fn make_feedback_page(
+    locale: Locale,
+    decision: &Decision,
+    query: &FeedbackLinkQuery,
+) -> String {
+    let catalog = catalog(locale);
+    let hidden = |name: &str, value: &str| {
+        format!(
+            r#"<input type="hidden" name="{}" value="{}">"#,
+            name,
+            escape_html(value)
+        )
+    };
+    let severities = [
+        Severity::Critical,
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+    ]
+    .iter()
+    .map(|v| format!(r#"<option value="{}">{}</option>"#, v, v))
+    .collect::<String>();
+    let explanation = decision
+        .explanation()
+        .map(|v| {
+            format!(
+                "<p><b>{}</b>: {}</p>",
+                catalog.explanation_header,
+                escape_html(v)
+            )
+        })
+        .unwrap_or_default();
+
+    let body = format!(
+        r#"<h1>{title}</h1>
+<p><b>{log_group_label}</b>: {log_group}</p>
+<p><b>{severity_label}</b>: {severity}</p>
+{explanation}<pre>{message}</pre>
+<form method="post">
+{decision_id}{expires}{signature}
+<p><label>{needs_notification_label}
+<select name="needs_notification">
+<option value="false">{not_required}</option>
+<option value="true">{required}</option>
+</select></label></p>
+<p><label>{severity_label}
+<select name="severity"><option value="">-</option>{severities}</select></label></p>
+<p><label>{reason_label}<br><textarea name="reason" rows="4" cols="60"></textarea></label></p>
+<p><button type="submit">{submit}</button></p>
+</form>"#,
+        title = catalog.feedback_title,
+        log_group_label = catalog.log_group_label,
+        log_group = escape_html(decision.log_group()),
+        severity_label = catalog.severity_label,
+        severity = decision.severity(),
+        explanation = explanation,
+        message = escape_html(decision.message()),
+        decision_id = hidden("decision_id", &query.decision_id),
+        expires = hidden("expires", &query.expires.to_string()),
+        signature = hidden("signature", &query.signature),
+        needs_notification_label = catalog.needs_notification_label,
+        not_required = catalog.notification_not_required,
+        required = catalog.notification_required,
+        severities = severities,
+        reason_label = catalog.reason_label,
+        submit = catalog.submit,
+    );
+    make_page(locale, catalog.feedback_title, &body)
+}
+
+/// Returns a page with a single message, such as the result of a submission.
+pub(crate) fn make_message_page(locale: Locale, title: &str, message: &str) -> String {
+    make_page(locale, title, &format!("<p>{}</p>", escape_html(message)))
+}
+
+fn make_page(locale: Locale, title: &str, body: &str) -> String {
+    let lang = match locale {
+        Locale::Ja => "ja",
+        Locale::En => "en",
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="{}">
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>{}</title></head>
+<body>
+{}
+</body>
+</html>
+"#,
+        lang,
+        escape_html(title),
+        body
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::{DecisionId, Timestamp};
+
+    #[test]
+    fn test_make_feedback_page() {
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("<script>alert(1)</script>".into())
+            .needs_notification(true)
+            .severity(Severity::High)
+            .build();
+        let query = FeedbackLinkQuery {
+            decision_id: decision.id().to_string(),
+            expires: 1_735_689_600,
+            signature: "abc\"".into(),
+        };
+
+        let page = make_feedback_page(Locale::En, &decision, &query);
+        assert!(page.contains("<pre>&lt;script&gt;alert(1)&lt;/script&gt;</pre>"));
+        assert!(page.contains(r#"<input type="hidden" name="expires" value="1735689600">"#));
+        assert!(page.contains(r#"<input type="hidden" name="signature" value="abc&quot;">"#));
+        assert!(!page.contains("<script>"));
+    }
+}
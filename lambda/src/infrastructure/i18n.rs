@@ -0,0 +1,590 @@
+//! Message catalog for the texts shown in Slack.
+//!
+//! Adding a language means adding a `Locale` variant and a `Catalog` for it.
+
+use crate::domain::value_objects::{Locale, Severity};
+use crate::util::format_duration;
+use std::collections::HashMap;
+
+pub(crate) struct Catalog {
+    /// Name of the language given to the model when it explains its judgement.
+    pub(crate) language: &'static str,
+    pub(crate) alert_header: &'static str,
+    /// Name of a severity in alert headers and the feedback modal.
+    pub(crate) severity_name: fn(severity: Severity) -> &'static str,
+    pub(crate) alert_fallback: fn(severity: Severity, log_group: &str) -> String,
+    pub(crate) log_group_header: &'static str,
+    pub(crate) message_header: &'static str,
+    pub(crate) log_source: fn(log_stream: &str, time: i64, account: &str) -> String,
+    pub(crate) open_in_cloudwatch: &'static str,
+    pub(crate) open_logs_insights: &'static str,
+    pub(crate) log_context_header: &'static str,
+    /// Stands in for the alerted log between the logs written around it.
+    pub(crate) log_context_marker: &'static str,
+    /// Appended to a text cut to fit in a block.
+    pub(crate) truncated: &'static str,
+    /// Posted with the full log uploaded as a file when the alert had to truncate it.
+    pub(crate) full_log_comment: &'static str,
+    pub(crate) explanation_header: &'static str,
+    pub(crate) feedback_button: &'static str,
+    pub(crate) feedback_done: &'static str,
+    pub(crate) notification_required: &'static str,
+    pub(crate) notification_not_required: &'static str,
+    pub(crate) rule_review_header: &'static str,
+    pub(crate) rule_context: fn(verdict: &str, source_count: usize) -> String,
+    pub(crate) approve: &'static str,
+    pub(crate) reject: &'static str,
+    pub(crate) rule_active: &'static str,
+    pub(crate) rule_rejected: &'static str,
+    pub(crate) rule_pending: &'static str,
+    pub(crate) feedback_title: &'static str,
+    pub(crate) needs_notification_label: &'static str,
+    pub(crate) severity_label: &'static str,
+    pub(crate) reason_label: &'static str,
+    pub(crate) cancel: &'static str,
+    pub(crate) submit: &'static str,
+    pub(crate) conflict_title: &'static str,
+    pub(crate) conflict_header: fn(count: usize) -> String,
+    /// Verdict and author of a feedback listed in a modal.
+    pub(crate) feedback_context: fn(verdict: &str, author: &str) -> String,
+    pub(crate) no_explanation: &'static str,
+    pub(crate) similar_feedback_header: &'static str,
+    pub(crate) no_similar_feedback: &'static str,
+    pub(crate) unknown_user: &'static str,
+    pub(crate) resolution_label: &'static str,
+    pub(crate) resolution_replace: &'static str,
+    pub(crate) resolution_retire: &'static str,
+    pub(crate) resolution_cancel: &'static str,
+    pub(crate) confirm: &'static str,
+    pub(crate) notification_label: &'static str,
+    pub(crate) retired: &'static str,
+    pub(crate) command_usage: &'static str,
+    pub(crate) command_failed: &'static str,
+    /// Answer to commands on log groups routed to another workspace.
+    pub(crate) command_forbidden: fn(log_group: &str) -> String,
+    pub(crate) status_model: fn(model_id: &str, top_p: f32, temperature: f32) -> String,
+    pub(crate) status_routes: fn(count: usize) -> String,
+    pub(crate) status_mutes: &'static str,
+    pub(crate) status_no_mutes: &'static str,
+    pub(crate) mute_entry: fn(log_group: &str, until: i64) -> String,
+    /// Counts are given for the last 24 hours and the last 7 days.
+    pub(crate) stats: fn(
+        log_group: &str,
+        alerted: [usize; 2],
+        suppressed: [usize; 2],
+        muted: [usize; 2],
+    ) -> String,
+    pub(crate) feedback_list_header: fn(log_group: &str, count: usize) -> String,
+    pub(crate) feedback_list_empty: fn(log_group: &str) -> String,
+    pub(crate) test_result: fn(verdict: &str, severity: Severity) -> String,
+    pub(crate) muted: fn(log_group: &str, until: i64) -> String,
+    pub(crate) snooze_for: fn(hours: i64) -> String,
+    pub(crate) snooze_custom: &'static str,
+    pub(crate) snooze_status: fn(until: i64, user: &str, whole_log_group: bool) -> String,
+    pub(crate) release_snooze: &'static str,
+    pub(crate) snooze_title: &'static str,
+    pub(crate) snooze_until_label: &'static str,
+    pub(crate) snooze_scope_label: &'static str,
+    pub(crate) snooze_scope_message: &'static str,
+    pub(crate) snooze_scope_log_group: &'static str,
+    pub(crate) snooze: &'static str,
+    pub(crate) mention_usage: &'static str,
+    pub(crate) acknowledge: &'static str,
+    pub(crate) resolve: &'static str,
+    pub(crate) acknowledged_status: fn(user: &str, at: i64) -> String,
+    pub(crate) resolved_status: fn(user: &str, at: i64) -> String,
+    pub(crate) response_times: fn(
+        acknowledged: usize,
+        mtta_seconds: Option<i64>,
+        resolved: usize,
+        mttr_seconds: Option<i64>,
+    ) -> String,
+    pub(crate) home_log_groups_header: &'static str,
+    pub(crate) home_no_log_groups: &'static str,
+    pub(crate) home_counts: fn(alerted: usize, suppressed: usize, muted: usize) -> String,
+    pub(crate) home_feedback_header: &'static str,
+    pub(crate) home_no_feedback: &'static str,
+    pub(crate) edit: &'static str,
+    pub(crate) delete: &'static str,
+    pub(crate) delete_feedback_title: &'static str,
+    pub(crate) delete_feedback_text: &'static str,
+    pub(crate) edit_feedback_title: &'static str,
+    pub(crate) save: &'static str,
+    pub(crate) log_group_label: &'static str,
+    pub(crate) log_stream_label: &'static str,
+    pub(crate) time_label: &'static str,
+    pub(crate) message_label: &'static str,
+    /// Link to the feedback page in alerts sent outside Slack.
+    pub(crate) feedback_link: &'static str,
+    pub(crate) feedback_page_done: &'static str,
+    /// Shown when feedback sent from the page contradicts earlier feedback, which can only
+    /// be resolved in Slack.
+    pub(crate) feedback_page_conflict: &'static str,
+    pub(crate) feedback_link_invalid: &'static str,
+    pub(crate) install_title: &'static str,
+    pub(crate) installed: fn(team: &str) -> String,
+    pub(crate) install_cancelled: &'static str,
+    pub(crate) install_link_invalid: &'static str,
+    /// Told to the user when the follow-up of an interaction fails after it was accepted.
+    pub(crate) task_failed: &'static str,
+    pub(crate) input_missing: &'static str,
+    pub(crate) input_invalid: &'static str,
+    pub(crate) reason_required: &'static str,
+    pub(crate) reason_too_short: fn(length: usize) -> String,
+    pub(crate) time_in_past: &'static str,
+}
+
+impl Catalog {
+    pub(crate) fn verdict(&self, needs_notification: bool) -> &'static str {
+        if needs_notification {
+            self.notification_required
+        } else {
+            self.notification_not_required
+        }
+    }
+}
+
+static JA: Catalog = Catalog {
+    language: "Japanese",
+    alert_header: "エラーが発生しました",
+    severity_name: |severity| match severity {
+        Severity::Critical => "緊急",
+        Severity::High => "高",
+        Severity::Medium => "中",
+        Severity::Low => "低",
+    },
+    alert_fallback: |severity, log_group| {
+        format!("[{}] {} でエラーが発生しました", severity, log_group)
+    },
+    log_group_header: "*CloudWatch Logs ロググループ*",
+    message_header: "*ログメッセージ*",
+    log_source: |log_stream, time, account| {
+        format!(
+            "ログストリーム: `{}` ・ 発生日時: <!date^{}^{{date_num}} {{time_secs}}|{}> ・ アカウント: {}",
+            log_stream, time, time, account
+        )
+    },
+    open_in_cloudwatch: "CloudWatchで開く",
+    open_logs_insights: "前後のログをLogs Insightsで検索",
+    log_context_header: "*前後のログ*",
+    log_context_marker: "───── 通知されたログ ─────",
+    truncated: "…(省略されました)",
+    full_log_comment: "ログメッセージの全文です。",
+    explanation_header: "判定理由",
+    feedback_button: "フィードバック",
+    feedback_done: "_フィードバック済み_",
+    notification_required: "必要",
+    notification_not_required: "不要",
+    rule_review_header: ":memo: フィードバックから生成されたルールのレビュー",
+    rule_context: |verdict, source_count| {
+        format!(
+            "通知: *{}* ・ 元のフィードバック: {} 件",
+            verdict, source_count
+        )
+    },
+    approve: "承認",
+    reject: "却下",
+    rule_active: "承認済み",
+    rule_rejected: "却下済み",
+    rule_pending: "レビュー待ち",
+    feedback_title: "フィードバック",
+    needs_notification_label: "通知が必要ですか？",
+    severity_label: "重要度",
+    reason_label: "理由",
+    cancel: "キャンセル",
+    submit: "送信",
+    conflict_title: "フィードバックの矛盾",
+    conflict_header: |count| {
+        format!(
+            "*類似するログに対して逆の判定をしたフィードバックが {} 件あります。*",
+            count
+        )
+    },
+    feedback_context: |verdict, author| format!("通知: *{}* ・ 登録者: {}", verdict, author),
+    no_explanation: "この通知の判定理由は記録されていません。",
+    similar_feedback_header: "似ているログへのフィードバック",
+    no_similar_feedback: "このロググループにはまだフィードバックがありません。",
+    unknown_user: "不明",
+    resolution_label: "既存のフィードバックをどうしますか？",
+    resolution_replace: "削除して置き換える",
+    resolution_retire: "無効化して残す",
+    resolution_cancel: "送信を取り消す",
+    confirm: "確定",
+    notification_label: "通知",
+    retired: "(無効)",
+    command_usage: "*使い方*\n\
+        `/alert-filter status`: 現在の設定と使用中のモデルを表示します\n\
+        `/alert-filter stats <ロググループ>`: 通知と抑制の件数を表示します\n\
+        `/alert-filter feedback list <ロググループ>`: フィードバックの一覧を表示します\n\
+        `/alert-filter test <ロググループ> <メッセージ>`: 現在のフィードバックで判定を試します\n\
+        `/alert-filter mute <ロググループ> <期間>`: 通知をミュートします(期間の例: `30m`、`4h`、`7d`)",
+    command_failed: "コマンドの実行に失敗しました。",
+    command_forbidden: |log_group| {
+        format!("{} はこのワークスペースのロググループではありません。", log_group)
+    },
+    status_model: |model_id, top_p, temperature| {
+        format!(
+            "*モデル*: `{}` (top_p: {}, temperature: {})",
+            model_id, top_p, temperature
+        )
+    },
+    status_routes: |count| format!("*ルート*: {} 件", count),
+    status_mutes: "*ミュート中のロググループ*",
+    status_no_mutes: "ミュート中のロググループはありません。",
+    mute_entry: |log_group, until| {
+        format!(
+            "• `{}` (<!date^{}^{{date_short}} {{time}}|{}> まで)",
+            log_group, until, until
+        )
+    },
+    stats: |log_group, alerted, suppressed, muted| {
+        format!(
+            "*`{}` の件数* (直近24時間 / 7日間)\n通知: {} / {}\n抑制: {} / {}\nミュート: {} / {}",
+            log_group, alerted[0], alerted[1], suppressed[0], suppressed[1], muted[0], muted[1]
+        )
+    },
+    feedback_list_header: |log_group, count| {
+        format!("*`{}` のフィードバック* ({} 件)", log_group, count)
+    },
+    feedback_list_empty: |log_group| format!("`{}` のフィードバックはありません。", log_group),
+    test_result: |verdict, severity| format!("判定: 通知 *{}* ・ 重要度: {}", verdict, severity),
+    muted: |log_group, until| {
+        format!(
+            "`{}` の通知を <!date^{}^{{date_short}} {{time}}|{}> までミュートしました。",
+            log_group, until, until
+        )
+    },
+    snooze_for: |hours| format!("{}時間スヌーズ", hours),
+    snooze_custom: "日時を指定してスヌーズ…",
+    snooze_status: |until, user, whole_log_group| {
+        format!(
+            ":zzz: <!date^{}^{{date_short}} {{time}}|{}> まで{}の通知をスヌーズ中 (<@{}>)",
+            until,
+            until,
+            if whole_log_group {
+                "ロググループ全体"
+            } else {
+                "同じ種類のログ"
+            },
+            user
+        )
+    },
+    release_snooze: "スヌーズを解除",
+    snooze_title: "スヌーズ",
+    snooze_until_label: "終了日時",
+    snooze_scope_label: "対象",
+    snooze_scope_message: "同じ種類のログ",
+    snooze_scope_log_group: "ロググループ全体",
+    snooze: "スヌーズ",
+    mention_usage: "ロググループとログメッセージを付けてメンションすると、現在のフィードバックで通知が必要か判定します。\n\
+        例: `@LLM Alert Filter /aws/lambda/my-function ERROR connection reset by peer`",
+    acknowledge: "確認",
+    resolve: "解決",
+    acknowledged_status: |user, at| {
+        format!(
+            ":eyes: <@{}> が確認しました (<!date^{}^{{date_short}} {{time}}|{}>)",
+            user, at, at
+        )
+    },
+    resolved_status: |user, at| {
+        format!(
+            ":white_check_mark: <@{}> が解決しました (<!date^{}^{{date_short}} {{time}}|{}>)",
+            user, at, at
+        )
+    },
+    response_times: |acknowledged, mtta_seconds, resolved, mttr_seconds| {
+        format!(
+            "MTTA: {} ({} 件) ・ MTTR: {} ({} 件)",
+            mtta_seconds.map(format_duration).unwrap_or("-".into()),
+            acknowledged,
+            mttr_seconds.map(format_duration).unwrap_or("-".into()),
+            resolved
+        )
+    },
+    home_log_groups_header: "フィードバックしたロググループ (直近7日間)",
+    home_no_log_groups: "通知にフィードバックを送ると、そのロググループの件数がここに表示されます。",
+    home_counts: |alerted, suppressed, muted| {
+        format!("通知: {} ・ 抑制: {} ・ ミュート: {}", alerted, suppressed, muted)
+    },
+    home_feedback_header: "最近のフィードバック",
+    home_no_feedback: "まだフィードバックはありません。",
+    edit: "編集",
+    delete: "削除",
+    delete_feedback_title: "フィードバックの削除",
+    delete_feedback_text: "このフィードバックを削除しますか?以降の判定では使われなくなります。",
+    edit_feedback_title: "フィードバックの編集",
+    save: "保存",
+    log_group_label: "ロググループ",
+    log_stream_label: "ログストリーム",
+    time_label: "発生日時",
+    message_label: "ログメッセージ",
+    feedback_link: "フィードバックを送る",
+    feedback_page_done: "フィードバックを受け付けました。",
+    feedback_page_conflict: "同じようなアラートへの以前のフィードバックと矛盾するため、受け付けられませんでした。Slackから確認してください。",
+    feedback_link_invalid: "リンクが無効か、有効期限が切れています。",
+    install_title: "インストール",
+    installed: |team| format!("{}にインストールしました。", team),
+    install_cancelled: "インストールを中止しました。",
+    install_link_invalid: "インストールの期限が切れています。もう一度やり直してください。",
+    task_failed: ":warning: 操作は記録されましたが、メッセージの更新に失敗しました。もう一度お試しください。",
+    input_missing: "入力してください。",
+    input_invalid: "入力された値が正しくありません。",
+    reason_required: "理由を入力してください。",
+    reason_too_short: |length| format!("理由は{}文字以上で入力してください。", length),
+    time_in_past: "現在より後の日時を指定してください。",
+};
+
+static EN: Catalog = Catalog {
+    language: "English",
+    alert_header: "An error occurred",
+    severity_name: |severity| match severity {
+        Severity::Critical => "Critical",
+        Severity::High => "High",
+        Severity::Medium => "Medium",
+        Severity::Low => "Low",
+    },
+    alert_fallback: |severity, log_group| {
+        format!("[{}] An error occurred in {}", severity, log_group)
+    },
+    log_group_header: "*CloudWatch Logs log group*",
+    message_header: "*Log message*",
+    log_source: |log_stream, time, account| {
+        format!(
+            "Log stream: `{}` ・ Time: <!date^{}^{{date_num}} {{time_secs}}|{}> ・ Account: {}",
+            log_stream, time, time, account
+        )
+    },
+    open_in_cloudwatch: "Open in CloudWatch",
+    open_logs_insights: "Logs Insights query around this time",
+    log_context_header: "*Surrounding logs*",
+    log_context_marker: "───── alerted log ─────",
+    truncated: "… (truncated)",
+    full_log_comment: "The full log message.",
+    explanation_header: "Reasoning",
+    feedback_button: "Feedback",
+    feedback_done: "_Feedback sent_",
+    notification_required: "Required",
+    notification_not_required: "Not required",
+    rule_review_header: ":memo: Review of a rule generated from feedback",
+    rule_context: |verdict, source_count| {
+        format!(
+            "Notification: *{}* ・ Source feedback: {}",
+            verdict, source_count
+        )
+    },
+    approve: "Approve",
+    reject: "Reject",
+    rule_active: "Approved",
+    rule_rejected: "Rejected",
+    rule_pending: "Pending review",
+    feedback_title: "Feedback",
+    needs_notification_label: "Is a notification needed?",
+    severity_label: "Severity",
+    reason_label: "Reason",
+    cancel: "Cancel",
+    submit: "Send",
+    conflict_title: "Conflicting feedback",
+    conflict_header: |count| {
+        format!(
+            "*{} feedback entries gave the opposite verdict for similar logs.*",
+            count
+        )
+    },
+    feedback_context: |verdict, author| {
+        format!("Notification: *{}* ・ Added by: {}", verdict, author)
+    },
+    no_explanation: "No reasoning was recorded for this alert.",
+    similar_feedback_header: "Feedback on similar logs",
+    no_similar_feedback: "There is no feedback for this log group yet.",
+    unknown_user: "Unknown",
+    resolution_label: "What should happen to the existing feedback?",
+    resolution_replace: "Delete and replace",
+    resolution_retire: "Retire and keep",
+    resolution_cancel: "Discard this feedback",
+    confirm: "Confirm",
+    notification_label: "Notification",
+    retired: "(retired)",
+    command_usage: "*Usage*\n\
+        `/alert-filter status`: Shows the current configuration and the model in use\n\
+        `/alert-filter stats <log group>`: Shows the alert and suppression counts\n\
+        `/alert-filter feedback list <log group>`: Lists the feedback\n\
+        `/alert-filter test <log group> <message>`: Tries a judgement with the current feedback\n\
+        `/alert-filter mute <log group> <duration>`: Mutes the alerts (e.g. `30m`, `4h`, `7d`)",
+    command_failed: "Failed to run the command.",
+    command_forbidden: |log_group| format!("{} is not a log group of this workspace.", log_group),
+    status_model: |model_id, top_p, temperature| {
+        format!(
+            "*Model*: `{}` (top_p: {}, temperature: {})",
+            model_id, top_p, temperature
+        )
+    },
+    status_routes: |count| format!("*Routes*: {}", count),
+    status_mutes: "*Muted log groups*",
+    status_no_mutes: "No log groups are muted.",
+    mute_entry: |log_group, until| {
+        format!(
+            "• `{}` (until <!date^{}^{{date_short}} {{time}}|{}>)",
+            log_group, until, until
+        )
+    },
+    stats: |log_group, alerted, suppressed, muted| {
+        format!(
+            "*Counts for `{}`* (last 24 hours / 7 days)\nAlerted: {} / {}\nSuppressed: {} / {}\nMuted: {} / {}",
+            log_group, alerted[0], alerted[1], suppressed[0], suppressed[1], muted[0], muted[1]
+        )
+    },
+    feedback_list_header: |log_group, count| format!("*Feedback for `{}`* ({})", log_group, count),
+    feedback_list_empty: |log_group| format!("There is no feedback for `{}`.", log_group),
+    test_result: |verdict, severity| {
+        format!(
+            "Verdict: notification *{}* ・ Severity: {}",
+            verdict, severity
+        )
+    },
+    muted: |log_group, until| {
+        format!(
+            "Muted the alerts of `{}` until <!date^{}^{{date_short}} {{time}}|{}>.",
+            log_group, until, until
+        )
+    },
+    snooze_for: |hours| format!("Snooze for {}h", hours),
+    snooze_custom: "Snooze until…",
+    snooze_status: |until, user, whole_log_group| {
+        format!(
+            ":zzz: Alerts for {} snoozed until <!date^{}^{{date_short}} {{time}}|{}> (<@{}>)",
+            if whole_log_group {
+                "the whole log group"
+            } else {
+                "this kind of log"
+            },
+            until,
+            until,
+            user
+        )
+    },
+    release_snooze: "Release snooze",
+    snooze_title: "Snooze",
+    snooze_until_label: "Until",
+    snooze_scope_label: "Scope",
+    snooze_scope_message: "This kind of log",
+    snooze_scope_log_group: "The whole log group",
+    snooze: "Snooze",
+    mention_usage: "Mention me with a log group and a log message to see whether it would be alerted with the current feedback.\n\
+        e.g. `@LLM Alert Filter /aws/lambda/my-function ERROR connection reset by peer`",
+    acknowledge: "Ack",
+    resolve: "Resolve",
+    acknowledged_status: |user, at| {
+        format!(
+            ":eyes: Acknowledged by <@{}> (<!date^{}^{{date_short}} {{time}}|{}>)",
+            user, at, at
+        )
+    },
+    resolved_status: |user, at| {
+        format!(
+            ":white_check_mark: Resolved by <@{}> (<!date^{}^{{date_short}} {{time}}|{}>)",
+            user, at, at
+        )
+    },
+    response_times: |acknowledged, mtta_seconds, resolved, mttr_seconds| {
+        format!(
+            "MTTA: {} ({} alerts) ・ MTTR: {} ({} alerts)",
+            mtta_seconds.map(format_duration).unwrap_or("-".into()),
+            acknowledged,
+            mttr_seconds.map(format_duration).unwrap_or("-".into()),
+            resolved
+        )
+    },
+    home_log_groups_header: "Your log groups (last 7 days)",
+    home_no_log_groups: "Log groups you give feedback on are counted here.",
+    home_counts: |alerted, suppressed, muted| {
+        format!(
+            "Alerted: {} ・ Suppressed: {} ・ Muted: {}",
+            alerted, suppressed, muted
+        )
+    },
+    home_feedback_header: "Your recent feedback",
+    home_no_feedback: "You have not given any feedback yet.",
+    edit: "Edit",
+    delete: "Delete",
+    delete_feedback_title: "Delete feedback",
+    delete_feedback_text: "Delete this feedback? It will no longer be used to judge logs.",
+    edit_feedback_title: "Edit feedback",
+    save: "Save",
+    log_group_label: "Log group",
+    log_stream_label: "Log stream",
+    time_label: "Time",
+    message_label: "Log message",
+    feedback_link: "Send feedback",
+    feedback_page_done: "Thank you, your feedback was recorded.",
+    feedback_page_conflict: "This feedback contradicts earlier feedback on similar alerts, so it was not recorded. Please resolve it from Slack.",
+    feedback_link_invalid: "This link is invalid or has expired.",
+    install_title: "Installation",
+    installed: |team| format!("The app was installed to {}.", team),
+    install_cancelled: "The installation was cancelled.",
+    install_link_invalid: "The installation has expired. Please start over.",
+    task_failed: ":warning: Your action was recorded, but the message could not be updated. Please try again.",
+    input_missing: "This field is required.",
+    input_invalid: "This value is not valid.",
+    reason_required: "Please enter a reason.",
+    reason_too_short: |length| format!("Please enter a reason of at least {} characters.", length),
+    time_in_past: "Please choose a time in the future.",
+};
+
+pub(crate) fn catalog(locale: Locale) -> &'static Catalog {
+    match locale {
+        Locale::Ja => &JA,
+        Locale::En => &EN,
+    }
+}
+
+/// Locale of the workspace and the channels that override it.
+#[derive(Clone, Debug, Default)]
+pub struct LocaleSettings {
+    default: Locale,
+    channels: HashMap<String, Locale>,
+}
+
+impl LocaleSettings {
+    /// Parses a locale code such as `en` and a JSON object mapping channel ids to locale codes.
+    pub fn new(
+        default: Option<&str>,
+        channels_json: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let default = default
+            .map(Locale::try_from)
+            .transpose()?
+            .unwrap_or_default();
+        let channels = match channels_json {
+            Some(json) => serde_json::from_str(json)?,
+            None => HashMap::new(),
+        };
+        Ok(Self { default, channels })
+    }
+
+    /// Returns the locale of views that do not belong to a channel, such as the Home tab.
+    pub(crate) fn default_locale(&self) -> Locale {
+        self.default
+    }
+
+    pub(crate) fn locale(&self, channel_id: &str) -> Locale {
+        self.channels
+            .get(channel_id)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_settings() {
+        let settings = LocaleSettings::new(Some("en"), Some(r#"{"C123": "ja"}"#)).unwrap();
+
+        assert_eq!(settings.locale("C123"), Locale::Ja);
+        assert_eq!(settings.locale("C456"), Locale::En);
+        assert_eq!(LocaleSettings::default().locale("C456"), Locale::Ja);
+        assert!(LocaleSettings::new(Some("fr"), None).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Reads the events around an alerted event back from CloudWatch Logs.
+
+use crate::domain::entities::LogEvent;
+use crate::domain::repositories::LogEventRepository;
+use std::error::Error;
+use typed_builder::TypedBuilder;
+
+/// Pages read at most per request, as a busy stream can return many sparse pages.
+const MAX_PAGES: usize = 5;
+
+#[derive(Clone, TypedBuilder)]
+pub struct LogEventRepositoryImpl {
+    client: aws_sdk_cloudwatchlogs::Client,
+}
+
+impl LogEventRepository for LogEventRepositoryImpl {
+    async fn list_events_before(
+        &self,
+        log_group: &str,
+        log_stream: &str,
+        end: i64,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut next_token: Option<String> = None;
+
+        for _ in 0..MAX_PAGES {
+            let resp = self
+                .client
+                .get_log_events()
+                .log_group_name(log_group)
+                .log_stream_name(log_stream)
+                .end_time(end)
+                .start_from_head(false)
+                .limit(limit.min(i32::MAX as usize) as i32)
+                .set_next_token(next_token.clone())
+                .send()
+                .await?;
+
+            let mut events: Vec<LogEvent> = resp
+                .events
+                .unwrap_or_default()
+                .into_iter()
+                .map(|v| LogEvent {
+                    id: None,
+                    timestamp: v.timestamp.unwrap_or_default(),
+                    message: v.message.unwrap_or_default(),
+                })
+                .collect();
+            // Pages go backwards in time, so each one is older than the events read so far.
+            events.append(&mut results);
+            results = events;
+
+            // The same token is returned once the start of the stream is reached.
+            if results.len() >= limit || resp.next_backward_token == next_token {
+                break;
+            }
+            next_token = resp.next_backward_token;
+        }
+
+        let excess = results.len().saturating_sub(limit);
+        Ok(results.split_off(excess))
+    }
+
+    async fn list_events_from(
+        &self,
+        log_group: &str,
+        log_stream: &str,
+        start: i64,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut next_token = None;
+
+        for _ in 0..MAX_PAGES {
+            let resp = self
+                .client
+                .filter_log_events()
+                .log_group_name(log_group)
+                .log_stream_names(log_stream)
+                .start_time(start)
+                .limit(limit.min(i32::MAX as usize) as i32)
+                .set_next_token(next_token)
+                .send()
+                .await?;
+
+            results.extend(
+                resp.events
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|v| LogEvent {
+                        id: v.event_id,
+                        timestamp: v.timestamp.unwrap_or_default(),
+                        message: v.message.unwrap_or_default(),
+                    }),
+            );
+
+            next_token = resp.next_token;
+            if results.len() >= limit || next_token.is_none() {
+                break;
+            }
+        }
+
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+/// Events of a single stream kept in memory, standing in for CloudWatch Logs in tests.
+#[cfg(test)]
+pub(crate) struct FakeLogEventRepository {
+    pub(crate) events: Vec<LogEvent>,
+}
+
+#[cfg(test)]
+impl LogEventRepository for FakeLogEventRepository {
+    async fn list_events_before(
+        &self,
+        _log_group: &str,
+        _log_stream: &str,
+        end: i64,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>, Box<dyn Error>> {
+        let events: Vec<LogEvent> = self
+            .events
+            .iter()
+            .filter(|v| v.timestamp < end)
+            .map(|v| LogEvent {
+                id: None,
+                ..v.clone()
+            })
+            .collect();
+        let excess = events.len().saturating_sub(limit);
+        Ok(events[excess..].to_vec())
+    }
+
+    async fn list_events_from(
+        &self,
+        _log_group: &str,
+        _log_stream: &str,
+        start: i64,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>, Box<dyn Error>> {
+        Ok(self
+            .events
+            .iter()
+            .filter(|v| v.timestamp >= start)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
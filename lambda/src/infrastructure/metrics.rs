@@ -0,0 +1,54 @@
+//! Metrics written to the logs in the CloudWatch embedded metric format, from which
+//! CloudWatch extracts them without calling its API.
+
+use serde_json::{json, Value};
+
+const NAMESPACE: &str = "LlmAlertFilter";
+
+/// Counts one occurrence of the metric for the value of a dimension.
+pub(crate) fn count(name: &str, dimension: &str, value: &str) {
+    // Printed as is rather than traced, since CloudWatch only reads lines of plain JSON.
+    println!(
+        "{}",
+        make_count(
+            name,
+            dimension,
+            value,
+            chrono::Utc::now().timestamp_millis()
+        )
+    );
+}
+
+fn make_count(name: &str, dimension: &str, value: &str, timestamp_millis: i64) -> Value {
+    let mut record = json!({
+        "_aws": {
+            "Timestamp": timestamp_millis,
+            "CloudWatchMetrics": [{
+                "Namespace": NAMESPACE,
+                "Dimensions": [[dimension]],
+                "Metrics": [{ "Name": name, "Unit": "Count" }]
+            }]
+        }
+    });
+    record[dimension] = json!(value);
+    record[name] = json!(1);
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_count() {
+        let record = make_count("Rejected", "Reason", "stale", 1_735_689_600_000);
+
+        assert_eq!(record["_aws"]["Timestamp"], 1_735_689_600_000_i64);
+        let directive = &record["_aws"]["CloudWatchMetrics"][0];
+        assert_eq!(directive["Namespace"], NAMESPACE);
+        assert_eq!(directive["Dimensions"], json!([["Reason"]]));
+        assert_eq!(directive["Metrics"][0]["Name"], "Rejected");
+        assert_eq!(record["Reason"], "stale");
+        assert_eq!(record["Rejected"], 1);
+    }
+}
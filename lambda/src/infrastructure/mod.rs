@@ -1,4 +1,17 @@
 pub mod bedrock;
+pub(crate) mod block_kit;
+pub(crate) mod console;
+pub mod feedback_link;
+pub(crate) mod feedback_page;
+pub mod i18n;
+pub mod log_events;
+pub(crate) mod metrics;
+pub mod oauth;
 pub mod repositories_impl;
 pub mod secrets;
+pub mod sinks;
 pub mod slack;
+pub mod slack_api;
+pub mod tasks;
+pub mod token_cipher;
+pub mod validation;
@@ -0,0 +1,125 @@
+//! Settings of the Slack OAuth v2 flow that installs the app to other workspaces.
+//!
+//! The `state` parameter carries an expiry and a random nonce signed with the client
+//! secret. It is also set in a cookie when the flow starts, so that the redirect is only
+//! accepted in the browser that started it.
+
+use crate::domain::value_objects::Timestamp;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+const AUTHORIZE_URL: &str = "https://slack.com/oauth/v2/authorize";
+
+/// Bot scopes requested on installation, the same as in the app manifest.
+const DEFAULT_SCOPES: [&str; 6] = [
+    "app_mentions:read",
+    "channels:history",
+    "chat:write",
+    "commands",
+    "chat:write.public",
+    "files:write",
+];
+
+/// How long an installation may take from the install page to the redirect.
+const STATE_TTL: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Name of the cookie binding the state to the browser.
+pub(crate) const STATE_COOKIE: &str = "slack_oauth_state";
+
+#[derive(Clone, TypedBuilder)]
+pub struct OAuthSettings {
+    client_id: String,
+    client_secret: String,
+    /// URL of the redirect route, which must also be listed in the app's settings.
+    redirect_url: String,
+    #[builder(default = DEFAULT_SCOPES.iter().map(|v| v.to_string()).collect())]
+    scopes: Vec<String>,
+}
+
+impl OAuthSettings {
+    pub(crate) fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    pub(crate) fn client_secret(&self) -> &str {
+        &self.client_secret
+    }
+
+    pub(crate) fn redirect_url(&self) -> &str {
+        &self.redirect_url
+    }
+
+    /// Returns a new state valid from `now` for a while.
+    pub(crate) fn new_state(&self, now: &Timestamp) -> String {
+        let payload = format!(
+            "{}.{}",
+            now.add(STATE_TTL).as_secs(),
+            Uuid::new_v4().simple()
+        );
+        format!("{}.{}", payload, self.sign(&payload))
+    }
+
+    /// Returns whether the state was made with the client secret and has not expired.
+    pub(crate) fn verify_state(&self, state: &str, now: &Timestamp) -> bool {
+        let Some((payload, signature)) = state.rsplit_once('.') else {
+            return false;
+        };
+        let Some(expires) = payload
+            .split_once('.')
+            .and_then(|(v, _)| v.parse::<i64>().ok())
+        else {
+            return false;
+        };
+        expires >= now.as_secs()
+            && bool::from(signature.as_bytes().ct_eq(self.sign(payload).as_bytes()))
+    }
+
+    /// Returns the Slack page asking the user to install the app.
+    pub(crate) fn authorize_url(&self, state: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let query = serde_urlencoded::to_string([
+            ("client_id", self.client_id.as_str()),
+            ("scope", &self.scopes.join(",")),
+            ("redirect_uri", &self.redirect_url),
+            ("state", state),
+        ])?;
+        Ok(format!("{}?{}", AUTHORIZE_URL, query))
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.client_secret.as_bytes())
+            .expect("HMAC takes any key");
+        mac.update(payload.as_bytes());
+        format!("{:x}", mac.finalize().into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oauth_state() {
+        let settings = OAuthSettings::builder()
+            .client_id("123.456".into())
+            .client_secret("secret".into())
+            .redirect_url("https://example.com/slack/oauth_redirect".into())
+            .build();
+        let now = Timestamp::from_secs(1_735_689_600);
+        let state = settings.new_state(&now);
+
+        assert!(settings.verify_state(&state, &now));
+        assert!(!settings.verify_state(&state, &now.add(chrono::Duration::minutes(11))));
+        assert!(!settings.verify_state(&format!("{}0", state), &now));
+        assert!(!settings.verify_state("", &now));
+        assert_ne!(state, settings.new_state(&now));
+
+        let url = settings.authorize_url(&state).unwrap();
+        assert!(url.starts_with(
+            "https://slack.com/oauth/v2/authorize?client_id=123.456&scope=app_mentions%3Aread%2C"
+        ));
+        assert!(url.contains("redirect_uri=https%3A%2F%2Fexample.com%2Fslack%2Foauth_redirect"));
+    }
+}
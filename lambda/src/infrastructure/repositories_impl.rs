@@ -1,9 +1,19 @@
-use crate::domain::entities::Feedback;
-use crate::domain::repositories::FeedbackRepository;
+use crate::domain::entities::{Decision, Feedback, Installation, Mute, Route, Rule};
+use crate::domain::repositories::{
+    DecisionRepository, FeedbackRepository, InstallationRepository, MuteRepository,
+    NonceRepository, RouteRepository, RuleRepository,
+};
+use crate::domain::value_objects::{
+    DecisionId, FeedbackId, MuteId, RuleId, RuleStatus, Severity, Timestamp,
+};
+use crate::infrastructure::token_cipher::TokenCipher;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
-use serde_dynamo::{from_items, to_item};
+use serde::{Deserialize, Serialize};
+use serde_dynamo::{from_item, from_items, to_attribute_value, to_item};
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
+use std::sync::{Arc, Mutex};
 use typed_builder::TypedBuilder;
 
 #[derive(Clone, TypedBuilder)]
@@ -26,6 +36,45 @@ impl FeedbackRepository for FeedbackRepositoryImpl {
         Ok(())
     }
 
+    async fn create_feedback(&self, feedback: Feedback) -> Result<bool, Box<dyn Error>> {
+        let item = to_item(feedback)?;
+
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(id)")
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn get_feedback(&self, id: &FeedbackId) -> Result<Option<Feedback>, Box<dyn Error>> {
+        let resp = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .send()
+            .await?;
+
+        match resp.item {
+            Some(item) => Ok(Some(from_item(item)?)),
+            None => Ok(None),
+        }
+    }
+
     async fn list_feedback_by_log_group(
         &self,
         log_group: &str,
@@ -64,4 +113,725 @@ impl FeedbackRepository for FeedbackRepositoryImpl {
 
         Ok(results)
     }
+
+    async fn list_feedback_by_user(&self, user_id: &str) -> Result<Vec<Feedback>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("user_id_index")
+                .key_condition_expression("user_id = :user_id")
+                .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+                .scan_index_forward(false)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let feedback: Vec<Feedback> = from_items(items)?;
+                results.extend(feedback);
+
+                match &resp.last_evaluated_key {
+                    Some(last_evaluated_key) => {
+                        exclusive_start_key = Some(last_evaluated_key.clone());
+                    }
+                    None => {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn list_log_groups(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut results = BTreeSet::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .projection_expression("log_group")
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            for item in resp.items.unwrap_or_default() {
+                if let Some(AttributeValue::S(log_group)) = item.get("log_group") {
+                    results.insert(log_group.clone());
+                }
+            }
+
+            match resp.last_evaluated_key {
+                Some(last_evaluated_key) => {
+                    exclusive_start_key = Some(last_evaluated_key);
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        Ok(results.into_iter().collect())
+    }
+
+    async fn delete_feedback(&self, id: &FeedbackId) -> Result<(), Box<dyn Error>> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn retire_feedback(&self, id: &FeedbackId) -> Result<(), Box<dyn Error>> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .update_expression("SET retired = :retired")
+            .expression_attribute_values(":retired", AttributeValue::Bool(true))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, TypedBuilder)]
+pub struct RuleRepositoryImpl {
+    client: Client,
+    table_name: String,
+}
+
+impl RuleRepository for RuleRepositoryImpl {
+    async fn add_rule(&self, rule: Rule) -> Result<(), Box<dyn Error>> {
+        let item = to_item(rule)?;
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_rule(&self, id: &RuleId) -> Result<Option<Rule>, Box<dyn Error>> {
+        let resp = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .send()
+            .await?;
+
+        match resp.item {
+            Some(item) => Ok(Some(from_item(item)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list_rules_by_log_group(&self, log_group: &str) -> Result<Vec<Rule>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("log_group_index")
+                .key_condition_expression("log_group = :log_group")
+                .expression_attribute_values(":log_group", AttributeValue::S(log_group.to_string()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let rules: Vec<Rule> = from_items(items)?;
+                results.extend(rules);
+
+                match &resp.last_evaluated_key {
+                    Some(last_evaluated_key) => {
+                        exclusive_start_key = Some(last_evaluated_key.clone());
+                    }
+                    None => {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn update_rule_status(
+        &self,
+        id: &RuleId,
+        status: RuleStatus,
+        reviewed_by: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .update_expression(
+                "SET #status = :status, reviewed_by = :reviewed_by, reviewed_at = :reviewed_at",
+            )
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":status", to_attribute_value(status)?)
+            .expression_attribute_values(":reviewed_by", AttributeValue::S(reviewed_by.into()))
+            .expression_attribute_values(":reviewed_at", to_attribute_value(Timestamp::new())?)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Loads routes from a JSON array given in configuration and from an optional table.
+#[derive(Clone, TypedBuilder)]
+pub struct RouteRepositoryImpl {
+    client: Client,
+    #[builder(default)]
+    table_name: Option<String>,
+    #[builder(default)]
+    routes_json: Option<String>,
+}
+
+impl RouteRepository for RouteRepositoryImpl {
+    async fn list_routes(&self) -> Result<Vec<Route>, Box<dyn Error>> {
+        let mut results: Vec<Route> = match &self.routes_json {
+            Some(routes_json) => serde_json::from_str(routes_json)?,
+            None => vec![],
+        };
+
+        let Some(table_name) = &self.table_name else {
+            return Ok(results);
+        };
+
+        let mut exclusive_start_key = None;
+        loop {
+            let resp = self
+                .client
+                .scan()
+                .table_name(table_name)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let routes: Vec<Route> = from_items(items)?;
+                results.extend(routes);
+            }
+
+            match resp.last_evaluated_key {
+                Some(last_evaluated_key) => {
+                    exclusive_start_key = Some(last_evaluated_key);
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[derive(Clone, TypedBuilder)]
+pub struct DecisionRepositoryImpl {
+    client: Client,
+    table_name: String,
+}
+
+impl DecisionRepository for DecisionRepositoryImpl {
+    async fn add_decision(&self, decision: Decision) -> Result<(), Box<dyn Error>> {
+        let item = to_item(decision)?;
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_decision(&self, id: &DecisionId) -> Result<Option<Decision>, Box<dyn Error>> {
+        let resp = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .send()
+            .await?;
+
+        match resp.item {
+            Some(item) => Ok(Some(from_item(item)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list_decisions_by_log_group(
+        &self,
+        log_group: &str,
+        since: &Timestamp,
+    ) -> Result<Vec<Decision>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("log_group_index")
+                .key_condition_expression("log_group = :log_group AND created_at >= :since")
+                .expression_attribute_values(":log_group", AttributeValue::S(log_group.to_string()))
+                .expression_attribute_values(":since", to_attribute_value(since)?)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let decisions: Vec<Decision> = from_items(items)?;
+                results.extend(decisions);
+
+                match &resp.last_evaluated_key {
+                    Some(last_evaluated_key) => {
+                        exclusive_start_key = Some(last_evaluated_key.clone());
+                    }
+                    None => {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn list_decisions_by_severity(
+        &self,
+        severity: Severity,
+        since: &Timestamp,
+    ) -> Result<Vec<Decision>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("severity_index")
+                .key_condition_expression("#severity = :severity AND created_at >= :since")
+                .expression_attribute_names("#severity", "severity")
+                .expression_attribute_values(":severity", to_attribute_value(severity)?)
+                .expression_attribute_values(":since", to_attribute_value(since)?)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let decisions: Vec<Decision> = from_items(items)?;
+                results.extend(decisions);
+
+                match &resp.last_evaluated_key {
+                    Some(last_evaluated_key) => {
+                        exclusive_start_key = Some(last_evaluated_key.clone());
+                    }
+                    None => {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn mark_feedback_sent(&self, id: &DecisionId) -> Result<(), Box<dyn Error>> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .update_expression("SET feedback_sent = :feedback_sent")
+            .expression_attribute_values(":feedback_sent", AttributeValue::Bool(true))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn acknowledge(
+        &self,
+        id: &DecisionId,
+        user_id: &str,
+        at: &Timestamp,
+    ) -> Result<(), Box<dyn Error>> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .update_expression(
+                "SET acknowledged_by = if_not_exists(acknowledged_by, :user_id), \
+                 acknowledged_at = if_not_exists(acknowledged_at, :at)",
+            )
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.into()))
+            .expression_attribute_values(":at", to_attribute_value(at)?)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn resolve(
+        &self,
+        id: &DecisionId,
+        user_id: &str,
+        at: &Timestamp,
+    ) -> Result<(), Box<dyn Error>> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .update_expression(
+                "SET acknowledged_by = if_not_exists(acknowledged_by, :user_id), \
+                 acknowledged_at = if_not_exists(acknowledged_at, :at), \
+                 resolved_by = if_not_exists(resolved_by, :user_id), \
+                 resolved_at = if_not_exists(resolved_at, :at)",
+            )
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.into()))
+            .expression_attribute_values(":at", to_attribute_value(at)?)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, TypedBuilder)]
+pub struct MuteRepositoryImpl {
+    client: Client,
+    table_name: String,
+}
+
+impl MuteRepository for MuteRepositoryImpl {
+    async fn add_mute(&self, mute: Mute) -> Result<(), Box<dyn Error>> {
+        let item = to_item(mute)?;
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_mute(&self, id: &MuteId) -> Result<Option<Mute>, Box<dyn Error>> {
+        let resp = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .send()
+            .await?;
+
+        match resp.item {
+            Some(item) => {
+                let mute: Mute = from_item(item)?;
+                Ok(Some(mute).filter(|v| v.expires_at() > &Timestamp::new()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_mute(&self, id: &MuteId) -> Result<(), Box<dyn Error>> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_mutes(&self) -> Result<Vec<Mute>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut exclusive_start_key = None;
+
+        // Expired items are filtered out since TTL deletion may lag behind.
+        loop {
+            let resp = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("expires_at > :now")
+                .expression_attribute_values(":now", to_attribute_value(Timestamp::new())?)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let mutes: Vec<Mute> = from_items(items)?;
+                results.extend(mutes);
+
+                match &resp.last_evaluated_key {
+                    Some(last_evaluated_key) => {
+                        exclusive_start_key = Some(last_evaluated_key.clone());
+                    }
+                    None => {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn list_mutes_by_log_group(&self, log_group: &str) -> Result<Vec<Mute>, Box<dyn Error>> {
+        let mut results = vec![];
+        let mut exclusive_start_key = None;
+
+        loop {
+            let resp = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("log_group_index")
+                .key_condition_expression("log_group = :log_group")
+                .filter_expression("expires_at > :now")
+                .expression_attribute_values(":log_group", AttributeValue::S(log_group.to_string()))
+                .expression_attribute_values(":now", to_attribute_value(Timestamp::new())?)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = resp.items {
+                let mutes: Vec<Mute> = from_items(items)?;
+                results.extend(mutes);
+
+                match &resp.last_evaluated_key {
+                    Some(last_evaluated_key) => {
+                        exclusive_start_key = Some(last_evaluated_key.clone());
+                    }
+                    None => {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Stores installations with their bot tokens encrypted.
+#[derive(Clone, TypedBuilder)]
+pub struct InstallationRepositoryImpl {
+    client: Client,
+    table_name: String,
+    cipher: TokenCipher,
+}
+
+/// An installation as stored in the table.
+#[derive(Serialize, Deserialize)]
+struct InstallationItem {
+    team_id: String,
+    team_name: Option<String>,
+    bot_user_id: Option<String>,
+    encrypted_bot_token: String,
+    installed_by: Option<String>,
+    installed_at: Timestamp,
+}
+
+impl InstallationRepository for InstallationRepositoryImpl {
+    async fn save_installation(&self, installation: Installation) -> Result<(), Box<dyn Error>> {
+        let item = to_item(InstallationItem {
+            team_id: installation.team_id().to_string(),
+            team_name: installation.team_name().map(|v| v.to_string()),
+            bot_user_id: installation.bot_user_id().map(|v| v.to_string()),
+            encrypted_bot_token: self
+                .cipher
+                .encrypt(installation.team_id(), installation.bot_token())?,
+            installed_by: installation.installed_by().map(|v| v.to_string()),
+            installed_at: installation.installed_at().clone(),
+        })?;
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_installation(
+        &self,
+        team_id: &str,
+    ) -> Result<Option<Installation>, Box<dyn Error>> {
+        let resp = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("team_id", AttributeValue::S(team_id.to_string()))
+            .send()
+            .await?;
+
+        let Some(item) = resp.item else {
+            return Ok(None);
+        };
+        let item: InstallationItem = from_item(item)?;
+        Ok(Some(
+            Installation::builder()
+                .bot_token(
+                    self.cipher
+                        .decrypt(&item.team_id, &item.encrypted_bot_token)?,
+                )
+                .team_id(item.team_id)
+                .team_name(item.team_name)
+                .bot_user_id(item.bot_user_id)
+                .installed_by(item.installed_by)
+                .installed_at(item.installed_at)
+                .build(),
+        ))
+    }
+}
+
+/// Nonces of the requests already handled, in a table expiring them by the `expires_at`
+/// attribute.
+#[derive(Clone, TypedBuilder)]
+pub struct NonceRepositoryImpl {
+    client: Client,
+    table_name: String,
+}
+
+impl NonceRepository for NonceRepositoryImpl {
+    async fn claim_nonce(
+        &self,
+        nonce: &str,
+        expires_at: &Timestamp,
+    ) -> Result<bool, Box<dyn Error>> {
+        // Expired items may stay in the table for a while before they are deleted.
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("nonce", AttributeValue::S(nonce.to_string()))
+            .item("expires_at", AttributeValue::N(expires_at.to_string()))
+            .condition_expression("attribute_not_exists(nonce) OR expires_at < :now")
+            .expression_attribute_values(":now", AttributeValue::N(Timestamp::new().to_string()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn release_nonce(&self, nonce: &str) -> Result<(), Box<dyn Error>> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("nonce", AttributeValue::S(nonce.to_string()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Nonces kept in the process, for when it is the only one serving the requests.
+#[derive(Clone, Default)]
+pub struct InMemoryNonceRepository {
+    nonces: Arc<Mutex<HashMap<String, Timestamp>>>,
+}
+
+impl NonceRepository for InMemoryNonceRepository {
+    async fn claim_nonce(
+        &self,
+        nonce: &str,
+        expires_at: &Timestamp,
+    ) -> Result<bool, Box<dyn Error>> {
+        let now = Timestamp::new();
+        let mut nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        nonces.retain(|_, v| *v >= now);
+        if nonces.contains_key(nonce) {
+            return Ok(false);
+        }
+        nonces.insert(nonce.to_string(), expires_at.clone());
+        Ok(true)
+    }
+
+    async fn release_nonce(&self, nonce: &str) -> Result<(), Box<dyn Error>> {
+        let mut nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        nonces.remove(nonce);
+        Ok(())
+    }
+}
+
+/// Either store of nonces, chosen by the configuration.
+#[derive(Clone)]
+pub enum NonceStore {
+    DynamoDb(NonceRepositoryImpl),
+    InMemory(InMemoryNonceRepository),
+}
+
+impl NonceRepository for NonceStore {
+    async fn claim_nonce(
+        &self,
+        nonce: &str,
+        expires_at: &Timestamp,
+    ) -> Result<bool, Box<dyn Error>> {
+        match self {
+            Self::DynamoDb(repo) => repo.claim_nonce(nonce, expires_at).await,
+            Self::InMemory(repo) => repo.claim_nonce(nonce, expires_at).await,
+        }
+    }
+
+    async fn release_nonce(&self, nonce: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::DynamoDb(repo) => repo.release_nonce(nonce).await,
+            Self::InMemory(repo) => repo.release_nonce(nonce).await,
+        }
+    }
 }
@@ -7,10 +7,11 @@ pub struct Client {
 }
 
 impl Client {
-    pub async fn load_secrets(
+    /// Returns the values of a secret holding a JSON object of strings.
+    pub async fn get_secrets(
         &self,
         secret_id: &str,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<HashMap<String, String>, Box<dyn std::error::Error + Send + Sync>> {
         let resp = self
             .inner
             .get_secret_value()
@@ -22,10 +23,6 @@ impl Client {
             .secret_string
             .ok_or(format!("Secret not found: {}", secret_id))?;
 
-        for (k, v) in serde_json::from_str::<HashMap<String, String>>(&secrets)? {
-            std::env::set_var(k, v);
-        }
-
-        Ok(())
+        Ok(serde_json::from_str(&secrets)?)
     }
 }
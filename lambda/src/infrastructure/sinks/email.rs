@@ -0,0 +1,150 @@
+//! Email over SMTP, as plain text.
+
+use super::{format_time, make_title, Alert, AlertSink, SINK_TIMEOUT};
+use crate::domain::value_objects::Locale;
+use crate::infrastructure::i18n::{catalog, Catalog};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize)]
+pub(crate) struct EmailSink {
+    host: String,
+    /// Defaults to 587 with STARTTLS, or 465 with `implicit_tls`.
+    port: Option<u16>,
+    /// Whether the connection is encrypted from the start rather than with STARTTLS.
+    #[serde(default)]
+    implicit_tls: bool,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+    to: Vec<String>,
+    #[serde(default)]
+    locale: Locale,
+}
+
+impl AlertSink for EmailSink {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        let message = self.make_message(catalog(self.locale), alert)?;
+
+        let mut transport = if self.implicit_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?
+        }
+        .timeout(Some(SINK_TIMEOUT));
+        if let Some(port) = self.port {
+            transport = transport.port(port);
+        }
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        transport.build().send(message).await?;
+
+        Ok(())
+    }
+}
+
+impl EmailSink {
+    fn make_message(
+        &self,
+        catalog: &Catalog,
+        alert: &Alert<'_>,
+    ) -> Result<Message, Box<dyn std::error::Error>> {
+        let mut builder = Message::builder()
+            .from(self.from.parse::<Mailbox>()?)
+            .subject(make_title(catalog, alert.decision));
+        for to in &self.to {
+            builder = builder.to(to.parse::<Mailbox>()?);
+        }
+
+        Ok(builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(make_body(catalog, alert))?)
+    }
+}
+
+fn make_body(catalog: &Catalog, alert: &Alert<'_>) -> String {
+    let decision = alert.decision;
+    let mut lines = vec![
+        make_title(catalog, decision),
+        String::new(),
+        format!("{}: {}", catalog.log_group_label, decision.log_group()),
+    ];
+    if let Some(source) = decision.source() {
+        lines.push(format!(
+            "{}: {}",
+            catalog.log_stream_label,
+            source.log_stream()
+        ));
+        lines.push(format!(
+            "{}: {}",
+            catalog.time_label,
+            format_time(source.event_time())
+        ));
+    }
+    lines.push(format!(
+        "{}: {}",
+        catalog.severity_label,
+        decision.severity()
+    ));
+    if let Some(explanation) = decision.explanation() {
+        lines.push(format!("{}: {}", catalog.explanation_header, explanation));
+    }
+    lines.extend([
+        String::new(),
+        format!("{}:", catalog.message_label),
+        decision.message().to_string(),
+    ]);
+    if let Some(url) = alert.feedback_url {
+        lines.extend([String::new(), format!("{}: {}", catalog.feedback_link, url)]);
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Decision;
+    use crate::domain::value_objects::{DecisionId, Severity, Timestamp};
+
+    #[test]
+    fn test_make_message() {
+        let sink: EmailSink = serde_json::from_value(serde_json::json!({
+            "host": "smtp.example.com",
+            "from": "Alerts <alerts@example.com>",
+            "to": ["ops@example.com", "dev@example.com"],
+            "locale": "en"
+        }))
+        .unwrap();
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR".into())
+            .needs_notification(true)
+            .severity(Severity::Low)
+            .build();
+        let alert = Alert {
+            decision: &decision,
+            mention_user_group: None,
+            feedback_url: Some("https://example.com/feedback-page"),
+        };
+
+        let message = sink.make_message(catalog(Locale::En), &alert).unwrap();
+        let text = String::from_utf8(message.formatted()).unwrap();
+        assert!(text.contains("Subject: [low] An error occurred in /aws/lambda/test"));
+        assert!(text.contains("To: ops@example.com, dev@example.com"));
+        assert_eq!(
+            make_body(catalog(Locale::En), &alert),
+            "[low] An error occurred in /aws/lambda/test\n\n\
+            Log group: /aws/lambda/test\n\
+            Severity: low\n\n\
+            Log message:\nERROR\n\n\
+            Send feedback: https://example.com/feedback-page"
+        );
+    }
+}
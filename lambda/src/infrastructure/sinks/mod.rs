@@ -0,0 +1,179 @@
+//! Destinations alerts are delivered to.
+//!
+//! Slack is always used for the channels of a log group. The other sinks are configured
+//! by name and enabled per log group by its route. They cannot take feedback themselves,
+//! so their alerts link to the feedback page of the collector when one is configured.
+
+mod email;
+mod pagerduty;
+mod teams;
+mod webhook;
+
+use crate::domain::entities::Decision;
+use crate::domain::value_objects::Timestamp;
+use crate::infrastructure::i18n::Catalog;
+use crate::infrastructure::slack;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub(crate) use email::EmailSink;
+pub(crate) use pagerduty::PagerDutySink;
+pub(crate) use teams::TeamsSink;
+pub(crate) use webhook::WebhookSink;
+
+/// How long a sink may take to accept an alert.
+const SINK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An alert to deliver, with what the sinks need beyond the decision.
+pub(crate) struct Alert<'a> {
+    pub(crate) decision: &'a Decision,
+    /// Slack user group mentioned on critical alerts.
+    pub(crate) mention_user_group: Option<&'a str>,
+    /// Signed link to the feedback page, present when the page is configured.
+    pub(crate) feedback_url: Option<&'a str>,
+}
+
+pub(crate) trait AlertSink {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Closes what the alert opened on the sink, for sinks that track alerts. Called when
+    /// the alert is resolved or judged not to need notification.
+    async fn resolve_alert(&self, _decision: &Decision) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+/// Posts alerts to a Slack channel, where feedback is sent from the alert itself.
+pub(crate) struct SlackSink<'a> {
+    pub(crate) client: &'a slack::Client,
+    pub(crate) channel_id: &'a str,
+}
+
+impl AlertSink for SlackSink<'_> {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        self.client
+            .post_alert(self.channel_id, alert.decision, alert.mention_user_group)
+            .await
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum Sink {
+    Teams(TeamsSink),
+    Webhook(WebhookSink),
+    Email(EmailSink),
+    #[serde(rename = "pagerduty")]
+    PagerDuty(PagerDutySink),
+}
+
+impl AlertSink for Sink {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Teams(sink) => sink.send_alert(alert).await,
+            Self::Webhook(sink) => sink.send_alert(alert).await,
+            Self::Email(sink) => sink.send_alert(alert).await,
+            Self::PagerDuty(sink) => sink.send_alert(alert).await,
+        }
+    }
+
+    async fn resolve_alert(&self, decision: &Decision) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Teams(sink) => sink.resolve_alert(decision).await,
+            Self::Webhook(sink) => sink.resolve_alert(decision).await,
+            Self::Email(sink) => sink.resolve_alert(decision).await,
+            Self::PagerDuty(sink) => sink.resolve_alert(decision).await,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NamedSink {
+    name: String,
+    #[serde(flatten)]
+    sink: Sink,
+}
+
+/// The sinks other than Slack, by the names routes refer to them with.
+#[derive(Clone, Default)]
+pub struct AlertSinks {
+    sinks: HashMap<String, Sink>,
+}
+
+impl AlertSinks {
+    /// Parses a JSON array of sinks, each with a `name` and a `kind` of `teams`,
+    /// `webhook`, `email` or `pagerduty`.
+    pub fn new(json: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        let Some(json) = json else {
+            return Ok(Self::default());
+        };
+        let mut sinks = HashMap::new();
+        for v in serde_json::from_str::<Vec<NamedSink>>(json)? {
+            if sinks.insert(v.name.clone(), v.sink).is_some() {
+                return Err(format!("Duplicate alert sink: {}", v.name).into());
+            }
+        }
+        Ok(Self { sinks })
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Sink> {
+        self.sinks.get(name)
+    }
+
+    /// Resolves the alert of the decision on the sinks it was sent to. Failures are only
+    /// logged, since the alert has already been handled on Slack.
+    pub(crate) async fn resolve_alert(&self, decision: &Decision) {
+        for name in decision.sinks() {
+            let Some(sink) = self.get(name) else {
+                tracing::warn!("Unknown alert sink: {}", name);
+                continue;
+            };
+            if let Err(e) = sink.resolve_alert(decision).await {
+                tracing::warn!("Failed to resolve the alert on {}: {:?}", name, e);
+            }
+        }
+    }
+}
+
+/// Returns the summary line used as the title of alerts outside Slack.
+fn make_title(catalog: &Catalog, decision: &Decision) -> String {
+    (catalog.alert_fallback)(decision.severity(), decision.log_group())
+}
+
+fn format_time(time: &Timestamp) -> String {
+    DateTime::<Utc>::from_timestamp(time.as_secs(), 0)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_sinks() {
+        let sinks = AlertSinks::new(Some(
+            r#"[
+                { "name": "ops", "kind": "teams", "url": "https://example.com/teams" },
+                { "name": "audit", "kind": "webhook", "url": "https://example.com/hook", "secret": "s" },
+                { "name": "mail", "kind": "email", "host": "smtp.example.com", "from": "alerts@example.com", "to": ["ops@example.com"] },
+                { "name": "pager", "kind": "pagerduty", "routing_key": "key" }
+            ]"#,
+        ))
+        .unwrap();
+
+        assert!(matches!(sinks.get("ops"), Some(Sink::Teams(_))));
+        assert!(matches!(sinks.get("audit"), Some(Sink::Webhook(_))));
+        assert!(matches!(sinks.get("mail"), Some(Sink::Email(_))));
+        assert!(matches!(sinks.get("pager"), Some(Sink::PagerDuty(_))));
+        assert!(sinks.get("slack").is_none());
+
+        assert!(AlertSinks::new(Some(
+            r#"[{ "name": "a", "kind": "teams", "url": "x" }, { "name": "a", "kind": "teams", "url": "y" }]"#
+        ))
+        .is_err());
+        assert!(AlertSinks::new(Some(r#"[{ "name": "a", "kind": "fax" }]"#)).is_err());
+    }
+}
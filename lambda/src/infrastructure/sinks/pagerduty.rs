@@ -0,0 +1,236 @@
+//! PagerDuty incidents through Events API v2.
+//!
+//! Alerts of the same kind of log in a log group share a dedup key, so that repeated
+//! occurrences are grouped into the open incident instead of paging again. The incident
+//! is resolved when the alert is resolved or marked as not needing notification.
+
+use super::{format_time, make_title, Alert, AlertSink, SINK_TIMEOUT};
+use crate::domain::entities::Decision;
+use crate::domain::value_objects::{Locale, Severity};
+use crate::infrastructure::i18n::catalog;
+use crate::util::truncate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const DEFAULT_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+/// PagerDuty rejects summaries longer than this.
+const MAX_SUMMARY_LENGTH: usize = 1024;
+
+#[derive(Clone, Deserialize)]
+pub(crate) struct PagerDutySink {
+    /// Integration key of the service the incidents are opened on.
+    routing_key: String,
+    #[serde(default = "default_url")]
+    url: String,
+    #[serde(default)]
+    locale: Locale,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+fn default_url() -> String {
+    DEFAULT_URL.to_string()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum EventAction {
+    Trigger,
+    Resolve,
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+    routing_key: &'a str,
+    event_action: EventAction,
+    dedup_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<Payload<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    links: Vec<Link<'a>>,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    summary: String,
+    source: &'a str,
+    severity: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    custom_details: CustomDetails<'a>,
+}
+
+#[derive(Serialize)]
+struct CustomDetails<'a> {
+    decision_id: String,
+    message: &'a str,
+    explanation: Option<&'a str>,
+    log_stream: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Link<'a> {
+    href: &'a str,
+    text: &'static str,
+}
+
+impl AlertSink for PagerDutySink {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        let decision = alert.decision;
+        let catalog = catalog(self.locale);
+        let event = Event {
+            routing_key: &self.routing_key,
+            event_action: EventAction::Trigger,
+            dedup_key: dedup_key(decision),
+            payload: Some(Payload {
+                summary: truncate(
+                    &make_title(catalog, decision),
+                    MAX_SUMMARY_LENGTH,
+                    catalog.truncated,
+                )
+                .into_owned(),
+                source: decision.log_group(),
+                severity: pagerduty_severity(decision.severity()),
+                timestamp: decision.source().map(|v| format_time(v.event_time())),
+                custom_details: CustomDetails {
+                    decision_id: decision.id().to_string(),
+                    message: decision.message(),
+                    explanation: decision.explanation(),
+                    log_stream: decision.source().map(|v| v.log_stream()),
+                },
+            }),
+            links: alert
+                .feedback_url
+                .map(|href| Link {
+                    href,
+                    text: catalog.feedback_link,
+                })
+                .into_iter()
+                .collect(),
+        };
+        self.send(&event).await
+    }
+
+    async fn resolve_alert(&self, decision: &Decision) -> Result<(), Box<dyn std::error::Error>> {
+        let event = Event {
+            routing_key: &self.routing_key,
+            event_action: EventAction::Resolve,
+            dedup_key: dedup_key(decision),
+            payload: None,
+            links: vec![],
+        };
+        self.send(&event).await
+    }
+}
+
+impl PagerDutySink {
+    async fn send(&self, event: &Event<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        self.client
+            .post(&self.url)
+            .timeout(SINK_TIMEOUT)
+            .json(event)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+/// Returns the key shared by the alerts of the decision's kind of log in its log group.
+/// It is hashed since log group names alone may exceed the 255 characters PagerDuty takes.
+fn dedup_key(decision: &Decision) -> String {
+    let digest = Sha256::digest(format!(
+        "{}\n{}",
+        decision.log_group(),
+        decision.fingerprint()
+    ));
+    format!("llm-alert-filter-{:x}", digest)
+}
+
+fn pagerduty_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "critical",
+        Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low => "info",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::{DecisionId, Timestamp};
+    use crate::util::test_server;
+    use axum::http::StatusCode;
+    use axum::Json;
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    fn decision(message: &str) -> Decision {
+        Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message(message.into())
+            .needs_notification(true)
+            .severity(Severity::High)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_send_and_resolve_alert() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/v2/enqueue",
+            axum::routing::post(move |Json(body): Json<Value>| async move {
+                let accepted = body["routing_key"] == "key";
+                sender.send(body).unwrap();
+                if accepted {
+                    StatusCode::ACCEPTED
+                } else {
+                    StatusCode::BAD_REQUEST
+                }
+            }),
+        );
+        let base_url = test_server(app).await;
+
+        let sink = |routing_key: &str| PagerDutySink {
+            routing_key: routing_key.into(),
+            url: format!("{}/v2/enqueue", base_url),
+            locale: Locale::En,
+            client: reqwest::Client::new(),
+        };
+        let first = decision("Timeout after 3001 ms (request 7f3a9c)");
+        let second = decision("Timeout after 2999 ms (request 1b2c3d)");
+        let alert = Alert {
+            decision: &first,
+            mention_user_group: None,
+            feedback_url: Some("https://example.com/feedback-page"),
+        };
+
+        sink("key").send_alert(&alert).await.unwrap();
+        let trigger = receiver.recv().await.unwrap();
+        assert_eq!(trigger["event_action"], "trigger");
+        assert_eq!(
+            trigger["payload"]["summary"],
+            "[high] An error occurred in /aws/lambda/test"
+        );
+        assert_eq!(trigger["payload"]["severity"], "error");
+        assert_eq!(trigger["payload"]["source"], "/aws/lambda/test");
+        assert_eq!(
+            trigger["links"][0]["href"],
+            "https://example.com/feedback-page"
+        );
+
+        // Another occurrence of the same error resolves the same incident.
+        sink("key").resolve_alert(&second).await.unwrap();
+        let resolve = receiver.recv().await.unwrap();
+        assert_eq!(resolve["event_action"], "resolve");
+        assert_eq!(resolve["dedup_key"], trigger["dedup_key"]);
+        assert!(resolve.get("payload").is_none());
+
+        assert_ne!(dedup_key(&first), dedup_key(&decision("Out of memory")));
+        assert!(sink("other").send_alert(&alert).await.is_err());
+    }
+}
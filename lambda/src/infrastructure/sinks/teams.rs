@@ -0,0 +1,138 @@
+//! Microsoft Teams incoming webhooks, which take alerts as Adaptive Cards.
+
+use super::{format_time, make_title, Alert, AlertSink, SINK_TIMEOUT};
+use crate::domain::value_objects::{Locale, Severity};
+use crate::infrastructure::i18n::{catalog, Catalog};
+use crate::util::truncate;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Teams does not show cards larger than about 28 KB, so long messages are cut.
+const MAX_MESSAGE_LENGTH: usize = 10000;
+
+#[derive(Clone, Deserialize)]
+pub(crate) struct TeamsSink {
+    url: String,
+    #[serde(default)]
+    locale: Locale,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+impl AlertSink for TeamsSink {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        self.client
+            .post(&self.url)
+            .timeout(SINK_TIMEOUT)
+            .json(&make_message(catalog(self.locale), alert))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+fn make_message(catalog: &Catalog, alert: &Alert<'_>) -> Value {
+    let decision = alert.decision;
+    let color = match decision.severity() {
+        Severity::Critical | Severity::High => "Attention",
+        Severity::Medium => "Warning",
+        Severity::Low => "Default",
+    };
+
+    let mut facts =
+        vec![json!({ "title": catalog.log_group_label, "value": decision.log_group() })];
+    if let Some(source) = decision.source() {
+        facts.push(json!({ "title": catalog.log_stream_label, "value": source.log_stream() }));
+        facts.push(
+            json!({ "title": catalog.time_label, "value": format_time(source.event_time()) }),
+        );
+    }
+    facts
+        .push(json!({ "title": catalog.severity_label, "value": decision.severity().to_string() }));
+
+    let mut body = vec![
+        json!({
+            "type": "TextBlock",
+            "text": make_title(catalog, decision),
+            "size": "Medium",
+            "weight": "Bolder",
+            "color": color,
+            "wrap": true
+        }),
+        json!({ "type": "FactSet", "facts": facts }),
+        json!({
+            "type": "TextBlock",
+            "text": truncate(decision.message(), MAX_MESSAGE_LENGTH, catalog.truncated),
+            "fontType": "Monospace",
+            "wrap": true
+        }),
+    ];
+    if let Some(explanation) = decision.explanation() {
+        body.push(json!({
+            "type": "TextBlock",
+            "text": format!("{}: {}", catalog.explanation_header, explanation),
+            "isSubtle": true,
+            "wrap": true
+        }));
+    }
+    let actions: Vec<Value> = alert
+        .feedback_url
+        .map(|url| json!({ "type": "Action.OpenUrl", "title": catalog.feedback_link, "url": url }))
+        .into_iter()
+        .collect();
+
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": body,
+                "actions": actions
+            }
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Decision;
+    use crate::domain::value_objects::{DecisionId, Timestamp};
+
+    #[test]
+    fn test_make_message() {
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR".into())
+            .needs_notification(true)
+            .severity(Severity::Critical)
+            .build();
+        let alert = Alert {
+            decision: &decision,
+            mention_user_group: None,
+            feedback_url: Some("https://example.com/feedback-page?decision_id=1"),
+        };
+
+        let message = make_message(catalog(Locale::En), &alert);
+        let card = &message["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(
+            card["body"][0]["text"],
+            "[critical] An error occurred in /aws/lambda/test"
+        );
+        assert_eq!(card["body"][0]["color"], "Attention");
+        assert_eq!(card["body"][1]["facts"][0]["value"], "/aws/lambda/test");
+        assert_eq!(card["body"][2]["text"], "ERROR");
+        assert_eq!(
+            card["actions"][0]["url"],
+            "https://example.com/feedback-page?decision_id=1"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Generic JSON webhooks, signed the same way Slack signs its requests so that receivers
+//! can reuse their verification code.
+//!
+//! The signature is `v0=` followed by the hex HMAC-SHA256 of `v0:<timestamp>:<body>`,
+//! sent in `X-Alert-Filter-Signature` with the timestamp in
+//! `X-Alert-Filter-Request-Timestamp`.
+
+use super::{format_time, Alert, AlertSink, SINK_TIMEOUT};
+use crate::util::{now_timestamp, slack_signature};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Deserialize)]
+pub(crate) struct WebhookSink {
+    url: String,
+    secret: String,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+/// Body of a webhook request.
+#[derive(Serialize)]
+struct Payload<'a> {
+    decision_id: String,
+    log_group: &'a str,
+    message: &'a str,
+    severity: String,
+    explanation: Option<&'a str>,
+    log_stream: Option<&'a str>,
+    event_time: Option<String>,
+    feedback_url: Option<&'a str>,
+}
+
+impl AlertSink for WebhookSink {
+    async fn send_alert(&self, alert: &Alert<'_>) -> Result<(), Box<dyn std::error::Error>> {
+        let decision = alert.decision;
+        let body = serde_json::to_string(&Payload {
+            decision_id: decision.id().to_string(),
+            log_group: decision.log_group(),
+            message: decision.message(),
+            severity: decision.severity().to_string(),
+            explanation: decision.explanation(),
+            log_stream: decision.source().map(|v| v.log_stream()),
+            event_time: decision.source().map(|v| format_time(v.event_time())),
+            feedback_url: alert.feedback_url,
+        })?;
+        let timestamp = now_timestamp();
+
+        self.client
+            .post(&self.url)
+            .timeout(SINK_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("X-Alert-Filter-Request-Timestamp", timestamp)
+            .header(
+                "X-Alert-Filter-Signature",
+                slack_signature(&self.secret, &timestamp.to_string(), body.as_bytes()),
+            )
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Decision;
+    use crate::domain::value_objects::{DecisionId, Severity, Timestamp};
+    use crate::util::test_server;
+    use axum::http::{HeaderMap, StatusCode};
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_send_alert() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move |headers: HeaderMap, body: String| async move {
+                let timestamp = headers["x-alert-filter-request-timestamp"]
+                    .to_str()
+                    .unwrap();
+                let signed = headers["x-alert-filter-signature"]
+                    == slack_signature("s", timestamp, body.as_bytes());
+                sender.send(body).unwrap();
+                if signed {
+                    StatusCode::OK
+                } else {
+                    StatusCode::UNAUTHORIZED
+                }
+            }),
+        );
+        let base_url = test_server(app).await;
+
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR".into())
+            .needs_notification(true)
+            .severity(Severity::High)
+            .build();
+        let alert = Alert {
+            decision: &decision,
+            mention_user_group: None,
+            feedback_url: Some("https://example.com/feedback-page"),
+        };
+        let sink = |secret: &str| WebhookSink {
+            url: format!("{}/hook", base_url),
+            secret: secret.into(),
+            client: reqwest::Client::new(),
+        };
+
+        sink("s").send_alert(&alert).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_str(&receiver.recv().await.unwrap()).unwrap();
+        assert_eq!(body["decision_id"], decision.id().to_string());
+        assert_eq!(body["severity"], "high");
+        assert_eq!(body["feedback_url"], "https://example.com/feedback-page");
+
+        assert!(sink("other").send_alert(&alert).await.is_err());
+    }
+}
@@ -1,333 +1,2344 @@
+use crate::domain::entities::{
+    AlertCounts, Decision, Feedback, Installation, LogContext, LogSource, Mute, ResponseTimes, Rule,
+};
+use crate::domain::repositories::InstallationRepository;
+use crate::domain::value_objects::{Locale, RuleStatus, Severity, Timestamp};
+use crate::infrastructure::bedrock::Judgement;
+use crate::infrastructure::block_kit::{
+    ActionId, Block, BlockId, ButtonStyle, CallbackId, CommandResponse, Confirm, Element, HomeView,
+    Modal, ResponseType, SelectOption, Text,
+};
+use crate::infrastructure::console::{log_events_url, logs_insights_url};
+use crate::infrastructure::i18n::{catalog, Catalog, LocaleSettings};
+use crate::infrastructure::metrics;
+use crate::infrastructure::repositories_impl::InstallationRepositoryImpl;
+use crate::infrastructure::slack_api::{
+    ApiResponse, Body, ErrorCode, HistoryMessage, RateKey, RateLimiter, SlackError,
+    INITIAL_BACKOFF, MAX_ATTEMPTS,
+};
+use crate::util::truncate;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::fmt;
 use std::io::{Read, Write};
+use std::time::{Duration, Instant};
 use typed_builder::TypedBuilder;
 
+/// Metadata of the feedback modal. The alert itself is looked up by its decision id, since
+/// the log message may not fit in `private_metadata`.
 #[derive(Debug, Serialize, Deserialize, TypedBuilder)]
 pub(crate) struct PrivateMetadata {
+    channel_id: String,
     ts: String,
-    log_group: String,
-    message: String,
+    decision_id: String,
 }
 
 impl PrivateMetadata {
+    pub(crate) fn channel_id(&self) -> &str {
+        &self.channel_id
+    }
+
     pub(crate) fn ts(&self) -> &str {
         &self.ts
     }
 
-    pub(crate) fn log_group(&self) -> &str {
-        &self.log_group
+    pub(crate) fn decision_id(&self) -> &str {
+        &self.decision_id
     }
 
-    pub(crate) fn message(&self) -> &str {
-        &self.message
+    pub(crate) fn encode_base64(&self) -> Result<String, Box<dyn std::error::Error>> {
+        encode_metadata(self)
     }
+}
 
-    pub(crate) fn encode_base64(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let json = serde_json::to_string(self)?;
+impl TryFrom<&str> for PrivateMetadata {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        decode_metadata(value)
+    }
+}
+
+/// Metadata of the modal asking the user how to resolve conflicting feedback.
+#[derive(Debug, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct ConflictMetadata {
+    channel_id: String,
+    ts: String,
+    decision_id: String,
+    needs_notification: bool,
+    severity: Option<Severity>,
+    reason: Option<String>,
+    user_id: String,
+    conflicting_ids: Vec<String>,
+}
+
+impl ConflictMetadata {
+    pub(crate) fn channel_id(&self) -> &str {
+        &self.channel_id
+    }
+
+    pub(crate) fn ts(&self) -> &str {
+        &self.ts
+    }
+
+    pub(crate) fn decision_id(&self) -> &str {
+        &self.decision_id
+    }
+
+    pub(crate) fn needs_notification(&self) -> bool {
+        self.needs_notification
+    }
+
+    pub(crate) fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    pub(crate) fn reason(&self) -> Option<&String> {
+        self.reason.as_ref()
+    }
+
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user_id
+    }
 
-        let mut gz_encoder = GzEncoder::new(Vec::new(), Compression::default());
-        gz_encoder.write_all(json.as_bytes())?;
-        let compressed = gz_encoder.finish()?;
+    pub(crate) fn conflicting_ids(&self) -> &[String] {
+        &self.conflicting_ids
+    }
 
-        Ok(BASE64_STANDARD.encode(compressed.as_slice()))
+    pub(crate) fn encode_base64(&self) -> Result<String, Box<dyn std::error::Error>> {
+        encode_metadata(self)
     }
 }
 
-impl TryFrom<&str> for PrivateMetadata {
+impl TryFrom<&str> for ConflictMetadata {
     type Error = Box<dyn std::error::Error>;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let compressed = BASE64_STANDARD.decode(value.as_bytes())?;
+        decode_metadata(value)
+    }
+}
 
-        let mut gz_decoder = flate2::read::GzDecoder::new(compressed.as_slice());
-        let mut json = String::new();
-        gz_decoder.read_to_string(&mut json)?;
+/// Hours offered in the snooze menu of an alert.
+const SNOOZE_HOURS: [i64; 3] = [1, 4, 24];
+
+/// Value of an option in the snooze menu of an alert, written as `<hours>h:<decision id>` or
+/// `custom:<decision id>` to fit in the 75 characters Slack allows.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SnoozeOption {
+    /// Hours to snooze for, or `None` to let the user choose the end time.
+    pub(crate) hours: Option<i64>,
+    pub(crate) decision_id: String,
+}
 
-        Ok(serde_json::from_str(&json)?)
+impl fmt::Display for SnoozeOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hours {
+            Some(hours) => write!(f, "{}h:{}", hours, self.decision_id),
+            None => write!(f, "custom:{}", self.decision_id),
+        }
+    }
+}
+
+impl TryFrom<&str> for SnoozeOption {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (duration, decision_id) = value.split_once(':').ok_or("Invalid snooze option")?;
+        let hours = match duration {
+            "custom" => None,
+            _ => Some(
+                duration
+                    .strip_suffix('h')
+                    .ok_or("Invalid snooze duration")?
+                    .parse()?,
+            ),
+        };
+        Ok(Self {
+            hours,
+            decision_id: decision_id.to_string(),
+        })
     }
 }
 
-#[derive(Deserialize)]
-struct Response {
-    ok: bool,
-    error: Option<String>,
-    response_metadata: Option<Value>,
+/// Returns whether a reply by the bot follows a reply mentioning it, skipping the message
+/// that started the thread.
+fn answered_mention(messages: &[HistoryMessage], bot_user_id: &str) -> bool {
+    let mention = format!("<@{}>", bot_user_id);
+    let mut replies = messages.iter().skip(1);
+    replies
+        .position(|v| {
+            v.user.as_deref() != Some(bot_user_id)
+                && v.text.as_deref().is_some_and(|v| v.contains(&mention))
+        })
+        .is_some()
+        && replies.any(|v| v.user.as_deref() == Some(bot_user_id))
+}
+
+fn encode_metadata<T: Serialize>(value: &T) -> Result<String, Box<dyn std::error::Error>> {
+    let json = serde_json::to_string(value)?;
+
+    let mut gz_encoder = GzEncoder::new(Vec::new(), Compression::default());
+    gz_encoder.write_all(json.as_bytes())?;
+    let compressed = gz_encoder.finish()?;
+
+    Ok(BASE64_STANDARD.encode(compressed.as_slice()))
+}
+
+fn decode_metadata<T: DeserializeOwned>(value: &str) -> Result<T, Box<dyn std::error::Error>> {
+    let compressed = BASE64_STANDARD.decode(value.as_bytes())?;
+
+    let mut gz_decoder = flate2::read::GzDecoder::new(compressed.as_slice());
+    let mut json = String::new();
+    gz_decoder.read_to_string(&mut json)?;
+
+    Ok(serde_json::from_str(&json)?)
 }
 
 #[derive(Clone, TypedBuilder)]
 pub struct Client {
     inner_client: reqwest::Client,
+    /// Bot token of the workspace configured directly, used for teams without an
+    /// installation.
     token: String,
+    /// Installations of the workspaces the app was added to through OAuth.
+    #[builder(default)]
+    installations: Option<InstallationRepositoryImpl>,
+    /// Workspace the client acts in, set by `for_team`.
+    #[builder(default)]
+    team_id: Option<String>,
+    /// Whether the workspace was installed through OAuth, set by `for_team`.
+    #[builder(default)]
+    installed: bool,
+    #[builder(default)]
+    locales: LocaleSettings,
+    #[builder(default = BASE_URL.to_string())]
+    base_url: String,
+    /// How long a call may spend waiting for rate limits and retries before giving up.
+    #[builder(default = DEFAULT_DEADLINE)]
+    deadline: Duration,
+    #[builder(default)]
+    rate_limiter: RateLimiter,
+    /// Region of the logs, used to link alerts to the CloudWatch console. No links are
+    /// shown when unset.
+    #[builder(default)]
+    aws_region: Option<String>,
 }
 
 const BASE_URL: &str = "https://slack.com/api";
 
+const DEFAULT_DEADLINE: Duration = Duration::from_secs(30);
+
+/// Maximum number of characters Slack accepts in the text of a section block.
+const MAX_SECTION_TEXT_LENGTH: usize = 3000;
+
+/// Maximum number of characters Slack accepts in a context block element.
+const MAX_CONTEXT_TEXT_LENGTH: usize = 2000;
+
 impl Client {
-    pub(crate) async fn post_alert(
+    /// Calls a Slack Web API method, waiting for its rate bucket and retrying rate-limited
+    /// and transient failures as long as the deadline allows. Methods that are not
+    /// idempotent are only retried when Slack is known not to have handled the request.
+    async fn call(&self, method: &'static str, body: Body) -> Result<ApiResponse, SlackError> {
+        let deadline = Instant::now() + self.deadline;
+        let rate_key = RateKey::new(self.team_id(), method, &body);
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+
+        loop {
+            loop {
+                let wait = self.rate_limiter.reserve(&rate_key);
+                if wait.is_zero() {
+                    break;
+                }
+                if Instant::now() + wait > deadline {
+                    return Err(SlackError::DeadlineExceeded { method, last: None });
+                }
+                tokio::time::sleep(wait).await;
+            }
+
+            let error = match self.send(method, &body).await {
+                Ok(resp) => return Ok(resp),
+                Err(error) => error,
+            };
+            if !error.is_retryable() || attempt >= MAX_ATTEMPTS {
+                return Err(error);
+            }
+
+            let rate_limit_wait = error.rate_limit_wait();
+            if let Some(wait) = rate_limit_wait {
+                // Later attempts and other calls sharing the bucket wait in it.
+                self.rate_limiter.block(&rate_key, wait);
+            }
+            let wait = rate_limit_wait.unwrap_or(backoff);
+            if Instant::now() + wait > deadline {
+                return Err(SlackError::DeadlineExceeded {
+                    method,
+                    last: Some(Box::new(error)),
+                });
+            }
+            tracing::warn!("Retrying {} in {:?}: {}", method, wait, error);
+            if rate_limit_wait.is_none() {
+                tokio::time::sleep(wait).await;
+                backoff *= 2;
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn send(&self, method: &'static str, body: &Body) -> Result<ApiResponse, SlackError> {
+        let mut req = self
+            .inner_client
+            .post(format!("{}/{}", self.base_url, method));
+        // OAuth methods authenticate with the app's credentials instead.
+        if !self.token.is_empty() {
+            req = req.bearer_auth(&self.token);
+        }
+        let req = match body {
+            Body::Json(json) => req.json(json),
+            Body::Form(form) => req.form(form),
+        };
+        let resp = req
+            .send()
+            .await
+            .map_err(|source| SlackError::Transport { method, source })?;
+
+        let status = resp.status();
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        if !status.is_success() {
+            return Err(SlackError::Http {
+                method,
+                status: status.as_u16(),
+                body: resp.text().await.unwrap_or_default(),
+                retry_after,
+            });
+        }
+
+        let resp: ApiResponse = resp
+            .json()
+            .await
+            .map_err(|source| SlackError::Transport { method, source })?;
+        if resp.ok {
+            Ok(resp)
+        } else {
+            Err(SlackError::Api {
+                method,
+                code: ErrorCode::from(resp.error.as_deref().unwrap_or_default()),
+                metadata: resp.response_metadata,
+                retry_after,
+            })
+        }
+    }
+
+    /// Returns the locale of the messages posted to the channel.
+    pub(crate) fn locale(&self, channel_id: &str) -> Locale {
+        self.locales.locale(channel_id)
+    }
+
+    /// Returns the locale of views that do not belong to a channel, such as the Home tab.
+    pub(crate) fn default_locale(&self) -> Locale {
+        self.locales.default_locale()
+    }
+
+    /// Replies in a thread with the judgement of a log pasted by a user.
+    pub(crate) async fn post_judgement_reply(
+        &self,
+        channel_id: &str,
+        thread_ts: &str,
+        judgement: &Judgement,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let text = make_judgement_text(catalog(self.locale(channel_id)), judgement);
+        self.post_thread_reply(channel_id, thread_ts, &text).await
+    }
+
+    /// Replies in a thread with how to ask for a judgement.
+    pub(crate) async fn post_mention_usage(
+        &self,
+        channel_id: &str,
+        thread_ts: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let text = catalog(self.locale(channel_id)).mention_usage;
+        self.post_thread_reply(channel_id, thread_ts, text).await
+    }
+
+    /// Replies in a thread that the log group is not one of the workspace.
+    pub(crate) async fn post_forbidden_reply(
         &self,
         channel_id: &str,
+        thread_ts: &str,
         log_group: &str,
-        message: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/chat.postMessage", BASE_URL);
+        let text = (catalog(self.locale(channel_id)).command_forbidden)(log_group);
+        self.post_thread_reply(channel_id, thread_ts, &text).await
+    }
 
-        let mut blocks = match self.make_base_alert_message(log_group, message).as_array() {
-            Some(blocks) => blocks.to_vec(),
-            None => vec![],
-        };
-        blocks.push(serde_json::json!({
-            "type": "actions",
-            "block_id": "feedback_button",
-            "elements": [
-                {
-                    "type": "button",
-                    "text": {
-                        "type": "plain_text",
-                        "text": "フィードバック"
-                    },
-                    "style": "primary",
-                    "value": "send_feedback",
-                    "action_id": "open_modal"
-                }
-            ]
-        }));
+    async fn post_thread_reply(
+        &self,
+        channel_id: &str,
+        thread_ts: &str,
+        text: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.call(
+            "chat.postMessage",
+            Body::Json(serde_json::json!({
+                "channel": channel_id,
+                "thread_ts": thread_ts,
+                "text": text,
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
 
+    /// Returns whether the bot answered a mention in the thread. Its other posts, such as the
+    /// alert that started the thread, do not count.
+    pub(crate) async fn has_answered_mention_in_thread(
+        &self,
+        channel_id: &str,
+        thread_ts: &str,
+        bot_user_id: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
         let resp = self
-            .inner_client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .json(&serde_json::json!({
-                "channel": channel_id,
-                "blocks": blocks
-            }))
-            .send()
+            .call(
+                "conversations.replies",
+                Body::Form(vec![
+                    ("channel", channel_id.to_string()),
+                    ("ts", thread_ts.to_string()),
+                ]),
+            )
             .await?;
 
-        if resp.status().is_success() {
-            let resp: Response = resp.json().await?;
-            if resp.ok {
-                Ok(())
-            } else {
-                Err(format!(
-                    "Failed to post message: {}",
-                    resp.error.unwrap_or("Unknown".into())
-                )
-                .into())
+        Ok(answered_mention(
+            &resp.messages.unwrap_or_default(),
+            bot_user_id,
+        ))
+    }
+
+    /// Returns a client acting in the workspace, with the bot token installed there. The
+    /// configured token is kept for workspaces without an installation.
+    pub(crate) async fn for_team(
+        &self,
+        team_id: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let client = Self {
+            team_id: team_id.map(|v| v.to_string()),
+            ..self.clone()
+        };
+        let (Some(installations), Some(team_id)) = (&self.installations, team_id) else {
+            if self.token.is_empty() {
+                return Err("No token for workspace-less route".into());
             }
-        } else {
-            Err(format!("Failed to post message: {}", resp.text().await?).into())
+            return Ok(client);
+        };
+        match installations.get_installation(team_id).await? {
+            Some(installation) => Ok(Self {
+                token: installation.bot_token().to_string(),
+                installed: true,
+                ..client
+            }),
+            None if !self.token.is_empty() => Ok(client),
+            None => Err(format!("Workspace {} is not installed", team_id).into()),
+        }
+    }
+
+    /// Returns the workspace the client acts in, if it was resolved for one.
+    pub(crate) fn team_id(&self) -> Option<&str> {
+        self.team_id.as_deref()
+    }
+
+    /// Returns whether the routes of a workspace are the ones of the workspace the client
+    /// acts in. Routes naming no workspace are the ones of the configured token.
+    pub(crate) fn is_own_team(&self, team_id: Option<&str>) -> bool {
+        match team_id {
+            Some(team_id) => self.team_id() == Some(team_id),
+            None => !self.installed,
         }
     }
 
-    pub(crate) async fn close_feedback_button(
+    /// Tells the user that the follow-up of an interaction failed, replying through the
+    /// response URL of the interaction when given and ephemerally in the channel otherwise.
+    pub(crate) async fn report_task_failure(
         &self,
-        channel_id: &str,
-        ts: &str,
-        log_group: &str,
-        message: &str,
+        response_url: Option<&str>,
+        channel_id: Option<&str>,
+        user_id: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/chat.update", BASE_URL);
+        let locale = channel_id.map_or(self.default_locale(), |v| self.locale(v));
+        let text = catalog(locale).task_failed;
+
+        if let Some(response_url) = response_url {
+            self.inner_client
+                .post(response_url)
+                .json(&serde_json::json!({
+                    "response_type": "ephemeral",
+                    "replace_original": false,
+                    "text": text,
+                }))
+                .send()
+                .await?
+                .error_for_status()?;
+            return Ok(());
+        }
+        let Some(channel_id) = channel_id else {
+            return Err("Nowhere to report the failure to")?;
+        };
+        self.call(
+            "chat.postEphemeral",
+            Body::Json(serde_json::json!({
+                "channel": channel_id,
+                "user": user_id,
+                "text": text,
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Exchanges the code of an OAuth redirect for the installation it grants.
+    pub(crate) async fn oauth_access(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<Installation, Box<dyn std::error::Error>> {
+        let client = Self {
+            token: String::new(),
+            ..self.clone()
+        };
+        let resp = client
+            .call(
+                "oauth.v2.access",
+                Body::Form(vec![
+                    ("client_id", client_id.to_string()),
+                    ("client_secret", client_secret.to_string()),
+                    ("code", code.to_string()),
+                    ("redirect_uri", redirect_uri.to_string()),
+                ]),
+            )
+            .await?;
+
+        let team = resp.team.ok_or("oauth.v2.access returned no team")?;
+        Ok(Installation::builder()
+            .team_id(team.id)
+            .team_name(team.name)
+            .bot_user_id(resp.bot_user_id)
+            .bot_token(
+                resp.access_token
+                    .ok_or("oauth.v2.access returned no token")?,
+            )
+            .installed_by(resp.authed_user.map(|v| v.id))
+            .installed_at(Timestamp::new())
+            .build())
+    }
 
-        let mut blocks = match self.make_base_alert_message(log_group, message).as_array() {
-            Some(blocks) => blocks.to_vec(),
-            None => vec![],
+    /// Opens a Socket Mode connection with the app-level token and returns the WebSocket
+    /// URL to connect to.
+    pub(crate) async fn open_connection(
+        &self,
+        app_token: &str,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let client = Self {
+            token: app_token.to_string(),
+            ..self.clone()
         };
-        blocks.push(serde_json::json!({
-            "type": "section",
-            "text": {
-                "type": "mrkdwn",
-                "text": "_フィードバック済み_"
+        let resp = client
+            .call("apps.connections.open", Body::Form(vec![]))
+            .await?;
+
+        Ok(resp.url.ok_or("apps.connections.open returned no URL")?)
+    }
+
+    /// Posts the alert of a decision. A log message too long for a message block is
+    /// truncated and uploaded in full as a file in the alert's thread.
+    pub(crate) async fn post_alert(
+        &self,
+        channel_id: &str,
+        decision: &Decision,
+        mention_user_group: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let catalog = catalog(self.locale(channel_id));
+        let mut blocks = self.make_base_alert_message(catalog, decision);
+        if let Some(user_group) = mention_user_group {
+            blocks.insert(
+                1,
+                Block::section(
+                    Some(BlockId::Mention),
+                    Text::mrkdwn(format!("<!subteam^{}>", user_group)),
+                ),
+            );
+        }
+        blocks.extend(make_alert_controls(catalog, decision, None));
+
+        let resp = self
+            .call(
+                "chat.postMessage",
+                Body::Json(serde_json::json!({
+                    "channel": channel_id,
+                    "text": make_alert_fallback_text(
+                        catalog,
+                        decision.log_group(),
+                        decision.severity(),
+                        mention_user_group,
+                    ),
+                    "blocks": blocks
+                })),
+            )
+            .await?;
+        let ts = resp.ts.ok_or("ts not found")?;
+
+        // The alert is posted by now, so a failed upload must not fail the notification,
+        // whose retry would post the alert again.
+        if decision.message().chars().count() > MAX_SECTION_TEXT_LENGTH {
+            if let Err(e) = self
+                .upload_full_log(channel_id, &ts, catalog, decision)
+                .await
+            {
+                tracing::error!("Failed to upload full log: {:?}", e);
+                metrics::count("FullLogUploadFailed", "LogGroup", decision.log_group());
             }
-        }));
+        }
+
+        Ok(())
+    }
+
+    /// Uploads the full log message of a decision to a thread with files.uploadV2, which
+    /// consists of getting an upload URL, uploading to it and completing the upload.
+    async fn upload_full_log(
+        &self,
+        channel_id: &str,
+        thread_ts: &str,
+        catalog: &Catalog,
+        decision: &Decision,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let content = decision.message().as_bytes().to_vec();
+        let filename = format!("{}.log", decision.id());
+
+        let resp = self
+            .call(
+                "files.getUploadURLExternal",
+                Body::Form(vec![
+                    ("filename", filename.clone()),
+                    ("length", content.len().to_string()),
+                ]),
+            )
+            .await?;
+        let upload_url = resp.upload_url.ok_or("upload_url not found")?;
+        let file_id = resp.file_id.ok_or("file_id not found")?;
 
         let resp = self
             .inner_client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .json(&serde_json::json!({
+            .post(upload_url)
+            .body(content)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(format!("Failed to upload file: {}", resp.text().await?).into());
+        }
+
+        self.call(
+            "files.completeUploadExternal",
+            Body::Json(serde_json::json!({
+                "files": [{ "id": file_id, "title": filename }],
+                "channel_id": channel_id,
+                "thread_ts": thread_ts,
+                "initial_comment": catalog.full_log_comment,
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates an alert to show whether feedback was sent and the snooze created from it.
+    pub(crate) async fn update_alert(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        decision: &Decision,
+        snooze: Option<&Mute>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let catalog = catalog(self.locale(channel_id));
+        let mut blocks = self.make_base_alert_message(catalog, decision);
+        blocks.extend(make_alert_controls(catalog, decision, snooze));
+
+        self.call(
+            "chat.update",
+            Body::Json(serde_json::json!({
                 "channel": channel_id,
                 "ts": ts,
                 "blocks": blocks
-            }))
-            .send()
-            .await?;
+            })),
+        )
+        .await?;
 
-        if resp.status().is_success() {
-            let resp: Response = resp.json().await?;
-            if resp.ok {
-                Ok(())
-            } else {
-                Err(format!(
-                    "Failed to close feedback button: {}",
-                    resp.error.unwrap_or("Unknown".into())
-                )
-                .into())
-            }
-        } else {
-            Err(format!("Failed to close feedback button: {}", resp.text().await?).into())
-        }
+        Ok(())
     }
 
-    fn make_base_alert_message(&self, log_group: &str, message: &str) -> Value {
-        serde_json::json!([
-            {
-                "type": "header",
-                "block_id": "header",
-                "text": {
-                    "type": "plain_text",
-                    "text": ":rotating_light: エラーが発生しました :rotating_light:",
-                    "emoji": true
-                }
-            },
-            {
-                "type": "section",
-                "block_id": "log_group_header",
-                "text": {
-                    "type": "mrkdwn",
-                    "text": "*CloudWatch Logs ロググループ*"
-                }
-            },
-            {
-                "type": "section",
-                "block_id": "log_group",
-                "text": {
-                    "type": "plain_text",
-                    "text": log_group
-                }
-            },
-            {
-                "type": "section",
-                "block_id": "message_header",
-                "text": {
-                    "type": "mrkdwn",
-                    "text": "*ログメッセージ*"
-                }
-            },
-            {
-                "type": "section",
-                "block_id": "message",
-                "text": {
-                    "type": "plain_text",
-                    "text": message
-                }
+    fn make_base_alert_message(&self, catalog: &Catalog, decision: &Decision) -> Vec<Block> {
+        let severity = decision.severity();
+        let name = (catalog.severity_name)(severity);
+        let header = match severity {
+            Severity::Critical => format!(
+                ":rotating_light: [{}] {} :rotating_light:",
+                name, catalog.alert_header
+            ),
+            Severity::High => format!(":large_orange_circle: [{}] {}", name, catalog.alert_header),
+            Severity::Medium => {
+                format!(":large_yellow_circle: [{}] {}", name, catalog.alert_header)
+            }
+            Severity::Low => format!(":white_circle: [{}] {}", name, catalog.alert_header),
+        };
+
+        let mut blocks = vec![
+            Block::Header {
+                block_id: Some(BlockId::Header),
+                text: Text::plain_emoji(header),
             },
-            {
-                "type": "divider",
-                "block_id": "divider"
+            Block::section(
+                Some(BlockId::LogGroupHeader),
+                Text::mrkdwn(catalog.log_group_header),
+            ),
+            Block::section(Some(BlockId::LogGroup), Text::plain(decision.log_group())),
+        ];
+        if let Some(source) = decision.source() {
+            blocks.push(Block::context(
+                Some(BlockId::LogSource),
+                Text::mrkdwn((catalog.log_source)(
+                    source.log_stream(),
+                    source.event_time().as_secs(),
+                    source.owner(),
+                )),
+            ));
+            if let Some(region) = &self.aws_region {
+                blocks.push(make_log_links(
+                    catalog,
+                    region,
+                    decision.log_group(),
+                    source,
+                ));
             }
-        ])
+        }
+        blocks.extend([
+            Block::section(
+                Some(BlockId::MessageHeader),
+                Text::mrkdwn(catalog.message_header),
+            ),
+            Block::section(
+                Some(BlockId::Message),
+                Text::plain(truncate(
+                    decision.message(),
+                    MAX_SECTION_TEXT_LENGTH,
+                    catalog.truncated,
+                )),
+            ),
+        ]);
+        if let Some(context) = decision.context() {
+            blocks.push(make_log_context(catalog, context));
+        }
+        if let Some(explanation) = decision.explanation() {
+            blocks.push(Block::Context {
+                block_id: Some(BlockId::Explanation),
+                elements: vec![
+                    Text::mrkdwn(format!("*{}*", catalog.explanation_header)),
+                    Text::plain(truncate(
+                        explanation,
+                        MAX_CONTEXT_TEXT_LENGTH,
+                        catalog.truncated,
+                    )),
+                ],
+            });
+        }
+        blocks.push(Block::divider(Some(BlockId::Divider)));
+        blocks
     }
 
+    /// Opens the feedback modal for an alert, showing what the filter already knows about
+    /// the log.
     pub(crate) async fn open_modal(
         &self,
         trigger_id: &str,
+        channel_id: &str,
         private_metadata: &str,
+        decision: &Decision,
+        similar: &[&Feedback],
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/views.open", BASE_URL);
+        let view =
+            make_alert_feedback_view(self.locale(channel_id), private_metadata, decision, similar);
 
-        let view = make_feedback_view(private_metadata);
+        self.call(
+            "views.open",
+            Body::Json(serde_json::json!({
+                "trigger_id": trigger_id,
+                "view": view,
+            })),
+        )
+        .await?;
 
-        let resp = self
-            .inner_client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .json(&serde_json::json!({
+        Ok(())
+    }
+
+    /// Opens the feedback modal filled in with a feedback to edit it.
+    pub(crate) async fn open_feedback_editor(
+        &self,
+        trigger_id: &str,
+        feedback: &Feedback,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let view = make_feedback_editor_view(self.default_locale(), feedback);
+
+        self.call(
+            "views.open",
+            Body::Json(serde_json::json!({
                 "trigger_id": trigger_id,
                 "view": view,
-            }))
-            .send()
-            .await?;
+            })),
+        )
+        .await?;
 
-        if resp.status().is_success() {
-            let resp: Response = resp.json().await?;
-            if resp.ok {
-                Ok(())
-            } else {
-                Err(format!(
-                    "Failed to open modal: {{ error: \"{}\", response_metadata: \"{}\" }}",
-                    resp.error.unwrap_or_default(),
-                    resp.response_metadata.unwrap_or_default(),
-                )
-                .into())
-            }
-        } else {
-            Err(format!("Failed to open modal: {}", resp.text().await?).into())
+        Ok(())
+    }
+
+    /// Publishes the Home tab of a user.
+    pub(crate) async fn publish_home(
+        &self,
+        user_id: &str,
+        log_groups: &[(String, AlertCounts)],
+        feedback: &[Feedback],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let view = make_home_view(self.default_locale(), log_groups, feedback);
+
+        self.call(
+            "views.publish",
+            Body::Json(serde_json::json!({
+                "user_id": user_id,
+                "view": view,
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn open_snooze_modal(
+        &self,
+        trigger_id: &str,
+        channel_id: &str,
+        private_metadata: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let view = make_snooze_view(self.locale(channel_id), private_metadata);
+
+        self.call(
+            "views.open",
+            Body::Json(serde_json::json!({
+                "trigger_id": trigger_id,
+                "view": view,
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn post_rule_review(
+        &self,
+        channel_id: &str,
+        rule: &Rule,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let catalog = catalog(self.locale(channel_id));
+        let mut blocks = self.make_base_rule_message(catalog, rule);
+        blocks.push(Block::Actions {
+            block_id: Some(BlockId::RuleReview),
+            elements: vec![
+                Element::Button {
+                    text: Text::plain(catalog.approve),
+                    action_id: ActionId::ApproveRule,
+                    value: Some(rule.id().to_string()),
+                    style: Some(ButtonStyle::Primary),
+                    confirm: None,
+                    url: None,
+                },
+                Element::Button {
+                    text: Text::plain(catalog.reject),
+                    action_id: ActionId::RejectRule,
+                    value: Some(rule.id().to_string()),
+                    style: Some(ButtonStyle::Danger),
+                    confirm: None,
+                    url: None,
+                },
+            ],
+        });
+
+        self.call(
+            "chat.postMessage",
+            Body::Json(serde_json::json!({
+                "channel": channel_id,
+                "blocks": blocks
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn close_rule_review(
+        &self,
+        channel_id: &str,
+        ts: &str,
+        rule: &Rule,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let catalog = catalog(self.locale(channel_id));
+        let status = match rule.status() {
+            RuleStatus::Active => catalog.rule_active,
+            RuleStatus::Rejected => catalog.rule_rejected,
+            RuleStatus::Pending => catalog.rule_pending,
+        };
+        let mut blocks = self.make_base_rule_message(catalog, rule);
+        blocks.push(Block::section(
+            Some(BlockId::RuleStatus),
+            Text::mrkdwn(format!("_{}_ (<@{}>)", status, user_id)),
+        ));
+
+        self.call(
+            "chat.update",
+            Body::Json(serde_json::json!({
+                "channel": channel_id,
+                "ts": ts,
+                "blocks": blocks
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    fn make_base_rule_message(&self, catalog: &Catalog, rule: &Rule) -> Vec<Block> {
+        vec![
+            Block::Header {
+                block_id: Some(BlockId::RuleHeader),
+                text: Text::plain_emoji(catalog.rule_review_header),
+            },
+            Block::section(
+                Some(BlockId::RuleLogGroup),
+                Text::mrkdwn(format!(
+                    "{}\n{}",
+                    catalog.log_group_header,
+                    rule.log_group()
+                )),
+            ),
+            Block::section(Some(BlockId::Rule), Text::plain(rule.rule())),
+            Block::context(
+                Some(BlockId::RuleContext),
+                Text::mrkdwn((catalog.rule_context)(
+                    catalog.verdict(rule.needs_notification()),
+                    rule.source_feedback_ids().len(),
+                )),
+            ),
+            Block::divider(Some(BlockId::Divider)),
+        ]
+    }
+}
+
+/// Builds the buttons opening the log stream and a Logs Insights query around the event.
+fn make_log_links(catalog: &Catalog, region: &str, log_group: &str, source: &LogSource) -> Block {
+    let link = |text: &str, action_id: ActionId, url: String| Element::Button {
+        text: Text::plain(text),
+        action_id,
+        value: None,
+        style: None,
+        confirm: None,
+        url: Some(url),
+    };
+
+    Block::Actions {
+        block_id: Some(BlockId::LogLinks),
+        elements: vec![
+            link(
+                catalog.open_in_cloudwatch,
+                ActionId::OpenInCloudWatch,
+                log_events_url(region, log_group, source.log_stream(), source.event_time()),
+            ),
+            link(
+                catalog.open_logs_insights,
+                ActionId::OpenLogsInsights,
+                logs_insights_url(region, log_group, source.event_time()),
+            ),
+        ],
+    }
+}
+
+/// Builds the section showing the logs around the alerted one as a code block, which Slack
+/// folds behind "see more" when it is long.
+fn make_log_context(catalog: &Catalog, context: &LogContext) -> Block {
+    let lines: Vec<&str> = context
+        .before()
+        .iter()
+        .map(String::as_str)
+        .chain([catalog.log_context_marker])
+        .chain(context.after().iter().map(String::as_str))
+        .collect();
+    let header = format!("{}\n```\n", catalog.log_context_header);
+    let footer = "\n```";
+    let max_chars = MAX_SECTION_TEXT_LENGTH - header.chars().count() - footer.len();
+    let body = escape_mrkdwn(&lines.join("\n"));
+
+    Block::Section {
+        block_id: Some(BlockId::LogContext),
+        text: Some(Text::mrkdwn(format!(
+            "{}{}{}",
+            header,
+            truncate(&body, max_chars, catalog.truncated),
+            footer
+        ))),
+        accessory: None,
+        expand: Some(false),
+    }
+}
+
+/// Escapes the characters Slack reads as markup in mrkdwn, including inside code blocks,
+/// and breaks up fences that would end the code block early.
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace("```", "`\u{200b}``")
+}
+
+/// Returns the user group to mention in an alert: the route's on-call group, or else the
+/// default one, for critical alerts only.
+pub(crate) fn oncall_mention<'a>(
+    severity: Severity,
+    route_user_group: Option<&'a str>,
+    default_user_group: Option<&'a str>,
+) -> Option<&'a str> {
+    match severity {
+        Severity::Critical => route_user_group.or(default_user_group),
+        _ => None,
+    }
+}
+
+fn make_alert_fallback_text(
+    catalog: &Catalog,
+    log_group: &str,
+    severity: Severity,
+    mention_user_group: Option<&str>,
+) -> String {
+    let text = (catalog.alert_fallback)(severity, log_group);
+    match mention_user_group {
+        Some(user_group) => format!("<!subteam^{}> {}", user_group, text),
+        None => text,
+    }
+}
+
+/// Makes the blocks after the log message of an alert: the lifecycle, feedback and snooze
+/// statuses, and the buttons to acknowledge, resolve, send feedback and snooze.
+fn make_alert_controls(
+    catalog: &Catalog,
+    decision: &Decision,
+    snooze: Option<&Mute>,
+) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut elements = vec![];
+    let status = match (
+        decision.resolved_by().zip(decision.resolved_at()),
+        decision.acknowledged_by().zip(decision.acknowledged_at()),
+    ) {
+        (Some((user, at)), _) => Some((catalog.resolved_status)(user, at.as_secs())),
+        (None, Some((user, at))) => Some((catalog.acknowledged_status)(user, at.as_secs())),
+        (None, None) => None,
+    };
+    if let Some(status) = status {
+        blocks.push(Block::context(
+            Some(BlockId::AlertStatus),
+            Text::mrkdwn(status),
+        ));
+    }
+    if decision.acknowledged_at().is_none() {
+        elements.push(Element::Button {
+            text: Text::plain(catalog.acknowledge),
+            action_id: ActionId::Acknowledge,
+            value: Some(decision.id().to_string()),
+            style: Some(ButtonStyle::Primary),
+            confirm: None,
+            url: None,
+        });
+    }
+    if decision.resolved_at().is_none() {
+        elements.push(Element::Button {
+            text: Text::plain(catalog.resolve),
+            action_id: ActionId::Resolve,
+            value: Some(decision.id().to_string()),
+            style: None,
+            confirm: None,
+            url: None,
+        });
+    }
+
+    if decision.feedback_sent() {
+        blocks.push(Block::section(
+            Some(BlockId::FeedbackStatus),
+            Text::mrkdwn(catalog.feedback_done),
+        ));
+    } else {
+        elements.push(Element::Button {
+            text: Text::plain(catalog.feedback_button),
+            action_id: ActionId::OpenModal,
+            value: Some(decision.id().to_string()),
+            style: None,
+            confirm: None,
+            url: None,
+        });
+    }
+
+    match snooze {
+        Some(snooze) => {
+            blocks.push(Block::context(
+                Some(BlockId::SnoozeStatus),
+                Text::mrkdwn((catalog.snooze_status)(
+                    snooze.expires_at().as_secs(),
+                    snooze.created_by(),
+                    snooze.fingerprint().is_none(),
+                )),
+            ));
+            elements.push(Element::Button {
+                text: Text::plain(catalog.release_snooze),
+                action_id: ActionId::ReleaseSnooze,
+                value: Some(snooze.id().to_string()),
+                style: None,
+                confirm: None,
+                url: None,
+            });
+        }
+        None => {
+            let option = |hours: Option<i64>| SnoozeOption {
+                hours,
+                decision_id: decision.id().to_string(),
+            };
+            let mut options: Vec<SelectOption> = SNOOZE_HOURS
+                .iter()
+                .map(|&hours| {
+                    SelectOption::new((catalog.snooze_for)(hours), option(Some(hours)).to_string())
+                })
+                .collect();
+            options.push(SelectOption::new(
+                catalog.snooze_custom,
+                option(None).to_string(),
+            ));
+            elements.push(Element::Overflow {
+                action_id: ActionId::Snooze,
+                options,
+            });
         }
     }
+
+    blocks.push(Block::Actions {
+        block_id: Some(BlockId::FeedbackButton),
+        elements,
+    });
+    blocks
 }
 
-fn make_feedback_view(private_metadata: &str) -> Value {
-    serde_json::json!({
-        "type": "modal",
-        "callback_id": "send_feedback",
-        "private_metadata": private_metadata,
-        "title": {
-            "type": "plain_text",
-            "text": "フィードバック"
-        },
-        "blocks": [
-            {
-                "type": "section",
-                "block_id": "needs_notification",
-                "text": {
-                    "type": "plain_text",
-                    "text": "通知が必要ですか？"
+fn make_severity_option(catalog: &Catalog, severity: Severity) -> SelectOption {
+    SelectOption::new((catalog.severity_name)(severity), severity.to_string())
+}
+
+fn make_needs_notification_option(catalog: &Catalog, needs_notification: bool) -> SelectOption {
+    SelectOption::new(
+        catalog.verdict(needs_notification),
+        needs_notification.to_string(),
+    )
+}
+
+fn make_feedback_view(
+    locale: Locale,
+    callback_id: CallbackId,
+    private_metadata: &str,
+    needs_notification: bool,
+    severity: Option<Severity>,
+    reason: Option<&str>,
+) -> Modal {
+    let catalog = catalog(locale);
+
+    Modal {
+        callback_id,
+        private_metadata: private_metadata.to_string(),
+        title: Text::plain(catalog.feedback_title),
+        blocks: vec![
+            Block::Section {
+                block_id: Some(BlockId::NeedsNotification),
+                text: Some(Text::plain(catalog.needs_notification_label)),
+                accessory: Some(Element::StaticSelect {
+                    action_id: ActionId::NeedsNotification,
+                    options: vec![
+                        make_needs_notification_option(catalog, false),
+                        make_needs_notification_option(catalog, true),
+                    ],
+                    initial_option: Some(make_needs_notification_option(
+                        catalog,
+                        needs_notification,
+                    )),
+                }),
+                expand: None,
+            },
+            Block::Input {
+                block_id: Some(BlockId::Severity),
+                label: Text::plain(catalog.severity_label),
+                element: Element::StaticSelect {
+                    action_id: ActionId::Severity,
+                    options: vec![
+                        make_severity_option(catalog, Severity::Critical),
+                        make_severity_option(catalog, Severity::High),
+                        make_severity_option(catalog, Severity::Medium),
+                        make_severity_option(catalog, Severity::Low),
+                    ],
+                    initial_option: severity.map(|v| make_severity_option(catalog, v)),
                 },
-                "accessory": {
-                    "type": "static_select",
-                    "action_id": "needs_notification",
-                    "initial_option": {
-                        "text": {
-                            "type": "plain_text",
-                            "text": "不要"
-                        },
-                        "value": "false"
-                    },
-                    "options": [
-                        {
-                            "text": {
-                                "type": "plain_text",
-                                "text": "不要"
-                            },
-                            "value": "false"
-                        },
-                        {
-                            "text": {
-                                "type": "plain_text",
-                                "text": "必要"
-                            },
-                            "value": "true"
-                        },
-                    ]
-                }
+                optional: true,
             },
-            {
-                "type": "input",
-                "block_id": "reason",
-                "label": {
-                    "type": "plain_text",
-                    "text": "理由"
+            Block::Input {
+                block_id: Some(BlockId::Reason),
+                label: Text::plain(catalog.reason_label),
+                element: Element::PlainTextInput {
+                    action_id: ActionId::Reason,
+                    multiline: true,
+                    initial_value: reason.map(str::to_string),
+                },
+                optional: true,
+            },
+        ],
+        close: Some(Text::plain(catalog.cancel)),
+        submit: Some(Text::plain(catalog.submit)),
+    }
+}
+
+/// Number of similar feedback entries shown in the feedback modal of an alert.
+pub(crate) const MAX_SIMILAR_FEEDBACK: usize = 5;
+
+/// Builds the feedback modal of an alert, showing the log message, the reasoning of the
+/// judgement and the feedback given for the most similar logs above the inputs.
+fn make_alert_feedback_view(
+    locale: Locale,
+    private_metadata: &str,
+    decision: &Decision,
+    similar: &[&Feedback],
+) -> Modal {
+    let catalog = catalog(locale);
+    let mut blocks = vec![
+        Block::section(
+            Some(BlockId::Message),
+            Text::plain(truncate(
+                decision.message(),
+                MAX_SECTION_TEXT_LENGTH,
+                catalog.truncated,
+            )),
+        ),
+        Block::context(
+            Some(BlockId::Explanation),
+            Text::mrkdwn(match decision.explanation() {
+                Some(explanation) => truncate(
+                    &format!("*{}*: {}", catalog.explanation_header, explanation),
+                    MAX_CONTEXT_TEXT_LENGTH,
+                    catalog.truncated,
+                )
+                .into_owned(),
+                None => catalog.no_explanation.to_string(),
+            }),
+        ),
+        Block::divider(None),
+        Block::section(
+            Some(BlockId::SimilarFeedbackHeader),
+            Text::mrkdwn(format!("*{}*", catalog.similar_feedback_header)),
+        ),
+    ];
+    if similar.is_empty() {
+        blocks.push(Block::context(
+            None,
+            Text::plain(catalog.no_similar_feedback),
+        ));
+    }
+    for feedback in similar.iter().take(MAX_SIMILAR_FEEDBACK) {
+        push_feedback_entry(&mut blocks, catalog, feedback, MAX_LISTED_MESSAGE_LENGTH);
+    }
+    blocks.push(Block::divider(None));
+
+    let mut view = make_feedback_view(
+        locale,
+        CallbackId::SendFeedback,
+        private_metadata,
+        false,
+        Some(decision.severity()),
+        None,
+    );
+    blocks.append(&mut view.blocks);
+    view.blocks = blocks;
+    view
+}
+
+/// Adds a feedback entry to a modal: its message, then who gave which verdict when and why.
+fn push_feedback_entry(
+    blocks: &mut Vec<Block>,
+    catalog: &Catalog,
+    feedback: &Feedback,
+    max_message_length: usize,
+) {
+    let author = feedback
+        .user_id()
+        .map(|user_id| format!("<@{}>", user_id))
+        .unwrap_or(catalog.unknown_user.into());
+    let mut context = format!(
+        "{} ・ <!date^{}^{{date_num}} {{time}}|{}>",
+        (catalog.feedback_context)(catalog.verdict(feedback.needs_notification()), &author),
+        feedback.created_at(),
+        feedback.created_at(),
+    );
+    if let Some(reason) = feedback.reason() {
+        context.push_str(&format!("\n{}: {}", catalog.reason_label, reason));
+    }
+
+    blocks.push(Block::section(
+        None,
+        Text::plain(truncate(
+            feedback.message(),
+            max_message_length,
+            catalog.truncated,
+        )),
+    ));
+    blocks.push(Block::context(
+        None,
+        Text::mrkdwn(truncate(
+            &context,
+            MAX_CONTEXT_TEXT_LENGTH,
+            catalog.truncated,
+        )),
+    ));
+}
+
+/// Builds the feedback modal for editing a feedback, showing the message it was given for.
+/// The feedback is identified by its id in `private_metadata`.
+fn make_feedback_editor_view(locale: Locale, feedback: &Feedback) -> Modal {
+    let catalog = catalog(locale);
+    let mut view = make_feedback_view(
+        locale,
+        CallbackId::EditFeedback,
+        &feedback.id().to_string(),
+        feedback.needs_notification(),
+        feedback.severity(),
+        feedback.reason().map(String::as_str),
+    );
+    view.title = Text::plain(catalog.edit_feedback_title);
+    view.submit = Some(Text::plain(catalog.save));
+    view.blocks.insert(
+        0,
+        Block::section(
+            Some(BlockId::Message),
+            Text::plain(truncate(
+                feedback.message(),
+                MAX_SECTION_TEXT_LENGTH,
+                catalog.truncated,
+            )),
+        ),
+    );
+    view.blocks.insert(
+        0,
+        Block::context(
+            Some(BlockId::LogGroup),
+            Text::mrkdwn(format!("`{}`", feedback.log_group())),
+        ),
+    );
+    view
+}
+
+fn make_snooze_view(locale: Locale, private_metadata: &str) -> Modal {
+    let catalog = catalog(locale);
+    let message = SelectOption::new(catalog.snooze_scope_message, "message");
+
+    Modal {
+        callback_id: CallbackId::Snooze,
+        private_metadata: private_metadata.to_string(),
+        title: Text::plain(catalog.snooze_title),
+        blocks: vec![
+            Block::Input {
+                block_id: Some(BlockId::SnoozeUntil),
+                label: Text::plain(catalog.snooze_until_label),
+                element: Element::DatetimePicker {
+                    action_id: ActionId::SnoozeUntil,
+                    initial_date_time: Some(
+                        Timestamp::new().add(chrono::Duration::days(1)).as_secs(),
+                    ),
                 },
-                "element": {
-                    "type": "plain_text_input",
-                    "action_id": "reason",
-                    "multiline": true
+                optional: false,
+            },
+            Block::Input {
+                block_id: Some(BlockId::SnoozeScope),
+                label: Text::plain(catalog.snooze_scope_label),
+                element: Element::StaticSelect {
+                    action_id: ActionId::SnoozeScope,
+                    options: vec![
+                        message.clone(),
+                        SelectOption::new(catalog.snooze_scope_log_group, "log_group"),
+                    ],
+                    initial_option: Some(message),
                 },
-                "optional": true
+                optional: false,
             },
         ],
-        "close": {
-            "type": "plain_text",
-            "text": "キャンセル"
-        },
-        "submit": {
-            "type": "plain_text",
-            "text": "送信"
+        close: Some(Text::plain(catalog.cancel)),
+        submit: Some(Text::plain(catalog.snooze)),
+    }
+}
+
+/// Maximum number of conflicting feedback entries listed in the conflict modal.
+const MAX_CONFLICTS_SHOWN: usize = 10;
+
+pub(crate) fn make_conflict_view(
+    locale: Locale,
+    private_metadata: &str,
+    conflicts: &[Feedback],
+) -> Modal {
+    let catalog = catalog(locale);
+    let mut blocks = vec![Block::section(
+        Some(BlockId::ConflictHeader),
+        Text::mrkdwn((catalog.conflict_header)(conflicts.len())),
+    )];
+
+    for feedback in conflicts.iter().take(MAX_CONFLICTS_SHOWN) {
+        blocks.push(Block::divider(None));
+        push_feedback_entry(&mut blocks, catalog, feedback, MAX_SECTION_TEXT_LENGTH);
+    }
+
+    let replace = SelectOption::new(catalog.resolution_replace, "replace");
+    blocks.push(Block::divider(None));
+    blocks.push(Block::Input {
+        block_id: Some(BlockId::Resolution),
+        label: Text::plain(catalog.resolution_label),
+        element: Element::StaticSelect {
+            action_id: ActionId::Resolution,
+            options: vec![
+                replace.clone(),
+                SelectOption::new(catalog.resolution_retire, "retire"),
+                SelectOption::new(catalog.resolution_cancel, "cancel"),
+            ],
+            initial_option: Some(replace),
         },
-    })
+        optional: false,
+    });
+
+    Modal {
+        callback_id: CallbackId::ResolveConflict,
+        private_metadata: private_metadata.to_string(),
+        title: Text::plain(catalog.conflict_title),
+        blocks,
+        close: Some(Text::plain(catalog.cancel)),
+        submit: Some(Text::plain(catalog.confirm)),
+    }
+}
+
+/// Number of log groups counted in the Home tab, which queries the decisions of each.
+pub(crate) const MAX_HOME_LOG_GROUPS: usize = 10;
+
+/// Number of feedback entries listed in the Home tab, newest first.
+const MAX_HOME_FEEDBACK: usize = 10;
+
+/// Builds the Home tab from the alert counts of the user's log groups and the user's
+/// feedback, newest first.
+fn make_home_view(
+    locale: Locale,
+    log_groups: &[(String, AlertCounts)],
+    feedback: &[Feedback],
+) -> HomeView {
+    let catalog = catalog(locale);
+    let mut blocks = vec![Block::Header {
+        block_id: None,
+        text: Text::plain(catalog.home_log_groups_header),
+    }];
+    if log_groups.is_empty() {
+        blocks.push(Block::context(
+            None,
+            Text::plain(catalog.home_no_log_groups),
+        ));
+    }
+    for (log_group, counts) in log_groups.iter().take(MAX_HOME_LOG_GROUPS) {
+        blocks.push(Block::section(
+            None,
+            Text::mrkdwn(format!(
+                "*`{}`*\n{}",
+                log_group,
+                (catalog.home_counts)(counts.alerted, counts.suppressed, counts.muted)
+            )),
+        ));
+    }
+
+    blocks.push(Block::divider(None));
+    blocks.push(Block::Header {
+        block_id: None,
+        text: Text::plain(catalog.home_feedback_header),
+    });
+    if feedback.is_empty() {
+        blocks.push(Block::context(None, Text::plain(catalog.home_no_feedback)));
+    }
+    for v in feedback.iter().take(MAX_HOME_FEEDBACK) {
+        let mut context = format!(
+            "`{}` ・ {}: *{}* ・ <!date^{}^{{date_num}}|{}>",
+            v.log_group(),
+            catalog.notification_label,
+            catalog.verdict(v.needs_notification()),
+            v.created_at(),
+            v.created_at(),
+        );
+        if v.retired() {
+            context.push_str(&format!(" {}", catalog.retired));
+        }
+        if let Some(reason) = v.reason() {
+            context.push_str(&format!("\n{}: {}", catalog.reason_label, reason));
+        }
+
+        blocks.push(Block::section(
+            None,
+            Text::plain(truncate(
+                v.message(),
+                MAX_LISTED_MESSAGE_LENGTH,
+                catalog.truncated,
+            )),
+        ));
+        blocks.push(Block::context(
+            None,
+            Text::mrkdwn(truncate(
+                &context,
+                MAX_CONTEXT_TEXT_LENGTH,
+                catalog.truncated,
+            )),
+        ));
+        blocks.push(Block::Actions {
+            block_id: None,
+            elements: vec![
+                Element::Button {
+                    text: Text::plain(catalog.edit),
+                    action_id: ActionId::EditFeedback,
+                    value: Some(v.id().to_string()),
+                    style: None,
+                    confirm: None,
+                    url: None,
+                },
+                Element::Button {
+                    text: Text::plain(catalog.delete),
+                    action_id: ActionId::DeleteFeedback,
+                    value: Some(v.id().to_string()),
+                    style: Some(ButtonStyle::Danger),
+                    confirm: Some(Confirm {
+                        title: Text::plain(catalog.delete_feedback_title),
+                        text: Text::plain(catalog.delete_feedback_text),
+                        confirm: Text::plain(catalog.delete),
+                        deny: Text::plain(catalog.cancel),
+                        style: Some(ButtonStyle::Danger),
+                    }),
+                    url: None,
+                },
+            ],
+        });
+    }
+
+    HomeView { blocks }
+}
+
+/// Number of feedback entries listed by the slash command, newest first.
+const MAX_FEEDBACK_LISTED: usize = 20;
+
+/// Messages in command responses are cut to this many characters.
+const MAX_LISTED_MESSAGE_LENGTH: usize = 200;
+
+fn ephemeral(text: String) -> CommandResponse {
+    CommandResponse {
+        response_type: ResponseType::Ephemeral,
+        text,
+    }
+}
+
+pub(crate) fn make_usage_response(locale: Locale) -> CommandResponse {
+    ephemeral(catalog(locale).command_usage.to_string())
+}
+
+pub(crate) fn make_command_failed_response(locale: Locale) -> CommandResponse {
+    ephemeral(catalog(locale).command_failed.to_string())
+}
+
+pub(crate) fn make_command_forbidden_response(locale: Locale, log_group: &str) -> CommandResponse {
+    ephemeral((catalog(locale).command_forbidden)(log_group))
+}
+
+pub(crate) fn make_status_response(
+    locale: Locale,
+    model_id: &str,
+    top_p: f32,
+    temperature: f32,
+    route_count: usize,
+    mutes: &[Mute],
+) -> CommandResponse {
+    let catalog = catalog(locale);
+    let mut lines = vec![
+        (catalog.status_model)(model_id, top_p, temperature),
+        (catalog.status_routes)(route_count),
+    ];
+    if mutes.is_empty() {
+        lines.push(catalog.status_no_mutes.to_string());
+    } else {
+        lines.push(catalog.status_mutes.to_string());
+        lines.extend(mutes.iter().map(|v| {
+            let entry = (catalog.mute_entry)(v.log_group(), v.expires_at().as_secs());
+            match v.fingerprint() {
+                Some(_) => format!("{} ・ {}", entry, catalog.snooze_scope_message),
+                None => entry,
+            }
+        }));
+    }
+
+    ephemeral(lines.join("\n"))
+}
+
+/// Counts the decisions made since `day_start` and all given decisions, and summarizes the
+/// response times of all given decisions.
+pub(crate) fn make_stats_response(
+    locale: Locale,
+    log_group: &str,
+    decisions: &[Decision],
+    day_start: &Timestamp,
+) -> CommandResponse {
+    let count = |predicate: fn(&Decision) -> bool| {
+        [
+            decisions
+                .iter()
+                .filter(|v| v.created_at() >= day_start && predicate(v))
+                .count(),
+            decisions.iter().filter(|v| predicate(v)).count(),
+        ]
+    };
+
+    let catalog = catalog(locale);
+    let response_times = ResponseTimes::new(decisions);
+    ephemeral(format!(
+        "{}\n{}",
+        (catalog.stats)(
+            log_group,
+            count(|v| v.alerted()),
+            count(|v| !v.needs_notification()),
+            count(|v| v.muted()),
+        ),
+        (catalog.response_times)(
+            response_times.acknowledged,
+            response_times.mtta_seconds,
+            response_times.resolved,
+            response_times.mttr_seconds,
+        )
+    ))
+}
+
+pub(crate) fn make_feedback_list_response(
+    locale: Locale,
+    log_group: &str,
+    feedback: &[Feedback],
+) -> CommandResponse {
+    let catalog = catalog(locale);
+    if feedback.is_empty() {
+        return ephemeral((catalog.feedback_list_empty)(log_group));
+    }
+
+    let mut sorted: Vec<&Feedback> = feedback.iter().collect();
+    sorted.sort_by(|a, b| b.created_at().cmp(a.created_at()));
+    let mut lines = vec![(catalog.feedback_list_header)(log_group, feedback.len())];
+    for v in sorted.into_iter().take(MAX_FEEDBACK_LISTED) {
+        let author = v
+            .user_id()
+            .map(|user_id| format!("<@{}>", user_id))
+            .unwrap_or(catalog.unknown_user.into());
+        let mut line = format!(
+            "• {}: *{}* ・ {} ・ <!date^{}^{{date_num}}|{}>",
+            catalog.notification_label,
+            catalog.verdict(v.needs_notification()),
+            author,
+            v.created_at(),
+            v.created_at(),
+        );
+        if v.retired() {
+            line.push_str(&format!(" {}", catalog.retired));
+        }
+        line.push_str(&format!(
+            "\n```{}```",
+            truncate(v.message(), MAX_LISTED_MESSAGE_LENGTH, catalog.truncated)
+        ));
+        if let Some(reason) = v.reason() {
+            line.push_str(&format!("\n{}: {}", catalog.reason_label, reason));
+        }
+        lines.push(line);
+    }
+
+    ephemeral(lines.join("\n"))
+}
+
+pub(crate) fn make_test_response(locale: Locale, judgement: &Judgement) -> CommandResponse {
+    ephemeral(make_judgement_text(catalog(locale), judgement))
+}
+
+fn make_judgement_text(catalog: &Catalog, judgement: &Judgement) -> String {
+    let mut text = (catalog.test_result)(
+        catalog.verdict(judgement.needs_notification),
+        judgement.severity,
+    );
+    if let Some(explanation) = &judgement.explanation {
+        text.push_str(&format!(
+            "\n{}: {}",
+            catalog.explanation_header, explanation
+        ));
+    }
+    text
+}
+
+/// The mute is announced to the channel so that nobody is surprised by the silence.
+pub(crate) fn make_mute_response(locale: Locale, mute: &Mute) -> CommandResponse {
+    CommandResponse {
+        response_type: ResponseType::InChannel,
+        text: (catalog(locale).muted)(mute.log_group(), mute.expires_at().as_secs()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::{DecisionId, FeedbackId, Timestamp};
+    use crate::util::test_server;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serves canned responses for a Slack method, one per request, and counts the requests.
+    async fn serve(
+        responses: Vec<(u16, Option<&'static str>, &'static str)>,
+    ) -> (String, Arc<AtomicUsize>) {
+        serve_method("chat.update", responses).await
+    }
+
+    async fn serve_method(
+        method: &str,
+        responses: Vec<(u16, Option<&'static str>, &'static str)>,
+    ) -> (String, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let app = axum::Router::new().route(
+            &format!("/{}", method),
+            axum::routing::post(move || {
+                let i = counter.fetch_add(1, Ordering::SeqCst);
+                let (status, retry_after, body) = responses[i.min(responses.len() - 1)];
+                async move {
+                    let mut resp = axum::response::Response::builder()
+                        .status(status)
+                        .header("content-type", "application/json");
+                    if let Some(retry_after) = retry_after {
+                        resp = resp.header("retry-after", retry_after);
+                    }
+                    resp.body(axum::body::Body::from(body)).unwrap()
+                }
+            }),
+        );
+        (test_server(app).await, count)
+    }
+
+    fn make_client(base_url: String, deadline: Duration) -> Client {
+        Client::builder()
+            .inner_client(reqwest::Client::new())
+            .token("token".into())
+            .base_url(base_url)
+            .deadline(deadline)
+            .build()
+    }
+
+    fn update_body() -> Body {
+        Body::Json(serde_json::json!({ "channel": "C123", "ts": "1.0" }))
+    }
+
+    #[tokio::test]
+    async fn test_call_retries_rate_limited() {
+        let (base_url, count) = serve(vec![
+            (429, Some("0"), ""),
+            (200, Some("0"), r#"{"ok":false,"error":"ratelimited"}"#),
+            (200, None, r#"{"ok":true,"ts":"1.0"}"#),
+        ])
+        .await;
+        let client = make_client(base_url, Duration::from_secs(5));
+
+        let resp = client.call("chat.update", update_body()).await.unwrap();
+
+        assert_eq!(resp.ts.as_deref(), Some("1.0"));
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_call_does_not_retry_permanent_errors() {
+        let (base_url, count) = serve(vec![(
+            200,
+            None,
+            r#"{"ok":false,"error":"channel_not_found"}"#,
+        )])
+        .await;
+        let client = make_client(base_url, Duration::from_secs(5));
+
+        let err = client.call("chat.update", update_body()).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            SlackError::Api {
+                code: ErrorCode::ChannelNotFound,
+                ..
+            }
+        ));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_call_does_not_retry_ambiguous_posts() {
+        let (base_url, count) = serve_method(
+            "chat.postMessage",
+            vec![(503, None, ""), (200, None, r#"{"ok":true,"ts":"1.0"}"#)],
+        )
+        .await;
+        let client = make_client(base_url, Duration::from_secs(5));
+
+        // The message may have been posted before the server error.
+        let err = client
+            .call("chat.postMessage", update_body())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SlackError::Http { status: 503, .. }));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // A rate-limited post was not handled, so it is retried.
+        let (base_url, count) = serve_method(
+            "chat.postMessage",
+            vec![
+                (429, Some("0"), ""),
+                (200, None, r#"{"ok":true,"ts":"1.0"}"#),
+            ],
+        )
+        .await;
+        let client = make_client(base_url, Duration::from_secs(5));
+
+        let resp = client
+            .call("chat.postMessage", update_body())
+            .await
+            .unwrap();
+        assert_eq!(resp.ts.as_deref(), Some("1.0"));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_call_gives_up_at_deadline() {
+        let (base_url, count) = serve(vec![(429, Some("60"), "")]).await;
+        let client = make_client(base_url, Duration::from_secs(1));
+
+        let err = client.call("chat.update", update_body()).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            SlackError::DeadlineExceeded { last: Some(_), .. }
+        ));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        // Later calls of the method wait for Retry-After instead of hitting Slack.
+        let err = client.call("chat.update", update_body()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            SlackError::DeadlineExceeded { last: None, .. }
+        ));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oauth_access() {
+        let app = axum::Router::new().route(
+            "/oauth.v2.access",
+            axum::routing::post(
+                |headers: axum::http::HeaderMap,
+                 axum::Form(form): axum::Form<std::collections::HashMap<String, String>>| async move {
+                    // The code is exchanged with the app's credentials, not a bot token.
+                    let authorized = !headers.contains_key("authorization")
+                        && form["client_secret"] == "secret"
+                        && form["code"] == "code";
+                    axum::Json(if authorized {
+                        serde_json::json!({
+                            "ok": true,
+                            "access_token": "xoxb-team",
+                            "bot_user_id": "U0BOT",
+                            "team": { "id": "T0123", "name": "Example" },
+                            "authed_user": { "id": "U0123" }
+                        })
+                    } else {
+                        serde_json::json!({ "ok": false, "error": "invalid_code" })
+                    })
+                },
+            ),
+        );
+        let base_url = test_server(app).await;
+        let client = make_client(base_url.clone(), Duration::from_secs(5));
+
+        let installation = client
+            .oauth_access(
+                "id",
+                "secret",
+                "code",
+                "https://example.com/slack/oauth_redirect",
+            )
+            .await
+            .unwrap();
+        assert_eq!(installation.team_id(), "T0123");
+        assert_eq!(installation.team_name(), Some("Example"));
+        assert_eq!(installation.bot_token(), "xoxb-team");
+        assert_eq!(installation.installed_by(), Some("U0123"));
+
+        assert!(client
+            .oauth_access(
+                "id",
+                "other",
+                "code",
+                "https://example.com/slack/oauth_redirect"
+            )
+            .await
+            .is_err());
+        // Without an installation store the configured token is used for every team.
+        assert_eq!(client.for_team(Some("T0123")).await.unwrap().token, "token");
+    }
+
+    #[tokio::test]
+    async fn test_report_task_failure() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/{path}",
+            axum::routing::post(
+                move |axum::extract::Path(path): axum::extract::Path<String>,
+                      axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    sender.send((path, body)).unwrap();
+                    axum::Json(serde_json::json!({ "ok": true }))
+                },
+            ),
+        );
+        let base_url = test_server(app).await;
+        let client = make_client(base_url.clone(), Duration::from_secs(5));
+
+        let response_url = format!("{}/response", base_url);
+        client
+            .report_task_failure(Some(&response_url), Some("C123"), "U123")
+            .await
+            .unwrap();
+        let (path, body) = receiver.recv().await.unwrap();
+        assert_eq!(path, "response");
+        assert_eq!(body["response_type"], "ephemeral");
+        assert_eq!(body["replace_original"], false);
+
+        client
+            .report_task_failure(None, Some("C123"), "U123")
+            .await
+            .unwrap();
+        let (path, body) = receiver.recv().await.unwrap();
+        assert_eq!(path, "chat.postEphemeral");
+        assert_eq!(body["channel"], "C123");
+        assert_eq!(body["user"], "U123");
+
+        assert!(client
+            .report_task_failure(None, None, "U123")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_alert_upload_failure() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/{method}",
+            axum::routing::post(
+                move |axum::extract::Path(method): axum::extract::Path<String>| async move {
+                    sender.send(method.clone()).unwrap();
+                    axum::Json(match method.as_str() {
+                        "chat.postMessage" => serde_json::json!({ "ok": true, "ts": "1.0" }),
+                        _ => serde_json::json!({ "ok": false, "error": "missing_scope" }),
+                    })
+                },
+            ),
+        );
+        let client = make_client(test_server(app).await, Duration::from_secs(5));
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("x".repeat(MAX_SECTION_TEXT_LENGTH + 1))
+            .needs_notification(true)
+            .severity(Severity::High)
+            .build();
+
+        // The alert went out, so the failed upload of the full log is not an error.
+        client.post_alert("C123", &decision, None).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "chat.postMessage");
+        assert_eq!(receiver.recv().await.unwrap(), "files.getUploadURLExternal");
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_alert_message_round_trip() {
+        let client = Client::builder()
+            .inner_client(reqwest::Client::new())
+            .token("token".into())
+            .build();
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("x".repeat(MAX_SECTION_TEXT_LENGTH + 1))
+            .needs_notification(true)
+            .severity(Severity::High)
+            .explanation(Some("explanation".into()))
+            .build();
+        let catalog = catalog(Locale::En);
+        let blocks = client.make_base_alert_message(catalog, &decision);
+
+        assert_eq!(round_trip(&blocks), blocks);
+
+        let message = blocks
+            .iter()
+            .find_map(|block| match block {
+                Block::Section {
+                    block_id: Some(BlockId::Message),
+                    text: Some(Text::PlainText { text, .. }),
+                    ..
+                } => Some(text),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(message.chars().count(), MAX_SECTION_TEXT_LENGTH);
+        assert!(message.ends_with(catalog.truncated));
+    }
+
+    #[test]
+    fn test_alert_log_source() {
+        let client = Client::builder()
+            .inner_client(reqwest::Client::new())
+            .token("token".into())
+            .aws_region(Some("ap-northeast-1".into()))
+            .build();
+        let source = LogSource::builder()
+            .log_stream("2025/01/01/[$LATEST]abc".into())
+            .owner("123456789012".into())
+            .subscription_filters(vec!["filter".into()])
+            .event_id("36000000000000000000000000000000000000000000000000000000".into())
+            .event_time(Timestamp::from_secs(1_735_689_600))
+            .build();
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR".into())
+            .needs_notification(true)
+            .severity(Severity::High)
+            .source(Some(source))
+            .build();
+        let blocks = client.make_base_alert_message(catalog(Locale::En), &decision);
+
+        assert_eq!(round_trip(&blocks), blocks);
+        let json = serde_json::to_value(&blocks).unwrap();
+        assert_eq!(json[3]["block_id"], "log_source");
+        assert!(json[3]["elements"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("`2025/01/01/[$LATEST]abc`"));
+        assert_eq!(json[4]["block_id"], "log_links");
+        assert!(json[4]["elements"][0]["url"]
+            .as_str()
+            .unwrap()
+            .contains("log-events/2025$252F01$252F01"));
+        assert!(json[4]["elements"][1]["url"]
+            .as_str()
+            .unwrap()
+            .contains("logs-insights"));
+    }
+
+    #[test]
+    fn test_alert_header() {
+        let client = Client::builder()
+            .inner_client(reqwest::Client::new())
+            .token("token".into())
+            .build();
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR".into())
+            .needs_notification(true)
+            .severity(Severity::Critical)
+            .build();
+        let header = |locale: Locale| {
+            let blocks = client.make_base_alert_message(catalog(locale), &decision);
+            serde_json::to_value(&blocks).unwrap()[0]["text"]["text"].clone()
+        };
+
+        assert_eq!(
+            header(Locale::Ja),
+            ":rotating_light: [緊急] エラーが発生しました :rotating_light:"
+        );
+        assert_eq!(
+            header(Locale::En),
+            ":rotating_light: [Critical] An error occurred :rotating_light:"
+        );
+    }
+
+    #[test]
+    fn test_alert_log_context() {
+        let client = Client::builder()
+            .inner_client(reqwest::Client::new())
+            .token("token".into())
+            .build();
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR".into())
+            .needs_notification(true)
+            .severity(Severity::High)
+            .context(Some(LogContext::new(
+                vec!["GET /items <200>".into()],
+                vec!["retrying ```".into()],
+                100,
+            )))
+            .build();
+        let blocks = client.make_base_alert_message(catalog(Locale::En), &decision);
+
+        assert_eq!(round_trip(&blocks), blocks);
+        let json = serde_json::to_value(&blocks).unwrap();
+        assert_eq!(json[5]["block_id"], "log_context");
+        assert_eq!(json[5]["expand"], false);
+        assert_eq!(
+            json[5]["text"]["text"],
+            "*Surrounding logs*\n```\nGET /items &lt;200&gt;\n───── alerted log ─────\nretrying `\u{200b}``\n```"
+        );
+    }
+
+    #[test]
+    fn test_feedback_view_round_trip() {
+        let view = make_feedback_view(
+            Locale::Ja,
+            CallbackId::SendFeedback,
+            "metadata",
+            false,
+            Some(Severity::Critical),
+            None,
+        );
+
+        assert_eq!(round_trip(&view), view);
+        assert_eq!(
+            serde_json::to_value(&view).unwrap()["callback_id"],
+            "send_feedback"
+        );
+    }
+
+    #[test]
+    fn test_alert_feedback_view() {
+        let decision = Decision::builder()
+            .id(DecisionId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR: connection reset by peer".into())
+            .needs_notification(true)
+            .severity(Severity::High)
+            .explanation(Some("Connections keep failing".into()))
+            .build();
+        let feedback = Feedback::builder()
+            .id(FeedbackId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR: connection reset".into())
+            .needs_notification(false)
+            .reason(None)
+            .user_id(Some("U123".into()))
+            .build();
+        let view = make_alert_feedback_view(Locale::En, "metadata", &decision, &[&feedback]);
+
+        assert_eq!(round_trip(&view), view);
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["callback_id"], "send_feedback");
+        assert_eq!(
+            json["blocks"][1]["elements"][0]["text"],
+            "*Reasoning*: Connections keep failing"
+        );
+        assert_eq!(json["blocks"][4]["text"]["text"], "ERROR: connection reset");
+        assert!(json["blocks"][5]["elements"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("<@U123>"));
+        // The inputs follow the alert and keep the severity of the judgement.
+        assert_eq!(
+            json["blocks"][8]["element"]["initial_option"]["value"],
+            "high"
+        );
+    }
+
+    #[test]
+    fn test_home_view_round_trip() {
+        let feedback = Feedback::builder()
+            .id(FeedbackId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/my-function".into())
+            .message("ERROR: connection reset by peer".into())
+            .needs_notification(true)
+            .reason(Some("Customers are affected".into()))
+            .user_id(Some("U123".into()))
+            .build();
+        let view = make_home_view(
+            Locale::En,
+            &[("/aws/lambda/my-function".into(), AlertCounts::default())],
+            std::slice::from_ref(&feedback),
+        );
+
+        assert_eq!(round_trip(&view), view);
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["type"], "home");
+        assert_eq!(
+            json["blocks"][6]["elements"][1]["value"],
+            feedback.id().to_string()
+        );
+
+        let editor = make_feedback_editor_view(Locale::En, &feedback);
+        assert_eq!(round_trip(&editor), editor);
+        assert_eq!(
+            serde_json::to_value(&editor).unwrap()["private_metadata"],
+            feedback.id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_snooze_view_round_trip() {
+        let view = make_snooze_view(Locale::En, "metadata");
+
+        assert_eq!(round_trip(&view), view);
+        assert_eq!(
+            serde_json::to_value(&view).unwrap()["blocks"][0]["element"]["type"],
+            "datetimepicker"
+        );
+    }
+
+    #[test]
+    fn test_answered_mention() {
+        let message = |user: &str, text: &str| HistoryMessage {
+            user: Some(user.to_string()),
+            text: Some(text.to_string()),
+        };
+        let alert = message("B1", "alert");
+        let upload = message("B1", "full log");
+
+        // The alert and the full log posted by the bot are not answers.
+        let thread = [alert, upload, message("U1", "looking into this")];
+        assert!(!answered_mention(&thread, "B1"));
+
+        let [alert, upload, reply] = thread;
+        let mention = message("U1", "<@B1> app-log error");
+        let thread = [alert, upload, mention, message("B1", "judgement"), reply];
+        assert!(answered_mention(&thread, "B1"));
+        assert!(!answered_mention(&thread[..3], "B1"));
+    }
+
+    #[test]
+    fn test_oncall_mention() {
+        // Only critical alerts mention on-call.
+        assert_eq!(
+            oncall_mention(Severity::Critical, None, Some("S1")),
+            Some("S1")
+        );
+        for severity in [Severity::High, Severity::Medium, Severity::Low] {
+            assert_eq!(oncall_mention(severity, Some("S2"), Some("S1")), None);
+        }
+
+        // The route's user group wins over the default one.
+        assert_eq!(
+            oncall_mention(Severity::Critical, Some("S2"), Some("S1")),
+            Some("S2")
+        );
+        assert_eq!(
+            oncall_mention(Severity::Critical, Some("S2"), None),
+            Some("S2")
+        );
+        assert_eq!(oncall_mention(Severity::Critical, None, None), None);
+    }
+
+    #[test]
+    fn test_snooze_option() {
+        let decision_id = DecisionId::new().to_string();
+        for hours in [Some(24), None] {
+            let option = SnoozeOption {
+                hours,
+                decision_id: decision_id.clone(),
+            };
+            let value = option.to_string();
+
+            assert!(value.len() <= 75);
+            assert_eq!(SnoozeOption::try_from(value.as_str()).unwrap(), option);
+        }
+        assert!(SnoozeOption::try_from("forever").is_err());
+        assert!(SnoozeOption::try_from("4d:id").is_err());
+    }
+
+    #[test]
+    fn test_conflict_view_round_trip() {
+        let feedback = Feedback::builder()
+            .id(FeedbackId::new())
+            .created_at(Timestamp::new())
+            .log_group("/aws/lambda/test".into())
+            .message("ERROR something failed".into())
+            .needs_notification(true)
+            .reason(Some("reason".into()))
+            .user_id(Some("U123".into()))
+            .build();
+        let view = make_conflict_view(Locale::En, "metadata", &[feedback]);
+
+        assert_eq!(round_trip(&view), view);
+        assert_eq!(serde_json::to_value(&view).unwrap()["type"], "modal");
+    }
+
+    #[test]
+    fn test_stats_response() {
+        let now = Timestamp::new();
+        let decision = |created_at: Timestamp, needs_notification: bool, muted: bool| {
+            Decision::builder()
+                .id(DecisionId::new())
+                .created_at(created_at)
+                .log_group("/aws/lambda/test".into())
+                .message("ERROR".into())
+                .needs_notification(needs_notification)
+                .severity(Severity::Low)
+                .muted(muted)
+                .build()
+        };
+        let two_days_ago = now.add(chrono::Duration::days(-2));
+        let decisions = vec![
+            decision(now.clone(), true, false),
+            decision(now.clone(), true, true),
+            decision(now.clone(), false, false),
+            decision(two_days_ago.clone(), true, false),
+            decision(two_days_ago, false, false),
+        ];
+
+        let response = make_stats_response(
+            Locale::En,
+            "/aws/lambda/test",
+            &decisions,
+            &now.add(chrono::Duration::days(-1)),
+        );
+
+        assert_eq!(response.response_type, ResponseType::Ephemeral);
+        assert!(response.text.ends_with(
+            "Alerted: 1 / 2\nSuppressed: 1 / 2\nMuted: 1 / 1\nMTTA: - (0 alerts) ・ MTTR: - (0 alerts)"
+        ));
+    }
 }
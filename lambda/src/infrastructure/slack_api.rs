@@ -0,0 +1,496 @@
+//! Request layer shared by all Slack Web API methods.
+//!
+//! Responses are checked in one place and turned into typed errors. Requests are spread
+//! over rate buckets per workspace and method, and rate-limited failures are retried until
+//! the caller's deadline, as are transient failures of idempotent methods.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of attempts made before a retryable error is returned to the caller.
+pub(crate) const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry of a transient error, doubled on each further retry.
+pub(crate) const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Wait after a rate-limited response without a usable `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+pub(crate) enum Body {
+    Json(Value),
+    Form(Vec<(&'static str, String)>),
+}
+
+impl Body {
+    /// Returns the channel the request is made in.
+    fn channel(&self) -> Option<&str> {
+        match self {
+            Self::Json(json) => json.get("channel").and_then(|v| v.as_str()),
+            Self::Form(form) => form
+                .iter()
+                .find(|(k, _)| *k == "channel")
+                .map(|(_, v)| v.as_str()),
+        }
+    }
+}
+
+/// Fields of the Slack responses used by this application.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApiResponse {
+    pub(crate) ok: bool,
+    pub(crate) error: Option<String>,
+    pub(crate) response_metadata: Option<Value>,
+    pub(crate) ts: Option<String>,
+    pub(crate) upload_url: Option<String>,
+    pub(crate) file_id: Option<String>,
+    pub(crate) messages: Option<Vec<HistoryMessage>>,
+    pub(crate) url: Option<String>,
+    pub(crate) access_token: Option<String>,
+    pub(crate) bot_user_id: Option<String>,
+    pub(crate) team: Option<Team>,
+    pub(crate) authed_user: Option<AuthedUser>,
+}
+
+/// The workspace an OAuth access was granted in.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Team {
+    pub(crate) id: String,
+    pub(crate) name: Option<String>,
+}
+
+/// The user who granted an OAuth access.
+#[derive(Debug, Deserialize)]
+pub(crate) struct AuthedUser {
+    pub(crate) id: String,
+}
+
+/// A message returned by the conversation history methods.
+#[derive(Debug, Deserialize)]
+pub(crate) struct HistoryMessage {
+    pub(crate) user: Option<String>,
+    pub(crate) text: Option<String>,
+}
+
+/// The `error` field of a failed Slack response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ErrorCode {
+    RateLimited,
+    NotAuthed,
+    InvalidAuth,
+    TokenRevoked,
+    AccountInactive,
+    MissingScope,
+    ChannelNotFound,
+    NotInChannel,
+    IsArchived,
+    InvalidArguments,
+    InvalidBlocks,
+    MsgTooLong,
+    ExpiredTriggerId,
+    InvalidTriggerId,
+    InternalError,
+    FatalError,
+    ServiceUnavailable,
+    RequestTimeout,
+    Other(String),
+}
+
+impl From<&str> for ErrorCode {
+    fn from(value: &str) -> Self {
+        match value {
+            "ratelimited" => Self::RateLimited,
+            "not_authed" => Self::NotAuthed,
+            "invalid_auth" => Self::InvalidAuth,
+            "token_revoked" => Self::TokenRevoked,
+            "account_inactive" => Self::AccountInactive,
+            "missing_scope" => Self::MissingScope,
+            "channel_not_found" => Self::ChannelNotFound,
+            "not_in_channel" => Self::NotInChannel,
+            "is_archived" => Self::IsArchived,
+            "invalid_arguments" => Self::InvalidArguments,
+            "invalid_blocks" => Self::InvalidBlocks,
+            "msg_too_long" => Self::MsgTooLong,
+            "expired_trigger_id" => Self::ExpiredTriggerId,
+            "invalid_trigger_id" => Self::InvalidTriggerId,
+            "internal_error" => Self::InternalError,
+            "fatal_error" => Self::FatalError,
+            "service_unavailable" => Self::ServiceUnavailable,
+            "request_timeout" => Self::RequestTimeout,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::RateLimited => "ratelimited",
+            Self::NotAuthed => "not_authed",
+            Self::InvalidAuth => "invalid_auth",
+            Self::TokenRevoked => "token_revoked",
+            Self::AccountInactive => "account_inactive",
+            Self::MissingScope => "missing_scope",
+            Self::ChannelNotFound => "channel_not_found",
+            Self::NotInChannel => "not_in_channel",
+            Self::IsArchived => "is_archived",
+            Self::InvalidArguments => "invalid_arguments",
+            Self::InvalidBlocks => "invalid_blocks",
+            Self::MsgTooLong => "msg_too_long",
+            Self::ExpiredTriggerId => "expired_trigger_id",
+            Self::InvalidTriggerId => "invalid_trigger_id",
+            Self::InternalError => "internal_error",
+            Self::FatalError => "fatal_error",
+            Self::ServiceUnavailable => "service_unavailable",
+            Self::RequestTimeout => "request_timeout",
+            Self::Other(value) => value,
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl ErrorCode {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited
+                | Self::InternalError
+                | Self::FatalError
+                | Self::ServiceUnavailable
+                | Self::RequestTimeout
+        )
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum SlackError {
+    /// Slack answered with `ok: false`.
+    Api {
+        method: &'static str,
+        code: ErrorCode,
+        metadata: Option<Value>,
+        retry_after: Option<Duration>,
+    },
+    /// Slack answered with a non-success HTTP status.
+    Http {
+        method: &'static str,
+        status: u16,
+        body: String,
+        retry_after: Option<Duration>,
+    },
+    /// The request could not be sent or the response could not be read.
+    Transport {
+        method: &'static str,
+        source: reqwest::Error,
+    },
+    /// The request was given up because the next attempt would end after the deadline.
+    DeadlineExceeded {
+        method: &'static str,
+        last: Option<Box<SlackError>>,
+    },
+}
+
+impl fmt::Display for SlackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Api {
+                method,
+                code,
+                metadata,
+                ..
+            } => match metadata {
+                Some(metadata) => write!(f, "{} failed: {} ({})", method, code, metadata),
+                None => write!(f, "{} failed: {}", method, code),
+            },
+            Self::Http {
+                method,
+                status,
+                body,
+                ..
+            } => write!(f, "{} failed with HTTP {}: {}", method, status, body),
+            Self::Transport { method, source } => write!(f, "{} failed: {}", method, source),
+            Self::DeadlineExceeded { method, last } => match last {
+                Some(last) => write!(f, "{} gave up at the deadline: {}", method, last),
+                None => write!(f, "{} gave up at the deadline", method),
+            },
+        }
+    }
+}
+
+impl std::error::Error for SlackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport { source, .. } => Some(source),
+            Self::DeadlineExceeded {
+                last: Some(last), ..
+            } => Some(last.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl SlackError {
+    /// Returns whether the request can be made again. Timeouts and server errors may come
+    /// after Slack handled the request, so methods that are not idempotent are only called
+    /// again when the request was rate limited or could not be sent at all.
+    pub(crate) fn is_retryable(&self) -> bool {
+        if !is_idempotent(self.method()) {
+            return self.rate_limit_wait().is_some()
+                || matches!(self, Self::Transport { source, .. } if source.is_connect());
+        }
+        match self {
+            Self::Api { code, .. } => code.is_retryable(),
+            Self::Http { status, .. } => *status == 429 || *status >= 500,
+            Self::Transport { source, .. } => source.is_timeout() || source.is_connect(),
+            Self::DeadlineExceeded { .. } => false,
+        }
+    }
+
+    fn method(&self) -> &'static str {
+        match self {
+            Self::Api { method, .. }
+            | Self::Http { method, .. }
+            | Self::Transport { method, .. }
+            | Self::DeadlineExceeded { method, .. } => method,
+        }
+    }
+
+    /// Returns how long the method must not be called again if the request was rate limited.
+    pub(crate) fn rate_limit_wait(&self) -> Option<Duration> {
+        match self {
+            Self::Api {
+                code: ErrorCode::RateLimited,
+                retry_after,
+                ..
+            }
+            | Self::Http {
+                status: 429,
+                retry_after,
+                ..
+            } => Some(retry_after.unwrap_or(DEFAULT_RETRY_AFTER)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether calling the method twice has the same effect as calling it once. Others,
+/// such as posting a message or opening a modal, would be done twice.
+fn is_idempotent(method: &str) -> bool {
+    matches!(
+        method,
+        "chat.update"
+            | "views.update"
+            | "views.publish"
+            | "conversations.replies"
+            | "files.getUploadURLExternal"
+            | "apps.connections.open"
+    )
+}
+
+/// Returns the number of requests per minute Slack allows for the method.
+fn requests_per_minute(method: &str) -> u32 {
+    match method {
+        // Special tier of about one message per second per channel.
+        "chat.postMessage" => 60,
+        // Tier 4
+        "views.open" | "views.update" | "views.publish" => 100,
+        "files.getUploadURLExternal" | "files.completeUploadExternal" => 100,
+        // Tier 1
+        "apps.connections.open" => 1,
+        // Tier 3, which covers most other methods
+        _ => 50,
+    }
+}
+
+/// What a rate bucket is kept for. Slack limits each method per workspace, and messages
+/// posted with `chat.postMessage` per channel as well.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct RateKey {
+    team_id: Option<String>,
+    method: &'static str,
+    channel: Option<String>,
+}
+
+impl RateKey {
+    pub(crate) fn new(team_id: Option<&str>, method: &'static str, body: &Body) -> Self {
+        let channel = match method {
+            "chat.postMessage" => body.channel().map(|v| v.to_string()),
+            _ => None,
+        };
+        Self {
+            team_id: team_id.map(|v| v.to_string()),
+            method,
+            channel,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    refill_per_sec: f64,
+    updated_at: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(method: &str, now: Instant) -> Self {
+        let per_minute = requests_per_minute(method) as f64;
+        // Allow a burst of five seconds' worth of requests.
+        let capacity = (per_minute / 12.0).ceil();
+        Self {
+            tokens: capacity,
+            capacity,
+            refill_per_sec: per_minute / 60.0,
+            updated_at: now,
+            blocked_until: None,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated_at = now;
+    }
+
+    /// Returns whether the bucket would be full and unblocked, as if it were new.
+    fn is_idle(&self, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.blocked_until.is_none_or(|v| v <= now)
+            && self.tokens + elapsed * self.refill_per_sec >= self.capacity
+    }
+}
+
+/// Token buckets per [`RateKey`], shared by the clones of a client, including those acting
+/// in other workspaces.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<HashMap<RateKey, Bucket>>>,
+}
+
+impl RateLimiter {
+    /// Takes a token for the key and returns zero, or returns how long to wait before
+    /// trying again without taking one.
+    pub(crate) fn reserve(&self, key: &RateKey) -> Duration {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = Self::bucket(&mut buckets, key, now);
+
+        if let Some(blocked_until) = bucket.blocked_until {
+            if blocked_until > now {
+                return blocked_until - now;
+            }
+            bucket.blocked_until = None;
+            bucket.updated_at = now;
+        }
+
+        bucket.refill(now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.refill_per_sec)
+        }
+    }
+
+    /// Stops handing out tokens for the key for the given duration, as told by Slack.
+    pub(crate) fn block(&self, key: &RateKey, duration: Duration) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = Self::bucket(&mut buckets, key, now);
+        bucket.tokens = 0.0;
+        bucket.blocked_until = Some(now + duration);
+    }
+
+    /// Returns the bucket of the key. Idle buckets are dropped before a new one is added,
+    /// so that the buckets of every workspace and channel ever called are not all kept.
+    fn bucket<'a>(
+        buckets: &'a mut HashMap<RateKey, Bucket>,
+        key: &RateKey,
+        now: Instant,
+    ) -> &'a mut Bucket {
+        if !buckets.contains_key(key) {
+            buckets.retain(|_, v| !v.is_idle(now));
+        }
+        buckets
+            .entry(key.clone())
+            .or_insert_with(|| Bucket::new(key.method, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(ErrorCode::from("ratelimited"), ErrorCode::RateLimited);
+        assert_eq!(
+            ErrorCode::from("something_new"),
+            ErrorCode::Other("something_new".into())
+        );
+        assert_eq!(
+            ErrorCode::ExpiredTriggerId.to_string(),
+            "expired_trigger_id"
+        );
+        assert!(ErrorCode::RateLimited.is_retryable());
+        assert!(!ErrorCode::ChannelNotFound.is_retryable());
+    }
+
+    fn key(team_id: &str, method: &'static str, channel: &str) -> RateKey {
+        RateKey::new(
+            Some(team_id),
+            method,
+            &Body::Json(serde_json::json!({ "channel": channel })),
+        )
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::default();
+        let update = key("T1", "chat.update", "C1");
+
+        // chat.update allows a burst of 5 requests.
+        for _ in 0..5 {
+            assert_eq!(limiter.reserve(&update), Duration::ZERO);
+        }
+        assert!(limiter.reserve(&update) > Duration::ZERO);
+        // Buckets are independent per method and per workspace, but not per channel for
+        // most methods.
+        assert_eq!(
+            limiter.reserve(&key("T1", "views.open", "C1")),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve(&key("T2", "chat.update", "C1")),
+            Duration::ZERO
+        );
+        assert!(limiter.reserve(&key("T1", "chat.update", "C2")) > Duration::ZERO);
+
+        limiter.block(&key("T1", "views.open", "C1"), Duration::from_secs(30));
+        assert!(limiter.reserve(&key("T1", "views.open", "C1")) > Duration::from_secs(29));
+        assert_eq!(
+            limiter.reserve(&key("T2", "views.open", "C1")),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_post_message() {
+        let limiter = RateLimiter::default();
+        let post = key("T1", "chat.postMessage", "C1");
+
+        // chat.postMessage allows a burst of 5 messages per channel.
+        for _ in 0..5 {
+            assert_eq!(limiter.reserve(&post), Duration::ZERO);
+        }
+        assert!(limiter.reserve(&post) > Duration::ZERO);
+        assert_eq!(
+            limiter.reserve(&key("T1", "chat.postMessage", "C2")),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Follow-ups of Slack interactions, run after the interaction is acknowledged.
+//!
+//! Slack shows the user an error when an interaction is not answered within 3 seconds,
+//! even if it is handled later. The collector therefore only writes the changes before
+//! answering, and leaves redrawing the messages and resolving the alerts on the sinks to
+//! tasks. Judging the logs pasted in threads is left to tasks as well, as Slack sends an
+//! event again when it is not answered within 3 seconds either.
+//!
+//! A task is sent to the task route of the collector, which is kept apart from the routes
+//! of the Slack requests: in Lambda by invoking the function itself asynchronously, with
+//! the request signed by a secret of the tasks, and elsewhere, such as in Socket Mode,
+//! through a worker running the route in the process without serving it.
+
+use crate::domain::value_objects::{DecisionId, RuleId};
+use crate::util::{now_timestamp, slack_signature};
+use aws_lambda_events::apigw::ApiGatewayV2httpRequest;
+use aws_sdk_lambda::primitives::Blob;
+use aws_sdk_lambda::types::InvocationType;
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, HeaderValue, Method, Request};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+/// Path of the task route, outside the routes of the Slack requests.
+pub(crate) const TASK_PATH: &str = "/tasks";
+
+/// Headers of the signature of a task, made like the one of a Slack request.
+pub(crate) const TASK_SIGNATURE_HEADER: &str = "X-Task-Signature";
+pub(crate) const TASK_TIMESTAMP_HEADER: &str = "X-Task-Timestamp";
+
+/// Longest a task may wait before it runs in seconds, which is how long Lambda keeps an
+/// asynchronous invocation, retries included.
+pub(crate) const MAX_TASK_AGE: i64 = 6 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct Task {
+    /// Identifies the task across its deliveries, which Lambda may repeat.
+    #[builder(default = Uuid::new_v4())]
+    id: Uuid,
+    /// Workspace the interaction happened in.
+    team_id: Option<String>,
+    /// User who interacted, told if the task fails.
+    user_id: String,
+    /// Where Slack takes replies to the interaction, given for actions on messages.
+    #[builder(default)]
+    response_url: Option<String>,
+    action: TaskAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum TaskAction {
+    /// Redraws an alert from its decision.
+    UpdateAlert {
+        channel_id: String,
+        ts: String,
+        decision_id: DecisionId,
+    },
+    /// Replaces the buttons of a rule review with the outcome.
+    CloseRuleReview {
+        channel_id: String,
+        ts: String,
+        rule_id: RuleId,
+    },
+    /// Refreshes the Home tab of the user.
+    PublishHome,
+    /// Resolves the alert of a decision on the sinks tracking it.
+    ResolveAlert { decision_id: DecisionId },
+    /// Judges a log pasted in a thread and replies with the judgement, or with how to ask
+    /// for one when there is no log group and message. Messages not mentioning the bot are
+    /// only answered in the threads it takes part in, which `bot_user_id` is given for.
+    ReplyWithJudgement {
+        channel_id: String,
+        thread_ts: String,
+        query: Option<(String, String)>,
+        bot_user_id: Option<String>,
+    },
+}
+
+impl Task {
+    pub(crate) fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    pub(crate) fn team_id(&self) -> Option<&str> {
+        self.team_id.as_deref()
+    }
+
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub(crate) fn response_url(&self) -> Option<&str> {
+        self.response_url.as_deref()
+    }
+
+    pub(crate) fn action(&self) -> &TaskAction {
+        &self.action
+    }
+
+    /// Returns the channel of the message the task changes, if any.
+    pub(crate) fn channel_id(&self) -> Option<&str> {
+        match &self.action {
+            TaskAction::UpdateAlert { channel_id, .. }
+            | TaskAction::CloseRuleReview { channel_id, .. }
+            | TaskAction::ReplyWithJudgement { channel_id, .. } => Some(channel_id),
+            TaskAction::PublishHome | TaskAction::ResolveAlert { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskQueue {
+    transport: Transport,
+    /// Secret the requests are signed with, unset when they do not leave the process.
+    task_secret: Option<String>,
+}
+
+#[derive(Clone)]
+enum Transport {
+    Lambda(LambdaInvoker),
+    InProcess(mpsc::UnboundedSender<Request<Body>>),
+}
+
+impl TaskQueue {
+    /// Returns a queue running the tasks by invoking the Lambda function asynchronously.
+    pub fn lambda(invoker: LambdaInvoker, task_secret: String) -> Self {
+        Self {
+            transport: Transport::Lambda(invoker),
+            task_secret: Some(task_secret),
+        }
+    }
+
+    /// Returns a queue handing the tasks to the returned worker in the process.
+    pub fn in_process() -> (Self, TaskWorker) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let queue = Self {
+            transport: Transport::InProcess(sender),
+            task_secret: None,
+        };
+        (queue, TaskWorker { receiver })
+    }
+
+    /// Sends the task to be run after the current request is answered.
+    pub(crate) async fn enqueue(&self, task: &Task) -> Result<(), Box<dyn std::error::Error>> {
+        let body = serde_json::to_string(task)?;
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if let Some(task_secret) = &self.task_secret {
+            let timestamp = now_timestamp().to_string();
+            let signature = slack_signature(task_secret, &timestamp, body.as_bytes());
+            headers.insert(TASK_TIMESTAMP_HEADER, timestamp.parse()?);
+            headers.insert(TASK_SIGNATURE_HEADER, signature.parse()?);
+        }
+
+        match &self.transport {
+            Transport::Lambda(invoker) => invoker.invoke(headers, body).await,
+            Transport::InProcess(sender) => {
+                let mut request = Request::post(TASK_PATH).body(Body::from(body))?;
+                *request.headers_mut() = headers;
+                sender
+                    .send(request)
+                    .map_err(|_| "Task worker has stopped")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Runs the tasks of an in-process queue on the router serving the task route.
+pub struct TaskWorker {
+    receiver: mpsc::UnboundedReceiver<Request<Body>>,
+}
+
+impl TaskWorker {
+    /// Runs the tasks for as long as the queue is kept, each concurrently with the others.
+    /// The router is not served, so the route is not checked.
+    pub async fn run(mut self, router: Router) {
+        while let Some(request) = self.receiver.recv().await {
+            let router = router.clone();
+            tokio::spawn(async move {
+                match router.oneshot(request).await {
+                    Ok(resp) if resp.status().is_success() => {}
+                    Ok(resp) => tracing::error!("Task failed with status {}", resp.status()),
+                    Err(e) => tracing::error!("Failed to run task: {:?}", e),
+                }
+            });
+        }
+    }
+}
+
+/// Invokes a Lambda function asynchronously with a request of a Lambda function URL.
+#[derive(Clone, TypedBuilder)]
+pub struct LambdaInvoker {
+    client: aws_sdk_lambda::Client,
+    function_name: String,
+}
+
+impl LambdaInvoker {
+    async fn invoke(
+        &self,
+        headers: HeaderMap,
+        body: String,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut event = ApiGatewayV2httpRequest::default();
+        event.version = Some("2.0".to_string());
+        event.raw_path = Some(TASK_PATH.to_string());
+        event.headers = headers;
+        event.body = Some(body);
+        event.request_context.http.method = Method::POST;
+        event.request_context.http.path = Some(TASK_PATH.to_string());
+
+        self.client
+            .invoke()
+            .function_name(&self.function_name)
+            .invocation_type(InvocationType::Event)
+            .payload(Blob::new(serde_json::to_vec(&event)?))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_server;
+    use aws_sdk_lambda::config::{BehaviorVersion, Credentials, Region};
+    use axum::extract::Path;
+    use axum::http::StatusCode;
+    use lambda_http::request::LambdaRequest;
+
+    fn task() -> Task {
+        Task::builder()
+            .team_id(Some("T0123".into()))
+            .user_id("U0123".into())
+            .action(TaskAction::UpdateAlert {
+                channel_id: "C0123".into(),
+                ts: "1735689600.000100".into(),
+                decision_id: DecisionId::new(),
+            })
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_lambda_invoke() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/2015-03-31/functions/{name}/invocations",
+            axum::routing::post(
+                move |Path(name): Path<String>, headers: HeaderMap, body: String| async move {
+                    sender.send((name, headers, body)).unwrap();
+                    StatusCode::ACCEPTED
+                },
+            ),
+        );
+        let base_url = test_server(app).await;
+
+        let client = aws_sdk_lambda::Client::from_conf(
+            aws_sdk_lambda::Config::builder()
+                .behavior_version(BehaviorVersion::latest())
+                .region(Region::new("ap-northeast-1"))
+                .credentials_provider(Credentials::new("AKID", "secret", None, None, "test"))
+                .endpoint_url(base_url)
+                .build(),
+        );
+        let invoker = LambdaInvoker::builder()
+            .client(client)
+            .function_name("collector".into())
+            .build();
+        let queue = TaskQueue::lambda(invoker, "task-secret".into());
+        let task = task();
+        queue.enqueue(&task).await.unwrap();
+
+        let (name, headers, body) = receiver.recv().await.unwrap();
+        assert_eq!(name, "collector");
+        assert_eq!(headers["x-amz-invocation-type"], "Event");
+        assert!(headers["authorization"]
+            .to_str()
+            .unwrap()
+            .starts_with("AWS4-HMAC-SHA256 Credential=AKID/"));
+
+        // The payload is taken by the runtime as a request to the task route.
+        let event: LambdaRequest = serde_json::from_str(&body).unwrap();
+        let request: Request<lambda_http::Body> = event.into();
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.uri().path(), TASK_PATH);
+        let timestamp = request.headers()[TASK_TIMESTAMP_HEADER].to_str().unwrap();
+        assert_eq!(
+            request.headers()[TASK_SIGNATURE_HEADER],
+            slack_signature("task-secret", timestamp, request.body().as_ref()).as_str()
+        );
+        let sent: Task = serde_json::from_slice(request.body().as_ref()).unwrap();
+        assert_eq!(sent, task);
+    }
+
+    #[tokio::test]
+    async fn test_in_process_queue() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let router = axum::Router::new().route(
+            TASK_PATH,
+            axum::routing::post(move |axum::Json(task): axum::Json<Task>| async move {
+                sender.send(task).unwrap();
+                StatusCode::OK
+            }),
+        );
+        let (queue, worker) = TaskQueue::in_process();
+        tokio::spawn(worker.run(router));
+
+        let task = task();
+        queue.enqueue(&task).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), task);
+    }
+
+    #[test]
+    fn test_reply_task_round_trip() {
+        let task = Task::builder()
+            .team_id(None)
+            .user_id("U0123".into())
+            .action(TaskAction::ReplyWithJudgement {
+                channel_id: "C0123".into(),
+                thread_ts: "1735689600.000100".into(),
+                query: Some(("/aws/lambda/test".into(), "ERROR: timeout".into())),
+                bot_user_id: Some("U0BOT".into()),
+            })
+            .build();
+
+        let sent: Task = serde_json::from_str(&serde_json::to_string(&task).unwrap()).unwrap();
+        assert_eq!(sent, task);
+        // Failures are told in the channel of the thread.
+        assert_eq!(sent.channel_id(), Some("C0123"));
+    }
+}
@@ -0,0 +1,100 @@
+//! Encryption of the bot tokens stored for the workspaces the app is installed to.
+//!
+//! Tokens are sealed with AES-256-GCM under a key kept in Secrets Manager, with the team
+//! id as associated data so that a sealed token cannot be moved to another workspace.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+
+/// Length of the nonce prepended to the ciphertext.
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Clone)]
+pub struct TokenCipher {
+    cipher: Aes256Gcm,
+}
+
+impl TokenCipher {
+    /// Takes a 256-bit key encoded in base64.
+    pub fn new(key: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let key = BASE64_STANDARD.decode(key.trim())?;
+        if key.len() != 32 {
+            return Err("Token encryption key must be 32 bytes".into());
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+
+    /// Returns the token sealed for the team, as the nonce and the ciphertext in base64.
+    pub(crate) fn encrypt(
+        &self,
+        team_id: &str,
+        token: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: token.as_bytes(),
+                    aad: team_id.as_bytes(),
+                },
+            )
+            .map_err(|_| "Failed to encrypt token")?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(BASE64_STANDARD.encode(sealed))
+    }
+
+    /// Opens a token sealed for the team, failing if it was sealed with another key or for
+    /// another team, or was altered.
+    pub(crate) fn decrypt(
+        &self,
+        team_id: &str,
+        sealed: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let sealed = BASE64_STANDARD.decode(sealed)?;
+        if sealed.len() < NONCE_LENGTH {
+            return Err("Sealed token is too short".into());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let token = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: team_id.as_bytes(),
+                },
+            )
+            .map_err(|_| "Failed to decrypt token")?;
+
+        Ok(String::from_utf8(token)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_cipher() {
+        let cipher = TokenCipher::new(&BASE64_STANDARD.encode([7u8; 32])).unwrap();
+        let sealed = cipher.encrypt("T0123", "xoxb-token").unwrap();
+
+        assert!(!sealed.contains("xoxb"));
+        assert_ne!(sealed, cipher.encrypt("T0123", "xoxb-token").unwrap());
+        assert_eq!(cipher.decrypt("T0123", &sealed).unwrap(), "xoxb-token");
+        assert!(cipher.decrypt("T9999", &sealed).is_err());
+
+        let other = TokenCipher::new(&BASE64_STANDARD.encode([8u8; 32])).unwrap();
+        assert!(other.decrypt("T0123", &sealed).is_err());
+
+        assert!(TokenCipher::new(&BASE64_STANDARD.encode([7u8; 16])).is_err());
+    }
+}
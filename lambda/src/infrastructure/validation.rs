@@ -0,0 +1,116 @@
+//! Checks of the values entered in modals, shown to the user under the fields they concern
+//! instead of failing the submission.
+
+use crate::infrastructure::block_kit::BlockId;
+use crate::infrastructure::i18n::Catalog;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// When a reason has to be given with feedback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReasonRequirement {
+    #[default]
+    Never,
+    /// Only with feedback saying that logs like the alerted one need no notification.
+    WhenSuppressing,
+    Always,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationRules {
+    #[serde(default)]
+    reason_required: ReasonRequirement,
+    /// Fewest characters a reason may have when one is given.
+    #[serde(default)]
+    min_reason_length: usize,
+}
+
+/// Why the value of a field was not accepted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum InputError {
+    Missing,
+    Invalid,
+    ReasonRequired,
+    ReasonTooShort(usize),
+    InPast,
+}
+
+/// Errors of a submission by the block of the field.
+pub(crate) type InputErrors = HashMap<BlockId, InputError>;
+
+impl ValidationRules {
+    /// Parses a JSON object such as `{"reason_required": "when_suppressing",
+    /// "min_reason_length": 10}`. Nothing beyond the form itself is checked when unset.
+    pub fn new(json: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        match json {
+            Some(json) => Ok(serde_json::from_str(json)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Checks the reason given with feedback. A reason of only whitespace counts as none.
+    pub(crate) fn check_feedback(
+        &self,
+        needs_notification: bool,
+        reason: Option<&str>,
+    ) -> InputErrors {
+        let required = match self.reason_required {
+            ReasonRequirement::Never => false,
+            ReasonRequirement::WhenSuppressing => !needs_notification,
+            ReasonRequirement::Always => true,
+        };
+        let error = match reason.map(str::trim).filter(|v| !v.is_empty()) {
+            None if required => Some(InputError::ReasonRequired),
+            Some(reason) if reason.chars().count() < self.min_reason_length => {
+                Some(InputError::ReasonTooShort(self.min_reason_length))
+            }
+            _ => None,
+        };
+        error.map(|v| (BlockId::Reason, v)).into_iter().collect()
+    }
+}
+
+impl InputError {
+    pub(crate) fn message(&self, catalog: &Catalog) -> String {
+        match self {
+            Self::Missing => catalog.input_missing.to_string(),
+            Self::Invalid => catalog.input_invalid.to_string(),
+            Self::ReasonRequired => catalog.reason_required.to_string(),
+            Self::ReasonTooShort(length) => (catalog.reason_too_short)(*length),
+            Self::InPast => catalog.time_in_past.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_feedback() {
+        let rules = ValidationRules::new(Some(
+            r#"{"reason_required": "when_suppressing", "min_reason_length": 5}"#,
+        ))
+        .unwrap();
+
+        assert!(rules.check_feedback(true, None).is_empty());
+        assert_eq!(
+            rules.check_feedback(false, Some("  ")),
+            InputErrors::from([(BlockId::Reason, InputError::ReasonRequired)])
+        );
+        assert_eq!(
+            rules.check_feedback(true, Some("flaky")),
+            InputErrors::new()
+        );
+        assert_eq!(
+            rules.check_feedback(true, Some(" ok ")),
+            InputErrors::from([(BlockId::Reason, InputError::ReasonTooShort(5))])
+        );
+
+        let rules = ValidationRules::new(None).unwrap();
+        assert!(rules.check_feedback(false, None).is_empty());
+        assert!(ValidationRules::new(Some(r#"{"min_length": 5}"#)).is_err());
+    }
+}
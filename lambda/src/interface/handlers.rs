@@ -1,20 +1,34 @@
-use crate::application::services::CollectionService;
-use crate::interface::payloads::{BlockActions, InteractivityPayload};
-use axum::extract::State;
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::Form;
+use crate::application::services::{
+    CollectionService, CommandService, EventService, FeedbackPageService, HtmlPage,
+    InstallationService, ReportService, SubmissionResult, STATS_DAYS,
+};
+use crate::domain::value_objects::Timestamp;
+use crate::infrastructure::block_kit::{ActionId, CallbackId, ResponseAction};
+use crate::infrastructure::feedback_link::FeedbackLinkQuery;
+use crate::infrastructure::oauth::STATE_COOKIE;
+use crate::infrastructure::slack::{PrivateMetadata, SnoozeOption};
+use crate::infrastructure::tasks::Task;
+use crate::interface::payloads::{
+    BlockActions, Command, Event, EventPayload, FeedbackPageForm, InteractivityPayload, JudgeQuery,
+    OAuthRedirectQuery, ReportQuery, SlashCommand, ViewSubmission,
+};
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::header::{COOKIE, SET_COOKIE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::{Form, Json};
 use std::collections::HashMap;
 
 pub(crate) async fn add_feedback_handler(
     State(state): State<CollectionService>,
     req: Form<HashMap<String, String>>,
-) -> impl IntoResponse {
+) -> Response {
     let payload = match req.get("payload") {
         Some(payload) => payload,
         None => {
             tracing::warn!("Payload not found");
-            return StatusCode::BAD_REQUEST;
+            return StatusCode::BAD_REQUEST.into_response();
         }
     };
 
@@ -22,65 +36,745 @@ pub(crate) async fn add_feedback_handler(
         Ok(payload) => payload,
         Err(e) => {
             tracing::warn!("Failed to parse payload: {:?}", e);
-            return StatusCode::BAD_REQUEST;
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+    let state = match state.for_team(payload.team_id()).await {
+        Ok(state) => state.with_response_url(payload.response_url()),
+        Err(e) => {
+            tracing::error!("Failed to resolve the workspace: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
     };
 
     match payload {
-        InteractivityPayload::BlockActions(payload) => match payload {
-            BlockActions::OpenModal(payload) => {
-                tracing::info!("Called open modal");
-
-                let ts = payload.get_ts().to_string();
-                let Some(log_group) = payload.get_log_group() else {
-                    tracing::warn!("Log group not found");
-                    return StatusCode::BAD_REQUEST;
-                };
-                let Some(message) = payload.get_message() else {
-                    tracing::warn!("Message not found");
-                    return StatusCode::BAD_REQUEST;
-                };
-
-                match state
-                    .open_modal(
-                        payload.trigger_id(),
-                        ts,
-                        log_group.to_string(),
-                        message.to_string(),
-                    )
-                    .await
-                {
-                    Ok(_) => StatusCode::OK,
-                    Err(e) => {
-                        tracing::error!("Failed to open modal: {:?}", e);
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    }
-                }
-            }
-            BlockActions::Other(_) => StatusCode::OK,
+        InteractivityPayload::BlockActions(payload) => match payload.action_id() {
+            Some(ActionId::OpenModal) => open_modal(&state, &payload).await,
+            Some(ActionId::ApproveRule) => review_rule(&state, &payload, true).await,
+            Some(ActionId::RejectRule) => review_rule(&state, &payload, false).await,
+            Some(ActionId::Acknowledge) => acknowledge(&state, &payload, false).await,
+            Some(ActionId::Resolve) => acknowledge(&state, &payload, true).await,
+            Some(ActionId::Snooze) => snooze(&state, &payload).await,
+            Some(ActionId::ReleaseSnooze) => release_snooze(&state, &payload).await,
+            Some(ActionId::EditFeedback) => open_feedback_editor(&state, &payload).await,
+            Some(ActionId::DeleteFeedback) => delete_feedback(&state, &payload).await,
+            _ => StatusCode::OK.into_response(),
         },
         InteractivityPayload::ViewSubmission(payload) => {
             tracing::info!("Called view submission");
 
-            let private_metadata = payload.get_private_metadata();
-            let (needs_notification, reason) = match payload.get_modal_values() {
-                Ok(values) => values,
-                Err(e) => {
-                    tracing::warn!("Failed to get modal values: {:?}", e);
-                    return StatusCode::BAD_REQUEST;
+            match payload.get_callback_id() {
+                CallbackId::SendFeedback => send_feedback(&state, &payload).await,
+                CallbackId::ResolveConflict => resolve_conflict(&state, &payload).await,
+                CallbackId::Snooze => snooze_until(&state, &payload).await,
+                CallbackId::EditFeedback => edit_feedback(&state, &payload).await,
+                CallbackId::Unknown => {
+                    tracing::warn!("Unknown callback id");
+                    StatusCode::BAD_REQUEST.into_response()
                 }
-            };
+            }
+        }
+    }
+}
+
+async fn open_modal(state: &CollectionService, payload: &BlockActions) -> Response {
+    tracing::info!("Called open modal");
+
+    let (Some(channel_id), Some(ts)) = (payload.get_channel_id(), payload.get_ts()) else {
+        tracing::warn!("Container not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let Some(decision_id) = payload.action_value() else {
+        tracing::warn!("Decision id not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let private_metadata = PrivateMetadata::builder()
+        .channel_id(channel_id.to_string())
+        .ts(ts.to_string())
+        .decision_id(decision_id.to_string())
+        .build();
+
+    match state
+        .open_modal(payload.trigger_id(), private_metadata)
+        .await
+    {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to open modal: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn review_rule(
+    state: &CollectionService,
+    payload: &BlockActions,
+    approved: bool,
+) -> Response {
+    tracing::info!("Called review rule");
+
+    let (Some(rule_id), Some(channel_id), Some(ts)) = (
+        payload.action_value(),
+        payload.get_channel_id(),
+        payload.get_ts(),
+    ) else {
+        tracing::warn!("Action not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match state
+        .review_rule(rule_id, approved, payload.user_id(), channel_id, ts)
+        .await
+    {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to review rule: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
 
-            match state
-                .add_feedback(private_metadata, needs_notification, reason)
+async fn acknowledge(
+    state: &CollectionService,
+    payload: &BlockActions,
+    resolved: bool,
+) -> Response {
+    tracing::info!("Called acknowledge");
+
+    let (Some(decision_id), Some(channel_id), Some(ts)) = (
+        payload.action_value(),
+        payload.get_channel_id(),
+        payload.get_ts(),
+    ) else {
+        tracing::warn!("Action not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match state
+        .acknowledge(channel_id, ts, decision_id, payload.user_id(), resolved)
+        .await
+    {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to acknowledge alert: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn snooze(state: &CollectionService, payload: &BlockActions) -> Response {
+    tracing::info!("Called snooze");
+
+    let (Some(channel_id), Some(ts)) = (payload.get_channel_id(), payload.get_ts()) else {
+        tracing::warn!("Container not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let option = match payload.selected_value().map(SnoozeOption::try_from) {
+        Some(Ok(option)) => option,
+        _ => {
+            tracing::warn!("Snooze option not found");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    let result = match option.hours {
+        Some(hours) => {
+            state
+                .snooze(
+                    channel_id,
+                    ts,
+                    &option.decision_id,
+                    Timestamp::new().add(chrono::Duration::hours(hours)),
+                    false,
+                    payload.user_id(),
+                )
                 .await
-            {
-                Ok(_) => StatusCode::OK,
-                Err(e) => {
-                    tracing::error!("Failed to add feedback: {:?}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                }
+        }
+        None => {
+            let private_metadata = PrivateMetadata::builder()
+                .channel_id(channel_id.to_string())
+                .ts(ts.to_string())
+                .decision_id(option.decision_id)
+                .build();
+            state
+                .open_snooze_modal(payload.trigger_id(), private_metadata)
+                .await
+        }
+    };
+
+    match result {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to snooze: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn release_snooze(state: &CollectionService, payload: &BlockActions) -> Response {
+    tracing::info!("Called release snooze");
+
+    let (Some(mute_id), Some(channel_id), Some(ts)) = (
+        payload.action_value(),
+        payload.get_channel_id(),
+        payload.get_ts(),
+    ) else {
+        tracing::warn!("Action not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match state
+        .release_snooze(channel_id, ts, mute_id, payload.user_id())
+        .await
+    {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to release snooze: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn open_feedback_editor(state: &CollectionService, payload: &BlockActions) -> Response {
+    tracing::info!("Called open feedback editor");
+
+    let Some(feedback_id) = payload.action_value() else {
+        tracing::warn!("Feedback id not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match state
+        .open_feedback_editor(payload.trigger_id(), feedback_id, payload.user_id())
+        .await
+    {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to open feedback editor: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn delete_feedback(state: &CollectionService, payload: &BlockActions) -> Response {
+    tracing::info!("Called delete feedback");
+
+    let Some(feedback_id) = payload.action_value() else {
+        tracing::warn!("Feedback id not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match state.delete_feedback(feedback_id, payload.user_id()).await {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to delete feedback: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn send_feedback(state: &CollectionService, payload: &ViewSubmission) -> Response {
+    let values = match payload.get_modal_values() {
+        Ok(values) => values,
+        Err(errors) => {
+            tracing::info!("Invalid modal values: {:?}", errors);
+            let result = state.reject_input(payload.get_private_metadata(), errors);
+            return make_submission_response(result);
+        }
+    };
+
+    match state
+        .add_feedback(
+            payload.view_id(),
+            payload.get_private_metadata(),
+            values.needs_notification,
+            values.severity,
+            values.reason,
+            payload.user_id(),
+        )
+        .await
+    {
+        Ok(result) => make_submission_response(result),
+        Err(e) => {
+            tracing::error!("Failed to add feedback: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn resolve_conflict(state: &CollectionService, payload: &ViewSubmission) -> Response {
+    let resolution = match payload.get_conflict_resolution() {
+        Ok(resolution) => resolution,
+        Err(e) => {
+            tracing::warn!("Failed to get conflict resolution: {:?}", e);
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    match state
+        .resolve_conflict(
+            payload.view_id(),
+            payload.get_private_metadata(),
+            resolution,
+        )
+        .await
+    {
+        Ok(result) => make_submission_response(result),
+        Err(e) => {
+            tracing::error!("Failed to resolve conflict: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn snooze_until(state: &CollectionService, payload: &ViewSubmission) -> Response {
+    let values = match payload.get_snooze_values() {
+        Ok(values) => values,
+        Err(errors) => {
+            tracing::info!("Invalid snooze values: {:?}", errors);
+            let result = state.reject_input(payload.get_private_metadata(), errors);
+            return make_submission_response(result);
+        }
+    };
+
+    match state
+        .snooze_until(
+            payload.get_private_metadata(),
+            Timestamp::from_secs(values.until),
+            values.whole_log_group,
+            payload.user_id(),
+        )
+        .await
+    {
+        Ok(result) => make_submission_response(result),
+        Err(e) => {
+            tracing::error!("Failed to snooze: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn edit_feedback(state: &CollectionService, payload: &ViewSubmission) -> Response {
+    let values = match payload.get_modal_values() {
+        Ok(values) => values,
+        Err(errors) => {
+            tracing::info!("Invalid modal values: {:?}", errors);
+            let result = state.reject_input(payload.get_private_metadata(), errors);
+            return make_submission_response(result);
+        }
+    };
+
+    match state
+        .edit_feedback(
+            payload.get_private_metadata(),
+            values.needs_notification,
+            values.severity,
+            values.reason,
+            payload.user_id(),
+        )
+        .await
+    {
+        Ok(result) => make_submission_response(result),
+        Err(e) => {
+            tracing::error!("Failed to edit feedback: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn make_submission_response(result: SubmissionResult) -> Response {
+    match result {
+        SubmissionResult::Completed => StatusCode::OK.into_response(),
+        SubmissionResult::UpdateView(view) => Json(ResponseAction::Update { view }).into_response(),
+        SubmissionResult::Clear => Json(ResponseAction::Clear).into_response(),
+        SubmissionResult::Errors(errors) => Json(ResponseAction::Errors { errors }).into_response(),
+    }
+}
+
+/// Runs a follow-up deferred by an interaction. The request is signed by the collector
+/// itself with the secret of the tasks.
+pub(crate) async fn run_task_handler(
+    State(state): State<CollectionService>,
+    Json(task): Json<Task>,
+) -> StatusCode {
+    tracing::info!("Called task");
+
+    match state.run_task(&task).await {
+        Ok(_) => StatusCode::OK,
+        Err(e) => {
+            tracing::error!("Failed to run task: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+pub(crate) async fn command_handler(
+    State(state): State<CommandService>,
+    Form(payload): Form<SlashCommand>,
+) -> Response {
+    tracing::info!("Called slash command");
+
+    let channel_id = payload.channel_id();
+    let state = match state.for_team(payload.team_id()).await {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::error!("Failed to resolve the workspace: {:?}", e);
+            return Json(state.failed(channel_id)).into_response();
+        }
+    };
+    let result = match payload.get_command() {
+        Some(Command::Status) => state.status(channel_id).await,
+        Some(Command::Stats { log_group }) => state.stats(channel_id, &log_group).await,
+        Some(Command::FeedbackList { log_group }) => {
+            state.list_feedback(channel_id, &log_group).await
+        }
+        Some(Command::Test { log_group, message }) => {
+            state.test(channel_id, &log_group, &message).await
+        }
+        Some(Command::Mute {
+            log_group,
+            duration,
+        }) => {
+            state
+                .mute(channel_id, payload.user_id(), &log_group, duration)
+                .await
+        }
+        None => Ok(state.usage(channel_id)),
+    };
+
+    // Slack shows nothing useful for an error status, so failures are answered in the channel.
+    match result {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to run slash command: {:?}", e);
+            Json(state.failed(channel_id)).into_response()
+        }
+    }
+}
+
+pub(crate) async fn events_handler(
+    State(state): State<EventService>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let payload: EventPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Failed to parse event: {:?}", e);
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    let callback = match payload {
+        EventPayload::UrlVerification { challenge } => {
+            return Json(serde_json::json!({ "challenge": challenge })).into_response();
+        }
+        EventPayload::EventCallback(callback) => callback,
+        EventPayload::Other => return StatusCode::OK.into_response(),
+    };
+
+    let Some(bot_user_id) = callback.bot_user_id() else {
+        tracing::warn!("Bot user not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    // Most messages of the channels the bot is in are of no interest, and are answered
+    // without being recorded.
+    if !is_handled(&callback.event, bot_user_id) {
+        return StatusCode::OK.into_response();
+    }
+    let state = match state.for_team(callback.team_id()).await {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::error!("Failed to resolve the workspace: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // Slack retries events that were answered with an error or took longer than 3 seconds.
+    // A retry is ignored only if an earlier delivery was taken, and an event that failed is
+    // given up so that its retry is handled.
+    let event_id = callback.event_id();
+    if let Some(event_id) = event_id {
+        match state.claim_event(event_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::info!(
+                    "Ignored event {} delivered again (retry {:?})",
+                    event_id,
+                    headers.get("X-Slack-Retry-Num")
+                );
+                return StatusCode::OK.into_response();
+            }
+            Err(e) => {
+                tracing::error!("Failed to claim event: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
             }
         }
     }
+
+    // The error is not `Send`, so it is dropped before the event is released.
+    let failed = match handle_event(&state, &callback.event, bot_user_id).await {
+        Ok(()) => false,
+        Err(e) => {
+            tracing::error!("Failed to handle event: {:?}", e);
+            true
+        }
+    };
+    if !failed {
+        return StatusCode::OK.into_response();
+    }
+    if let Some(event_id) = event_id {
+        if let Err(e) = state.release_event(event_id).await {
+            tracing::error!("Failed to release event: {:?}", e);
+        }
+    }
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+/// Returns whether the event is answered by [`handle_event`]: mentions of the bot, logs
+/// pasted in threads without mentioning it, and openings of the Home tab.
+fn is_handled(event: &Event, bot_user_id: &str) -> bool {
+    match event {
+        Event::AppMention(event) => event.is_user_message(),
+        Event::Message(event) => {
+            event.is_user_message()
+                && !event.mentions(bot_user_id)
+                && event.thread_ts().is_some()
+                && event.get_query().is_some()
+        }
+        Event::AppHomeOpened(event) => event.is_home(),
+        Event::Other => false,
+    }
+}
+
+/// Answers the event, replying to logs pasted in mentions and in threads the bot answered a
+/// mention in, and publishing the Home tab.
+async fn handle_event(
+    state: &EventService,
+    event: &Event,
+    bot_user_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match event {
+        Event::AppMention(event) if event.is_user_message() => {
+            tracing::info!("Called app mention");
+
+            let query = event.get_query();
+            state
+                .reply_with_judgement(
+                    event.user_id(),
+                    event.channel_id(),
+                    event.reply_ts(),
+                    as_pair(&query),
+                    None,
+                )
+                .await
+        }
+        // Mentions in threads are answered as app mentions.
+        Event::Message(event) if event.is_user_message() && !event.mentions(bot_user_id) => {
+            // Replies without a pasted log are chat, and are not sent to be judged.
+            let (Some(thread_ts), Some(query)) = (event.thread_ts(), event.get_query()) else {
+                return Ok(());
+            };
+
+            state
+                .reply_with_judgement(
+                    event.user_id(),
+                    event.channel_id(),
+                    thread_ts,
+                    Some((&query.log_group, &query.message)),
+                    Some(bot_user_id),
+                )
+                .await
+        }
+        Event::AppHomeOpened(event) if event.is_home() => {
+            tracing::info!("Called app home opened");
+
+            state.publish_home(event.user_id()).await
+        }
+        _ => Ok(()),
+    }
+}
+
+fn as_pair(query: &Option<JudgeQuery>) -> Option<(&str, &str)> {
+    query
+        .as_ref()
+        .map(|v| (v.log_group.as_str(), v.message.as_str()))
+}
+
+pub(crate) async fn unacknowledged_alerts_handler(
+    State(state): State<ReportService>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    match state
+        .unacknowledged_alerts(query.days.unwrap_or(STATS_DAYS))
+        .await
+    {
+        Ok(alerts) => Json(alerts).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to list unacknowledged alerts: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub(crate) async fn response_times_handler(
+    State(state): State<ReportService>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let Some(log_group) = query.log_group else {
+        tracing::warn!("Log group not found");
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match state
+        .response_times(&log_group, query.days.unwrap_or(STATS_DAYS))
+        .await
+    {
+        Ok(response_times) => Json(response_times).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to compute response times: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub(crate) async fn feedback_page_handler(
+    State(state): State<FeedbackPageService>,
+    Query(query): Query<FeedbackLinkQuery>,
+) -> Response {
+    match state.show(&query).await {
+        Ok(page) => into_page_response(page),
+        Err(e) => {
+            tracing::error!("Failed to show feedback page: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub(crate) async fn submit_feedback_page_handler(
+    State(state): State<FeedbackPageService>,
+    Form(form): Form<FeedbackPageForm>,
+) -> Response {
+    match state
+        .submit(
+            &form.link(),
+            form.needs_notification(),
+            form.severity(),
+            form.reason(),
+        )
+        .await
+    {
+        Ok(page) => into_page_response(page),
+        Err(e) => {
+            tracing::error!("Failed to add feedback from page: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn into_page_response(page: HtmlPage) -> Response {
+    match page {
+        HtmlPage::Ok(html) => Html(html).into_response(),
+        HtmlPage::Forbidden(html) => (StatusCode::FORBIDDEN, Html(html)).into_response(),
+        HtmlPage::Conflict(html) => (StatusCode::CONFLICT, Html(html)).into_response(),
+    }
+}
+
+pub(crate) async fn install_handler(State(state): State<InstallationService>) -> Response {
+    match state.start_install() {
+        Ok((url, oauth_state)) => (
+            [(
+                SET_COOKIE,
+                format!(
+                    "{}={}; Path=/slack; Max-Age=600; HttpOnly; Secure; SameSite=Lax",
+                    STATE_COOKIE, oauth_state
+                ),
+            )],
+            Redirect::to(&url),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to start installation: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub(crate) async fn oauth_redirect_handler(
+    State(state): State<InstallationService>,
+    headers: HeaderMap,
+    Query(query): Query<OAuthRedirectQuery>,
+) -> Response {
+    let cookie_state = headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|v| v.trim().split_once('='))
+        .find(|(name, _)| *name == STATE_COOKIE)
+        .map(|(_, value)| value);
+
+    match state
+        .complete_install(&query.state, cookie_state, query.code.as_deref())
+        .await
+    {
+        Ok(page) => into_page_response(page),
+        Err(e) => {
+            tracing::error!("Failed to complete installation: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(json: serde_json::Value) -> Event {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_is_handled() {
+        let message = |text: &str, thread_ts: Option<&str>| {
+            event(serde_json::json!({
+                "type": "message",
+                "channel": "C123",
+                "user": "U123",
+                "text": text,
+                "ts": "2.0",
+                "thread_ts": thread_ts,
+            }))
+        };
+
+        assert!(is_handled(
+            &message("/aws/lambda/app ERROR failed", Some("1.0")),
+            "U0BOT"
+        ));
+        // Replies without a log, messages outside threads and mentions answered as app
+        // mentions.
+        assert!(!is_handled(&message("thanks!", Some("1.0")), "U0BOT"));
+        assert!(!is_handled(
+            &message("/aws/lambda/app ERROR failed", None),
+            "U0BOT"
+        ));
+        assert!(!is_handled(
+            &message("<@U0BOT> /aws/lambda/app ERROR failed", Some("1.0")),
+            "U0BOT"
+        ));
+        assert!(is_handled(
+            &event(serde_json::json!({
+                "type": "app_mention",
+                "channel": "C123",
+                "user": "U123",
+                "text": "<@U0BOT> hi",
+                "ts": "1.0",
+            })),
+            "U0BOT"
+        ));
+        assert!(!is_handled(
+            &event(serde_json::json!({ "type": "reaction_added" })),
+            "U0BOT"
+        ));
+    }
 }
@@ -1,17 +1,109 @@
-use crate::util::now_timestamp;
-use axum::body::Body;
+use crate::domain::repositories::NonceRepository;
+use crate::domain::value_objects::Timestamp;
+use crate::infrastructure::metrics;
+use crate::infrastructure::repositories_impl::NonceStore;
+use crate::infrastructure::tasks::{MAX_TASK_AGE, TASK_SIGNATURE_HEADER, TASK_TIMESTAMP_HEADER};
+use crate::util::{now_timestamp, slack_signature};
+use axum::body::{Body, Bytes};
+use axum::http::header::{AUTHORIZATION, CONTENT_LENGTH};
 use axum::http::request::Parts;
 use axum::http::{Request, Response, StatusCode};
 use futures_util::future::BoxFuture;
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
-use std::str::from_utf8;
+use http_body_util::LengthLimitError;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use subtle::{Choice, ConstantTimeEq};
 use tower_http::auth::{AsyncAuthorizeRequest, AsyncRequireAuthorizationLayer};
 use typed_builder::TypedBuilder;
 
+/// Largest body of a Slack request read unless configured otherwise, well above the size
+/// of the payloads Slack sends.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// How far the timestamp of a request may be from the current time, either way.
+const MAX_TIMESTAMP_SKEW: i64 = 60 * 5;
+
+/// Headers a kind of signed request carries its signature in, and how old it may be.
+struct Scheme {
+    signature_header: &'static str,
+    timestamp_header: &'static str,
+    max_age: i64,
+}
+
+const SLACK: Scheme = Scheme {
+    signature_header: "X-Slack-Signature",
+    timestamp_header: "X-Slack-Request-Timestamp",
+    max_age: MAX_TIMESTAMP_SKEW,
+};
+
+/// Tasks are retried by Lambda long after they were signed.
+const TASKS: Scheme = Scheme {
+    signature_header: TASK_SIGNATURE_HEADER,
+    timestamp_header: TASK_TIMESTAMP_HEADER,
+    max_age: MAX_TASK_AGE,
+};
+
+/// Checks that requests were signed by Slack with one of the signing secrets.
 #[derive(Clone, TypedBuilder)]
 pub struct Auth {
-    signing_secret: String,
+    /// Secrets a request may be signed with. Both the new and the old secret are listed
+    /// while the secret is being rotated.
+    signing_secrets: Vec<String>,
+    #[builder(default = DEFAULT_MAX_BODY_BYTES)]
+    max_body_bytes: usize,
+    /// Where the requests already handled are recorded, so that a captured request cannot
+    /// be sent again while its timestamp is recent. Replays pass when unset.
+    #[builder(default)]
+    nonces: Option<NonceStore>,
+}
+
+/// Why a request was rejected, counted in the `SignatureRejected` metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rejection {
+    BodyTooLarge,
+    UnreadableBody,
+    MissingSignature,
+    MissingTimestamp,
+    InvalidTimestamp,
+    TimestampTooOld,
+    TimestampInFuture,
+    SignatureMismatch,
+    Replayed,
+    /// The store of nonces failed, so whether the request is a replay is unknown.
+    ReplayCheckFailed,
+}
+
+impl Rejection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::BodyTooLarge => "body_too_large",
+            Self::UnreadableBody => "unreadable_body",
+            Self::MissingSignature => "missing_signature",
+            Self::MissingTimestamp => "missing_timestamp",
+            Self::InvalidTimestamp => "invalid_timestamp",
+            Self::TimestampTooOld => "timestamp_too_old",
+            Self::TimestampInFuture => "timestamp_in_future",
+            Self::SignatureMismatch => "signature_mismatch",
+            Self::Replayed => "replayed",
+            Self::ReplayCheckFailed => "replay_check_failed",
+        }
+    }
+
+    fn into_response(self) -> Response<Body> {
+        tracing::warn!("Rejected request: {}", self.as_str());
+        metrics::count("SignatureRejected", "Reason", self.as_str());
+        match self {
+            Self::BodyTooLarge => Response::builder()
+                .status(StatusCode::PAYLOAD_TOO_LARGE)
+                .body(Body::empty())
+                .unwrap(),
+            Self::ReplayCheckFailed => Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap(),
+            _ => make_error_response(),
+        }
+    }
 }
 
 impl AsyncAuthorizeRequest<Body> for Auth {
@@ -20,62 +112,125 @@ impl AsyncAuthorizeRequest<Body> for Auth {
     type Future = BoxFuture<'static, Result<Request<Body>, Response<Self::ResponseBody>>>;
 
     fn authorize(&mut self, request: Request<Body>) -> Self::Future {
-        let signing_secret = self.signing_secret.clone();
+        let auth = self.clone();
         Box::pin(async move {
-            let (parts, body) = request.into_parts();
-            let bytes = axum::body::to_bytes(body, usize::MAX)
-                .await
-                .map_err(|_| make_error_response())?;
-
-            let check_result = match check_signature(&parts, bytes.as_ref(), &signing_secret).await
-            {
-                Ok(result) => result,
-                Err(e) => {
-                    tracing::warn!("Failed to check signature: {:?}", e);
-                    return Err(make_error_response());
-                }
-            };
-
-            if check_result {
-                let body = Body::from(bytes);
-                let request = Request::from_parts(parts, body);
-                Ok(request)
-            } else {
-                Err(make_error_response())
+            let (parts, bytes) =
+                read_signed(request, auth.max_body_bytes, &auth.signing_secrets, &SLACK)
+                    .await
+                    .map_err(Rejection::into_response)?;
+            if let Some(nonces) = &auth.nonces {
+                check_replay(&parts, nonces)
+                    .await
+                    .map_err(Rejection::into_response)?;
             }
+
+            Ok(Request::from_parts(parts, Body::from(bytes)))
         })
     }
 }
 
-async fn check_signature(
+/// Reads the body of a request and checks that it was signed with one of the secrets.
+async fn read_signed(
+    request: Request<Body>,
+    max_body_bytes: usize,
+    secrets: &[String],
+    scheme: &Scheme,
+) -> Result<(Parts, Bytes), Rejection> {
+    let (parts, body) = request.into_parts();
+    let content_length = parts
+        .headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|v| v > max_body_bytes) {
+        return Err(Rejection::BodyTooLarge);
+    }
+    // Bodies without a length, such as chunked ones, are cut off at the limit instead.
+    let bytes = axum::body::to_bytes(body, max_body_bytes)
+        .await
+        .map_err(|e| {
+            if e.source().is_some_and(|v| v.is::<LengthLimitError>()) {
+                Rejection::BodyTooLarge
+            } else {
+                Rejection::UnreadableBody
+            }
+        })?;
+
+    check_signature(&parts, &bytes, secrets, now_timestamp(), scheme)?;
+    Ok((parts, bytes))
+}
+
+fn check_signature(
     parts: &Parts,
     body: &[u8],
-    signing_secret: &str,
-) -> Result<bool, Box<dyn std::error::Error>> {
+    signing_secrets: &[String],
+    now: i64,
+    scheme: &Scheme,
+) -> Result<(), Rejection> {
     let signature = parts
         .headers
-        .get("X-Slack-Signature")
-        .ok_or("no signature")?
-        .to_str()?;
+        .get(scheme.signature_header)
+        .ok_or(Rejection::MissingSignature)?
+        .as_bytes();
     let timestamp = parts
         .headers
-        .get("X-Slack-Request-Timestamp")
-        .ok_or("no timestamp")?
-        .to_str()?;
+        .get(scheme.timestamp_header)
+        .ok_or(Rejection::MissingTimestamp)?
+        .to_str()
+        .map_err(|_| Rejection::InvalidTimestamp)?;
 
-    if now_timestamp() - timestamp.parse::<i64>()? > 60 * 5 {
-        return Err("timestamp is too old".into());
+    let skew = now
+        - timestamp
+            .parse::<i64>()
+            .map_err(|_| Rejection::InvalidTimestamp)?;
+    if skew > scheme.max_age {
+        return Err(Rejection::TimestampTooOld);
+    }
+    if skew < -MAX_TIMESTAMP_SKEW {
+        return Err(Rejection::TimestampInFuture);
     }
 
-    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())?;
-
-    let hash = format!("v0:{}:{}", timestamp, from_utf8(body)?,);
-
-    mac.update(hash.as_bytes());
+    // Every secret is tried, so that the time taken does not reveal which one matched.
+    let matched = signing_secrets.iter().fold(Choice::from(0), |matched, v| {
+        matched
+            | slack_signature(v, timestamp, body)
+                .as_bytes()
+                .ct_eq(signature)
+    });
+    if bool::from(matched) {
+        Ok(())
+    } else {
+        Err(Rejection::SignatureMismatch)
+    }
+}
 
-    let result = format!("v0={:x}", mac.finalize().into_bytes());
+/// Claims the nonce of a request whose signature was checked, which is the digest of its
+/// timestamp and signature. The nonce is kept for as long as the timestamp passes.
+async fn check_replay(parts: &Parts, nonces: &NonceStore) -> Result<(), Rejection> {
+    let header = |name| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    let timestamp = header("X-Slack-Request-Timestamp");
+    let nonce = format!(
+        "{:x}",
+        Sha256::digest(format!("{}:{}", timestamp, header("X-Slack-Signature")))
+    );
+    let expires_at =
+        Timestamp::from_secs(timestamp.parse::<i64>().unwrap_or_default() + MAX_TIMESTAMP_SKEW);
 
-    Ok(signature == result)
+    match nonces.claim_nonce(&nonce, &expires_at).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Rejection::Replayed),
+        Err(e) => {
+            // Requests are refused rather than risk handling one twice.
+            tracing::error!("Failed to claim nonce: {:?}", e);
+            Err(Rejection::ReplayCheckFailed)
+        }
+    }
 }
 
 fn make_error_response() -> Response<Body> {
@@ -85,6 +240,244 @@ fn make_error_response() -> Response<Body> {
         .unwrap()
 }
 
-pub fn create_auth_layer(signing_secret: String) -> AsyncRequireAuthorizationLayer<Auth> {
-    AsyncRequireAuthorizationLayer::new(Auth::builder().signing_secret(signing_secret).build())
+pub fn create_auth_layer(
+    signing_secrets: Vec<String>,
+    max_body_bytes: usize,
+    nonces: Option<NonceStore>,
+) -> AsyncRequireAuthorizationLayer<Auth> {
+    AsyncRequireAuthorizationLayer::new(
+        Auth::builder()
+            .signing_secrets(signing_secrets)
+            .max_body_bytes(max_body_bytes)
+            .nonces(nonces)
+            .build(),
+    )
+}
+
+/// Requires `Authorization: Bearer <api_token>` on the API, which is not called by Slack.
+#[derive(Clone, TypedBuilder)]
+pub struct ApiAuth {
+    api_token: String,
+}
+
+impl AsyncAuthorizeRequest<Body> for ApiAuth {
+    type RequestBody = Body;
+    type ResponseBody = Body;
+    type Future = BoxFuture<'static, Result<Request<Body>, Response<Self::ResponseBody>>>;
+
+    fn authorize(&mut self, request: Request<Body>) -> Self::Future {
+        let authorized = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|v| bool::from(v.as_bytes().ct_eq(self.api_token.as_bytes())));
+        Box::pin(async move {
+            if authorized {
+                Ok(request)
+            } else {
+                Err(make_error_response())
+            }
+        })
+    }
+}
+
+pub fn create_api_auth_layer(api_token: String) -> AsyncRequireAuthorizationLayer<ApiAuth> {
+    AsyncRequireAuthorizationLayer::new(ApiAuth::builder().api_token(api_token).build())
+}
+
+/// Checks that tasks were signed by the collector with the secret of the tasks, which
+/// Slack does not know, so that Slack requests cannot pass for tasks.
+#[derive(Clone, TypedBuilder)]
+pub struct TaskAuth {
+    task_secret: String,
+}
+
+impl AsyncAuthorizeRequest<Body> for TaskAuth {
+    type RequestBody = Body;
+    type ResponseBody = Body;
+    type Future = BoxFuture<'static, Result<Request<Body>, Response<Self::ResponseBody>>>;
+
+    fn authorize(&mut self, request: Request<Body>) -> Self::Future {
+        let secrets = vec![self.task_secret.clone()];
+        Box::pin(async move {
+            let (parts, bytes) = read_signed(request, DEFAULT_MAX_BODY_BYTES, &secrets, &TASKS)
+                .await
+                .map_err(Rejection::into_response)?;
+            Ok(Request::from_parts(parts, Body::from(bytes)))
+        })
+    }
+}
+
+pub fn create_task_auth_layer(task_secret: String) -> AsyncRequireAuthorizationLayer<TaskAuth> {
+    AsyncRequireAuthorizationLayer::new(TaskAuth::builder().task_secret(task_secret).build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::repositories_impl::InMemoryNonceRepository;
+    use tower::ServiceExt;
+
+    fn parts(signature: Option<&str>, timestamp: Option<&str>) -> Parts {
+        let mut builder = Request::post("/feedback");
+        if let Some(signature) = signature {
+            builder = builder.header("X-Slack-Signature", signature);
+        }
+        if let Some(timestamp) = timestamp {
+            builder = builder.header("X-Slack-Request-Timestamp", timestamp);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_check_signature() {
+        let now = 1_735_689_600;
+        let secrets = vec!["new".to_string(), "old".to_string()];
+        let body = b"payload=%7B%7D";
+        let check = |signature: Option<&str>, timestamp: i64| {
+            let timestamp = timestamp.to_string();
+            check_signature(
+                &parts(signature, Some(&timestamp)),
+                body,
+                &secrets,
+                now,
+                &SLACK,
+            )
+        };
+
+        let signed = |secret, timestamp: i64| slack_signature(secret, &timestamp.to_string(), body);
+        assert_eq!(check(Some(&signed("new", now)), now), Ok(()));
+        // Requests signed with the previous secret pass while it is still listed.
+        assert_eq!(check(Some(&signed("old", now - 60)), now - 60), Ok(()));
+        assert_eq!(
+            check(Some(&signed("other", now)), now),
+            Err(Rejection::SignatureMismatch)
+        );
+        assert_eq!(
+            check(Some(&signed("new", now - 301)), now - 301),
+            Err(Rejection::TimestampTooOld)
+        );
+        assert_eq!(
+            check(Some(&signed("new", now + 301)), now + 301),
+            Err(Rejection::TimestampInFuture)
+        );
+        assert_eq!(check(None, now), Err(Rejection::MissingSignature));
+        assert_eq!(
+            check_signature(&parts(Some("v0=0"), None), body, &secrets, now, &SLACK),
+            Err(Rejection::MissingTimestamp)
+        );
+        assert_eq!(
+            check_signature(
+                &parts(Some("v0=0"), Some("soon")),
+                body,
+                &secrets,
+                now,
+                &SLACK
+            ),
+            Err(Rejection::InvalidTimestamp)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replayed_request() {
+        let auth = Auth::builder()
+            .signing_secrets(vec!["secret".into()])
+            .nonces(Some(NonceStore::InMemory(
+                InMemoryNonceRepository::default(),
+            )))
+            .build();
+        let app = axum::Router::new()
+            .route(
+                "/feedback",
+                axum::routing::post(|| async { StatusCode::OK }),
+            )
+            .layer(AsyncRequireAuthorizationLayer::new(auth));
+        let timestamp = now_timestamp().to_string();
+        let request = |body: &'static str| {
+            Request::post("/feedback")
+                .header("X-Slack-Request-Timestamp", &timestamp)
+                .header(
+                    "X-Slack-Signature",
+                    slack_signature("secret", &timestamp, body.as_bytes()),
+                )
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let status = |request: Request<Body>| {
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+        assert_eq!(status(request("payload=%7B%7D")).await, StatusCode::OK);
+        assert_eq!(
+            status(request("payload=%7B%7D")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        // Other requests signed in the same second have other signatures.
+        assert_eq!(status(request("payload=%5B%5D")).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_task_auth() {
+        let app = axum::Router::new()
+            .route("/tasks", axum::routing::post(|| async { StatusCode::OK }))
+            .layer(create_task_auth_layer("task-secret".into()));
+        let body = r#"{"type":"publish_home"}"#;
+        let request = |headers: [&str; 2], secret: &str, timestamp: i64| {
+            let timestamp = timestamp.to_string();
+            Request::post("/tasks")
+                .header(headers[0], &timestamp)
+                .header(
+                    headers[1],
+                    slack_signature(secret, &timestamp, body.as_bytes()),
+                )
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let status = |request: Request<Body>| {
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+        let task_headers = [TASK_TIMESTAMP_HEADER, TASK_SIGNATURE_HEADER];
+        let slack_headers = ["X-Slack-Request-Timestamp", "X-Slack-Signature"];
+        let now = now_timestamp();
+
+        assert_eq!(
+            status(request(task_headers, "task-secret", now)).await,
+            StatusCode::OK
+        );
+        // Tasks retried by Lambda an hour later still pass.
+        assert_eq!(
+            status(request(task_headers, "task-secret", now - 3600)).await,
+            StatusCode::OK
+        );
+        // Requests signed by Slack are not tasks.
+        assert_eq!(
+            status(request(task_headers, "signing-secret", now)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(request(slack_headers, "task-secret", now)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_too_large() {
+        let app = axum::Router::new()
+            .route(
+                "/feedback",
+                axum::routing::post(|| async { StatusCode::OK }),
+            )
+            .layer(create_auth_layer(vec!["secret".into()], 1000, None));
+        // A streamed body has no Content-Length, so only reading it finds it too large.
+        let chunks = (0..2).map(|_| Ok::<_, std::io::Error>(Bytes::from(vec![b'a'; 600])));
+        let request = Request::post("/feedback")
+            .body(Body::from_stream(futures_util::stream::iter(chunks)))
+            .unwrap();
+        assert!(request.headers().get(CONTENT_LENGTH).is_none());
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
 }
@@ -2,3 +2,4 @@ mod handlers;
 pub mod middleware;
 mod payloads;
 pub mod routers;
+pub mod socket_mode;
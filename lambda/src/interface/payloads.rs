@@ -1,6 +1,13 @@
-use crate::util::deserialize_bool;
+use crate::domain::value_objects::{ConflictResolution, Severity};
+use crate::infrastructure::block_kit::{
+    ActionId, BlockId, CallbackId, ElementState, SelectOption, StateValues,
+};
+use crate::infrastructure::feedback_link::FeedbackLinkQuery;
+use crate::infrastructure::validation::{InputError, InputErrors};
+use crate::util::parse_duration;
+use regex::Regex;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::sync::LazyLock;
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -9,140 +16,716 @@ pub(crate) enum InteractivityPayload {
     ViewSubmission(ViewSubmission),
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged, rename_all = "snake_case")]
-pub(crate) enum BlockActions {
-    OpenModal(OpenModal),
-    #[allow(dead_code)]
-    Other(serde_json::Value),
+impl InteractivityPayload {
+    /// Returns the workspace the interaction happened in.
+    pub(crate) fn team_id(&self) -> Option<&str> {
+        let team = match self {
+            Self::BlockActions(payload) => &payload.team,
+            Self::ViewSubmission(payload) => &payload.team,
+        };
+        team.as_ref().map(|v| v.id.as_str())
+    }
+
+    /// Returns where replies to the interaction can be sent, given for actions on messages.
+    pub(crate) fn response_url(&self) -> Option<&str> {
+        match self {
+            Self::BlockActions(payload) => payload.response_url.as_deref(),
+            Self::ViewSubmission(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct OpenModal {
+pub(crate) struct BlockActions {
     trigger_id: String,
-    message: Message,
+    user: User,
+    team: Option<Team>,
+    container: Container,
+    actions: Vec<Action>,
+    response_url: Option<String>,
 }
 
-impl OpenModal {
+impl BlockActions {
     pub(crate) fn trigger_id(&self) -> &str {
         &self.trigger_id
     }
 
-    pub(crate) fn get_ts(&self) -> &str {
-        &self.message.ts
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user.id
     }
 
-    pub(crate) fn get_log_group(&self) -> Option<&str> {
-        self.message
-            .blocks
-            .iter()
-            .find_map(|block| match block.block_id {
-                BlockId::LogGroup => Some(&block.text.as_ref()?.value),
-                _ => None,
-            })
-            .map(|value| value.as_str())
+    pub(crate) fn action_id(&self) -> Option<ActionId> {
+        self.actions.first().map(|action| action.action_id)
     }
 
-    pub(crate) fn get_message(&self) -> Option<&str> {
-        self.message
-            .blocks
-            .iter()
-            .find_map(|block| match block.block_id {
-                BlockId::Message => Some(&block.text.as_ref()?.value),
-                _ => None,
-            })
-            .map(|value| value.as_str())
+    pub(crate) fn action_value(&self) -> Option<&str> {
+        self.actions
+            .first()
+            .and_then(|action| action.value.as_deref())
+    }
+
+    /// Returns the value of the option chosen in a menu.
+    pub(crate) fn selected_value(&self) -> Option<&str> {
+        self.actions
+            .first()
+            .and_then(|action| action.selected_option.as_ref())
+            .map(|option| option.value.as_str())
+    }
+
+    /// Returns the channel of the message the action was taken on.
+    pub(crate) fn get_channel_id(&self) -> Option<&str> {
+        self.container.channel_id.as_deref()
+    }
+
+    /// Returns the timestamp of the message the action was taken on.
+    pub(crate) fn get_ts(&self) -> Option<&str> {
+        self.container.message_ts.as_deref()
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct Action {
+    action_id: ActionId,
+    value: Option<String>,
+    selected_option: Option<SelectOption>,
+}
+
+/// Where an action was taken, either a message or a view.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Container {
+    message_ts: Option<String>,
+    channel_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct ViewSubmission {
+    user: User,
+    team: Option<Team>,
     view: View,
 }
 
 impl ViewSubmission {
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user.id
+    }
+
+    /// Returns the id of the modal, which stays the same when the modal is updated.
+    pub(crate) fn view_id(&self) -> &str {
+        &self.view.id
+    }
+
+    pub(crate) fn get_callback_id(&self) -> CallbackId {
+        self.view.callback_id
+    }
+
     pub(crate) fn get_private_metadata(&self) -> &str {
         &self.view.private_metadata
     }
 
-    pub(crate) fn get_modal_values(
-        &self,
-    ) -> Result<(bool, Option<String>), Box<dyn std::error::Error>> {
-        let needs_notification = match self.view.state.values.get("needs_notification") {
-            Some(Value::NeedsNotification(need_notification)) => {
-                need_notification.selected_option.value
+    /// Returns the values of the feedback modal, or the errors of all the fields that could
+    /// not be read.
+    pub(crate) fn get_modal_values(&self) -> Result<FeedbackValues, InputErrors> {
+        let mut errors = InputErrors::new();
+        let needs_notification = match self
+            .get_selected_value(BlockId::NeedsNotification, ActionId::NeedsNotification)
+            .map(str::parse::<bool>)
+        {
+            Some(Ok(needs_notification)) => Some(needs_notification),
+            Some(Err(_)) => {
+                errors.insert(BlockId::NeedsNotification, InputError::Invalid);
+                None
+            }
+            None => {
+                errors.insert(BlockId::NeedsNotification, InputError::Missing);
+                None
             }
-            _ => return Err("Needs notification not found")?,
         };
-        let reason = match self.view.state.values.get("reason") {
-            Some(Value::Reason(reason)) => reason.value.clone(),
-            _ => return Err("Reason not found")?,
+        let severity = self
+            .get_selected_value(BlockId::Severity, ActionId::Severity)
+            .map(Severity::try_from)
+            .transpose()
+            .unwrap_or_else(|_| {
+                errors.insert(BlockId::Severity, InputError::Invalid);
+                None
+            });
+        let reason = match self.get_state(BlockId::Reason, ActionId::Reason) {
+            Some(ElementState::PlainTextInput { value }) => value.clone(),
+            _ => {
+                errors.insert(BlockId::Reason, InputError::Missing);
+                None
+            }
+        };
+
+        match needs_notification {
+            Some(needs_notification) if errors.is_empty() => Ok(FeedbackValues {
+                needs_notification,
+                severity,
+                reason,
+            }),
+            _ => Err(errors),
+        }
+    }
+
+    pub(crate) fn get_conflict_resolution(
+        &self,
+    ) -> Result<ConflictResolution, Box<dyn std::error::Error>> {
+        let resolution = self
+            .get_selected_value(BlockId::Resolution, ActionId::Resolution)
+            .ok_or("Resolution not found")?;
+        ConflictResolution::try_from(resolution)
+    }
+
+    /// Returns the values of the snooze modal, or the errors of all the fields that could
+    /// not be read.
+    pub(crate) fn get_snooze_values(&self) -> Result<SnoozeValues, InputErrors> {
+        let mut errors = InputErrors::new();
+        let until = match self.get_state(BlockId::SnoozeUntil, ActionId::SnoozeUntil) {
+            Some(ElementState::DatetimePicker {
+                selected_date_time: Some(until),
+            }) => Some(*until),
+            _ => {
+                errors.insert(BlockId::SnoozeUntil, InputError::Missing);
+                None
+            }
         };
-        Ok((needs_notification, reason))
+        let whole_log_group =
+            match self.get_selected_value(BlockId::SnoozeScope, ActionId::SnoozeScope) {
+                Some("message") => Some(false),
+                Some("log_group") => Some(true),
+                Some(_) => {
+                    errors.insert(BlockId::SnoozeScope, InputError::Invalid);
+                    None
+                }
+                None => {
+                    errors.insert(BlockId::SnoozeScope, InputError::Missing);
+                    None
+                }
+            };
+
+        match (until, whole_log_group) {
+            (Some(until), Some(whole_log_group)) => Ok(SnoozeValues {
+                until,
+                whole_log_group,
+            }),
+            _ => Err(errors),
+        }
+    }
+
+    fn get_state(&self, block_id: BlockId, action_id: ActionId) -> Option<&ElementState> {
+        self.view.state.values.get(&block_id)?.get(&action_id)
+    }
+
+    fn get_selected_value(&self, block_id: BlockId, action_id: ActionId) -> Option<&str> {
+        match self.get_state(block_id, action_id)? {
+            ElementState::StaticSelect {
+                selected_option: Some(option),
+            } => Some(&option.value),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct Message {
-    ts: String,
-    blocks: Vec<Block>,
+/// Values entered in the feedback modal.
+pub(crate) struct FeedbackValues {
+    pub(crate) needs_notification: bool,
+    pub(crate) severity: Option<Severity>,
+    pub(crate) reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct Block {
-    block_id: BlockId,
-    text: Option<Text>,
+/// Values entered in the snooze modal.
+pub(crate) struct SnoozeValues {
+    /// Unix time in seconds.
+    pub(crate) until: i64,
+    pub(crate) whole_log_group: bool,
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub(crate) enum BlockId {
-    Header,
-    LogGroupHeader,
-    MessageHeader,
-    LogGroup,
-    Message,
-    Divider,
-    FeedbackButton,
+pub(crate) struct User {
+    id: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct Text {
-    #[serde(rename = "text")]
-    value: String,
+pub(crate) struct Team {
+    id: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct View {
+    id: String,
+    callback_id: CallbackId,
     state: State,
     private_metadata: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct State {
-    values: HashMap<String, Value>,
+    values: StateValues,
+}
+
+/// Payload of the Events API.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum EventPayload {
+    UrlVerification {
+        challenge: String,
+    },
+    EventCallback(Box<EventCallback>),
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub(crate) enum Value {
-    #[serde(rename = "needs_notification")]
-    NeedsNotification(NeedsNotification),
-    Reason(Reason),
+pub(crate) struct EventCallback {
+    pub(crate) event: Event,
+    event_id: Option<String>,
+    team_id: Option<String>,
+    #[serde(default)]
+    authorizations: Vec<Authorization>,
+}
+
+impl EventCallback {
+    /// Returns the id of the event, the same in each delivery of it.
+    pub(crate) fn event_id(&self) -> Option<&str> {
+        self.event_id.as_deref()
+    }
+
+    /// Returns the workspace the event happened in.
+    pub(crate) fn team_id(&self) -> Option<&str> {
+        self.team_id.as_deref()
+    }
+
+    /// Returns the user id of this app's bot in the workspace the event was sent from.
+    pub(crate) fn bot_user_id(&self) -> Option<&str> {
+        self.authorizations.first().map(|v| v.user_id.as_str())
+    }
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct NeedsNotification {
-    selected_option: SelectedOption,
+pub(crate) struct Authorization {
+    user_id: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct Reason {
-    value: Option<String>,
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Event {
+    AppMention(MessageEvent),
+    Message(MessageEvent),
+    AppHomeOpened(AppHomeOpened),
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct SelectedOption {
-    #[serde(deserialize_with = "deserialize_bool")]
-    value: bool,
+pub(crate) struct MessageEvent {
+    channel: String,
+    user: Option<String>,
+    #[serde(default)]
+    text: String,
+    ts: String,
+    thread_ts: Option<String>,
+    bot_id: Option<String>,
+    subtype: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AppHomeOpened {
+    user: String,
+    tab: String,
+}
+
+impl AppHomeOpened {
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user
+    }
+
+    /// Returns whether the Home tab was opened, as opposed to the Messages tab.
+    pub(crate) fn is_home(&self) -> bool {
+        self.tab == "home"
+    }
+}
+
+/// A log pasted in a message to be judged.
+#[derive(Debug, PartialEq)]
+pub(crate) struct JudgeQuery {
+    pub(crate) log_group: String,
+    pub(crate) message: String,
+}
+
+impl MessageEvent {
+    pub(crate) fn channel_id(&self) -> &str {
+        &self.channel
+    }
+
+    /// Returns the author of the message, empty for messages not written by a user.
+    pub(crate) fn user_id(&self) -> &str {
+        self.user.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the thread to reply in, which is the message itself if it is not in a thread.
+    pub(crate) fn reply_ts(&self) -> &str {
+        self.thread_ts.as_deref().unwrap_or(&self.ts)
+    }
+
+    pub(crate) fn thread_ts(&self) -> Option<&str> {
+        self.thread_ts.as_deref()
+    }
+
+    /// Returns whether the message was written by a user, as opposed to a bot or an edit,
+    /// a deletion or another change to a message.
+    pub(crate) fn is_user_message(&self) -> bool {
+        self.user.is_some() && self.bot_id.is_none() && self.subtype.is_none()
+    }
+
+    pub(crate) fn mentions(&self, user_id: &str) -> bool {
+        self.text.contains(&format!("<@{}>", user_id))
+    }
+
+    /// Parses a log group followed by a log message, ignoring mentions and code formatting.
+    /// Returns `None` if either is missing.
+    pub(crate) fn get_query(&self) -> Option<JudgeQuery> {
+        let text = MENTION.replace_all(&self.text, "");
+        let (log_group, message) = split_first_word(&text);
+        let log_group = log_group.trim_matches('`');
+        let message = message
+            .trim_start_matches("```")
+            .trim_end_matches("```")
+            .trim()
+            .trim_matches('`')
+            .trim();
+        if log_group.is_empty() || message.is_empty() {
+            return None;
+        }
+        Some(JudgeQuery {
+            log_group: unescape(log_group),
+            message: unescape(message),
+        })
+    }
+}
+
+static MENTION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<@[A-Z0-9]+>").unwrap());
+
+/// Reverts the escaping Slack applies to the text of messages.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Query of the API reports.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReportQuery {
+    /// Number of days covered by the report.
+    pub(crate) days: Option<i64>,
+    /// Log group to report on, required by the reports of a single log group.
+    pub(crate) log_group: Option<String>,
+}
+
+/// Query of the redirect from Slack at the end of the OAuth flow.
+#[derive(Debug, Deserialize)]
+pub(crate) struct OAuthRedirectQuery {
+    #[serde(default)]
+    pub(crate) state: String,
+    /// Missing when the user cancelled the installation.
+    pub(crate) code: Option<String>,
+}
+
+/// Payload of the `/alert-filter` slash command.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SlashCommand {
+    #[serde(default)]
+    team_id: Option<String>,
+    user_id: String,
+    channel_id: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// Feedback posted from the feedback page, with the parameters of the link it was opened
+/// from.
+#[derive(Debug, Deserialize)]
+pub(crate) struct FeedbackPageForm {
+    decision_id: String,
+    expires: i64,
+    signature: String,
+    needs_notification: bool,
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    reason: String,
+}
+
+impl FeedbackPageForm {
+    pub(crate) fn link(&self) -> FeedbackLinkQuery {
+        FeedbackLinkQuery {
+            decision_id: self.decision_id.clone(),
+            expires: self.expires,
+            signature: self.signature.clone(),
+        }
+    }
+
+    pub(crate) fn needs_notification(&self) -> bool {
+        self.needs_notification
+    }
+
+    /// Returns the chosen severity, or `None` when none was chosen.
+    pub(crate) fn severity(&self) -> Option<Severity> {
+        Severity::try_from(self.severity.as_str()).ok()
+    }
+
+    pub(crate) fn reason(&self) -> Option<String> {
+        Some(self.reason.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+    }
+}
+
+/// A subcommand of `/alert-filter`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Status,
+    Stats {
+        log_group: String,
+    },
+    FeedbackList {
+        log_group: String,
+    },
+    Test {
+        log_group: String,
+        message: String,
+    },
+    Mute {
+        log_group: String,
+        duration: chrono::Duration,
+    },
+}
+
+impl SlashCommand {
+    pub(crate) fn team_id(&self) -> Option<&str> {
+        self.team_id.as_deref()
+    }
+
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub(crate) fn channel_id(&self) -> &str {
+        &self.channel_id
+    }
+
+    /// Parses the text after the command name, returning `None` if it is not a valid subcommand.
+    pub(crate) fn get_command(&self) -> Option<Command> {
+        let (name, args) = split_first_word(&self.text);
+        match name {
+            "status" if args.is_empty() => Some(Command::Status),
+            "stats" => Some(Command::Stats {
+                log_group: single_word(args)?,
+            }),
+            "feedback" => match split_first_word(args) {
+                ("list", log_group) => Some(Command::FeedbackList {
+                    log_group: single_word(log_group)?,
+                }),
+                _ => None,
+            },
+            "test" => {
+                let (log_group, message) = split_first_word(args);
+                if log_group.is_empty() || message.is_empty() {
+                    return None;
+                }
+                Some(Command::Test {
+                    log_group: log_group.to_string(),
+                    message: message.to_string(),
+                })
+            }
+            "mute" => {
+                let (log_group, duration) = split_first_word(args);
+                if log_group.is_empty() {
+                    return None;
+                }
+                Some(Command::Mute {
+                    log_group: log_group.to_string(),
+                    duration: parse_duration(&single_word(duration)?)?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Splits off the first whitespace-separated word, returning it and the trimmed rest.
+fn split_first_word(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    match text.split_once(char::is_whitespace) {
+        Some((first, rest)) => (first, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+fn single_word(text: &str) -> Option<String> {
+    match split_first_word(text) {
+        (word, "") if !word.is_empty() => Some(word.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Option<Command> {
+        SlashCommand {
+            team_id: Some("T123".into()),
+            user_id: "U123".into(),
+            channel_id: "C123".into(),
+            text: text.into(),
+        }
+        .get_command()
+    }
+
+    fn message(text: &str) -> MessageEvent {
+        MessageEvent {
+            channel: "C123".into(),
+            user: Some("U123".into()),
+            text: text.into(),
+            ts: "1700000000.000100".into(),
+            thread_ts: None,
+            bot_id: None,
+            subtype: None,
+        }
+    }
+
+    #[test]
+    fn test_get_query() {
+        assert_eq!(
+            message("<@U0BOT> /aws/lambda/app ERROR a &lt; b &amp;&amp; c").get_query(),
+            Some(JudgeQuery {
+                log_group: "/aws/lambda/app".into(),
+                message: "ERROR a < b && c".into()
+            })
+        );
+        assert_eq!(
+            message("<@U0BOT> `/aws/lambda/app`\n```\nERROR request failed\n```").get_query(),
+            Some(JudgeQuery {
+                log_group: "/aws/lambda/app".into(),
+                message: "ERROR request failed".into()
+            })
+        );
+        assert_eq!(message("<@U0BOT> /aws/lambda/app").get_query(), None);
+        assert_eq!(message("<@U0BOT>").get_query(), None);
+
+        let event: EventPayload = serde_json::from_str(
+            r#"{"type":"event_callback","event_id":"Ev123","event":{"type":"app_mention","channel":"C123","user":"U123","text":"<@U0BOT> hi","ts":"1.2"},"authorizations":[{"user_id":"U0BOT"}]}"#,
+        )
+        .unwrap();
+        let EventPayload::EventCallback(callback) = event else {
+            panic!("not an event callback");
+        };
+        assert_eq!(callback.event_id(), Some("Ev123"));
+        assert_eq!(callback.bot_user_id(), Some("U0BOT"));
+        assert!(matches!(callback.event, Event::AppMention(ref v) if v.mentions("U0BOT")));
+    }
+
+    fn submission(values: serde_json::Value) -> ViewSubmission {
+        serde_json::from_value(serde_json::json!({
+            "user": { "id": "U123" },
+            "view": {
+                "id": "V123",
+                "callback_id": "send_feedback",
+                "private_metadata": "",
+                "state": { "values": values }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn select(value: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "type": "static_select",
+            "selected_option": value.map(|v| serde_json::json!({
+                "text": { "type": "plain_text", "text": v },
+                "value": v
+            }))
+        })
+    }
+
+    #[test]
+    fn test_get_modal_values() {
+        let values = submission(serde_json::json!({
+            "needs_notification": { "needs_notification": select(Some("false")) },
+            "severity": { "severity": select(None) },
+            "reason": { "reason": { "type": "plain_text_input", "value": "expected" } }
+        }))
+        .get_modal_values()
+        .unwrap();
+        assert!(!values.needs_notification);
+        assert_eq!(values.severity, None);
+        assert_eq!(values.reason.as_deref(), Some("expected"));
+
+        // Every field that cannot be read is reported at once.
+        let errors = submission(serde_json::json!({
+            "needs_notification": { "needs_notification": select(None) },
+            "severity": { "severity": select(Some("urgent")) },
+            "reason": { "reason": { "type": "plain_text_input", "value": null } }
+        }))
+        .get_modal_values()
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors,
+            InputErrors::from([
+                (BlockId::NeedsNotification, InputError::Missing),
+                (BlockId::Severity, InputError::Invalid),
+            ])
+        );
+
+        let errors = submission(serde_json::json!({
+            "snooze_scope": { "snooze_scope": select(Some("everything")) }
+        }))
+        .get_snooze_values()
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors,
+            InputErrors::from([
+                (BlockId::SnoozeUntil, InputError::Missing),
+                (BlockId::SnoozeScope, InputError::Invalid),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_get_command() {
+        assert_eq!(parse(" status "), Some(Command::Status));
+        assert_eq!(
+            parse("stats /aws/lambda/app"),
+            Some(Command::Stats {
+                log_group: "/aws/lambda/app".into()
+            })
+        );
+        assert_eq!(
+            parse("feedback list /aws/lambda/app"),
+            Some(Command::FeedbackList {
+                log_group: "/aws/lambda/app".into()
+            })
+        );
+        assert_eq!(
+            parse("test /aws/lambda/app ERROR  request  failed"),
+            Some(Command::Test {
+                log_group: "/aws/lambda/app".into(),
+                message: "ERROR  request  failed".into()
+            })
+        );
+        assert_eq!(
+            parse("mute /aws/lambda/app 4h"),
+            Some(Command::Mute {
+                log_group: "/aws/lambda/app".into(),
+                duration: chrono::Duration::hours(4)
+            })
+        );
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("stats"), None);
+        assert_eq!(parse("stats a b"), None);
+        assert_eq!(parse("feedback delete /aws/lambda/app"), None);
+        assert_eq!(parse("test /aws/lambda/app"), None);
+        assert_eq!(parse("mute /aws/lambda/app forever"), None);
+    }
 }
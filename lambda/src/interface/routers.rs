@@ -1,27 +1,220 @@
-use crate::application::services::CollectionService;
-use crate::infrastructure::repositories_impl::FeedbackRepositoryImpl;
-use crate::infrastructure::slack;
-use crate::interface::handlers::add_feedback_handler;
-use axum::routing::post;
+use crate::application::services::{
+    CollectionService, CommandService, EventService, FeedbackPageService, InstallationService,
+    ReportService,
+};
+use crate::infrastructure::feedback_link::FeedbackLinks;
+use crate::infrastructure::oauth::OAuthSettings;
+use crate::infrastructure::repositories_impl::{
+    DecisionRepositoryImpl, FeedbackRepositoryImpl, InstallationRepositoryImpl, MuteRepositoryImpl,
+    NonceStore, RouteRepositoryImpl, RuleRepositoryImpl,
+};
+use crate::infrastructure::sinks::AlertSinks;
+use crate::infrastructure::tasks::{TaskQueue, TASK_PATH};
+use crate::infrastructure::validation::ValidationRules;
+use crate::infrastructure::{bedrock, slack};
+use crate::interface::handlers::{
+    add_feedback_handler, command_handler, events_handler, feedback_page_handler, install_handler,
+    oauth_redirect_handler, response_times_handler, run_task_handler, submit_feedback_page_handler,
+    unacknowledged_alerts_handler,
+};
+use axum::routing::{get, post};
 use axum::Router;
 
+/// Returns the routes of the interactions, and apart from them the route of the tasks
+/// finishing the interactions after they are answered, which Slack does not call. The
+/// tasks are run before answering when no queue is given.
+#[allow(clippy::too_many_arguments)]
 pub async fn create_feedback_router(
     dynamodb_client: aws_sdk_dynamodb::Client,
     table_name: String,
+    rule_table_name: String,
+    decision_table_name: String,
+    mute_table_name: String,
+    route_repo: RouteRepositoryImpl,
+    bedrock_client: bedrock::Client,
+    slack_client: slack::Client,
+    sinks: AlertSinks,
+    tasks: Option<TaskQueue>,
+    nonces: Option<NonceStore>,
+    validation: ValidationRules,
+) -> (Router, Router) {
+    let repo = FeedbackRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(table_name)
+        .build();
+    let rule_repo = RuleRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(rule_table_name)
+        .build();
+    let decision_repo = DecisionRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(decision_table_name)
+        .build();
+    let mute_repo = MuteRepositoryImpl::builder()
+        .client(dynamodb_client)
+        .table_name(mute_table_name)
+        .build();
+
+    let state = CollectionService::builder()
+        .repo(repo)
+        .rule_repo(rule_repo)
+        .decision_repo(decision_repo)
+        .mute_repo(mute_repo)
+        .route_repo(route_repo)
+        .bedrock_client(bedrock_client)
+        .slack_client(slack_client)
+        .sinks(sinks)
+        .tasks(tasks)
+        .nonces(nonces)
+        .validation(validation)
+        .build();
+    let feedback = Router::new()
+        .route("/", post(add_feedback_handler))
+        .with_state(state.clone());
+    let tasks = Router::new()
+        .route(TASK_PATH, post(run_task_handler))
+        .with_state(state);
+    (feedback, tasks)
+}
+
+pub async fn create_command_router(
+    dynamodb_client: aws_sdk_dynamodb::Client,
+    table_name: String,
+    decision_table_name: String,
+    mute_table_name: String,
+    route_repo: RouteRepositoryImpl,
+    bedrock_client: bedrock::Client,
     slack_client: slack::Client,
-    slack_channel_id: String,
 ) -> Router {
     let repo = FeedbackRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(table_name)
+        .build();
+    let decision_repo = DecisionRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(decision_table_name)
+        .build();
+    let mute_repo = MuteRepositoryImpl::builder()
         .client(dynamodb_client)
+        .table_name(mute_table_name)
+        .build();
+
+    let state = CommandService::builder()
+        .repo(repo)
+        .decision_repo(decision_repo)
+        .route_repo(route_repo)
+        .mute_repo(mute_repo)
+        .bedrock_client(bedrock_client)
+        .slack_client(slack_client)
+        .build();
+    Router::new()
+        .route("/", post(command_handler))
+        .with_state(state)
+}
+
+/// Routes of the events. Judgements asked for in threads are run before answering when no
+/// queue is given, and retried events are handled again when no nonces are given.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_event_router(
+    dynamodb_client: aws_sdk_dynamodb::Client,
+    table_name: String,
+    decision_table_name: String,
+    route_repo: RouteRepositoryImpl,
+    bedrock_client: bedrock::Client,
+    slack_client: slack::Client,
+    tasks: Option<TaskQueue>,
+    nonces: Option<NonceStore>,
+) -> Router {
+    let repo = FeedbackRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
         .table_name(table_name)
         .build();
+    let decision_repo = DecisionRepositoryImpl::builder()
+        .client(dynamodb_client)
+        .table_name(decision_table_name)
+        .build();
 
-    let state = CollectionService::builder()
+    let state = EventService::builder()
         .repo(repo)
+        .decision_repo(decision_repo)
+        .route_repo(route_repo)
+        .bedrock_client(bedrock_client)
         .slack_client(slack_client)
-        .slack_channel_id(slack_channel_id)
+        .tasks(tasks)
+        .nonces(nonces)
         .build();
     Router::new()
-        .route("/", post(add_feedback_handler))
+        .route("/", post(events_handler))
+        .with_state(state)
+}
+
+pub async fn create_api_router(
+    dynamodb_client: aws_sdk_dynamodb::Client,
+    decision_table_name: String,
+) -> Router {
+    let decision_repo = DecisionRepositoryImpl::builder()
+        .client(dynamodb_client)
+        .table_name(decision_table_name)
+        .build();
+
+    let state = ReportService::builder()
+        .decision_repo(decision_repo)
+        .build();
+    Router::new()
+        .route("/alerts/unacknowledged", get(unacknowledged_alerts_handler))
+        .route("/stats/response-times", get(response_times_handler))
+        .with_state(state)
+}
+
+/// Routes of the feedback page linked by the alert sinks other than Slack. The page
+/// checks the signature of its links itself, as browsers cannot sign requests.
+pub async fn create_feedback_page_router(
+    dynamodb_client: aws_sdk_dynamodb::Client,
+    table_name: String,
+    decision_table_name: String,
+    feedback_links: FeedbackLinks,
+    slack_client: slack::Client,
+    sinks: AlertSinks,
+) -> Router {
+    let repo = FeedbackRepositoryImpl::builder()
+        .client(dynamodb_client.clone())
+        .table_name(table_name)
+        .build();
+    let decision_repo = DecisionRepositoryImpl::builder()
+        .client(dynamodb_client)
+        .table_name(decision_table_name)
+        .build();
+
+    let state = FeedbackPageService::builder()
+        .repo(repo)
+        .decision_repo(decision_repo)
+        .feedback_links(feedback_links)
+        .locale(slack_client.default_locale())
+        .sinks(sinks)
+        .build();
+    Router::new()
+        .route(
+            "/",
+            get(feedback_page_handler).post(submit_feedback_page_handler),
+        )
+        .with_state(state)
+}
+
+/// Routes of the OAuth flow installing the app to other workspaces. They are opened in
+/// browsers, so they are not signed by Slack.
+pub async fn create_install_router(
+    installations: InstallationRepositoryImpl,
+    oauth: OAuthSettings,
+    slack_client: slack::Client,
+) -> Router {
+    let state = InstallationService::builder()
+        .repo(installations)
+        .locale(slack_client.default_locale())
+        .slack_client(slack_client)
+        .oauth(oauth)
+        .build();
+    Router::new()
+        .route("/install", get(install_handler))
+        .route("/oauth_redirect", get(oauth_redirect_handler))
         .with_state(state)
 }
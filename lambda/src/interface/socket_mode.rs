@@ -0,0 +1,521 @@
+//! Slack Socket Mode, for workspaces that do not allow the collector on a public URL.
+//!
+//! Envelopes received over the WebSocket are turned into the requests Slack sends in HTTP
+//! mode and passed to the same router, so that both modes share the handlers. Envelopes
+//! accepting a response payload are acknowledged with whatever their handler responds;
+//! the others are acknowledged before they are handled, as Slack sends an envelope again
+//! unless it is acknowledged within 3 seconds.
+
+use crate::infrastructure::slack;
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::Request;
+use axum::Router;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tower::ServiceExt;
+use typed_builder::TypedBuilder;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Wait before reconnecting after a failed connection, doubled on each further failure.
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Longest wait for the acknowledgements of envelopes still being handled on a disconnect.
+/// Slack sends the envelopes again after 3 seconds anyway.
+const MAX_DRAIN_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EnvelopeType {
+    Hello,
+    Disconnect,
+    EventsApi,
+    Interactive,
+    SlashCommands,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(rename = "type")]
+    kind: EnvelopeType,
+    envelope_id: Option<String>,
+    #[serde(default)]
+    payload: Value,
+    #[serde(default)]
+    accepts_response_payload: bool,
+    /// Why Slack is about to close the connection, on `disconnect` envelopes.
+    reason: Option<String>,
+    /// How many times Slack has sent the envelope before, on redelivered events.
+    #[serde(default)]
+    retry_attempt: u32,
+    retry_reason: Option<String>,
+}
+
+#[derive(Clone, TypedBuilder)]
+pub struct SocketModeClient {
+    slack_client: slack::Client,
+    /// App-level token with the `connections:write` scope.
+    app_token: String,
+    /// Routes of the Slack requests, without the signature check, which Socket Mode does
+    /// not need.
+    router: Router,
+}
+
+impl SocketModeClient {
+    /// Serves envelopes for as long as the process runs, connecting again whenever Slack
+    /// asks to or the connection is lost.
+    pub async fn run(&self) {
+        let mut backoff = INITIAL_RECONNECT_BACKOFF;
+        loop {
+            match self.connect().await {
+                Ok(()) => {
+                    tracing::info!("Socket Mode connection closed, reconnecting");
+                    backoff = INITIAL_RECONNECT_BACKOFF;
+                }
+                Err(e) => {
+                    tracing::warn!("Socket Mode connection failed: {:?}", e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                }
+            }
+        }
+    }
+
+    async fn connect(&self) -> Result<(), Error> {
+        let url = self.slack_client.open_connection(&self.app_token).await?;
+        self.serve(&url).await
+    }
+
+    /// Serves the envelopes of a single connection until it is closed. Envelopes are
+    /// handled concurrently, so that a slow handler does not hold up the others.
+    async fn serve(&self, url: &str) -> Result<(), Error> {
+        let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+        let (mut sink, mut stream) = socket.split();
+        let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+
+        loop {
+            tokio::select! {
+                Some(ack) = receiver.recv() => sink.send(Message::text(ack)).await?,
+                message = stream.next() => {
+                    let text = match message.transpose()? {
+                        Some(Message::Text(text)) => text,
+                        Some(Message::Close(_)) | None => return Ok(()),
+                        Some(_) => continue,
+                    };
+                    let envelope: Envelope = match serde_json::from_str(&text) {
+                        Ok(envelope) => envelope,
+                        Err(e) => {
+                            tracing::warn!("Failed to parse envelope: {:?}", e);
+                            continue;
+                        }
+                    };
+                    match envelope.kind {
+                        EnvelopeType::Hello => tracing::info!("Connected in Socket Mode"),
+                        EnvelopeType::Disconnect => {
+                            tracing::info!("Disconnect requested: {:?}", envelope.reason);
+                            // Envelopes still being handled are acknowledged before the
+                            // connection is left, so that Slack does not send them again.
+                            drop(sender);
+                            let drain = async {
+                                while let Some(ack) = receiver.recv().await {
+                                    sink.send(Message::text(ack)).await?;
+                                }
+                                Ok::<_, Error>(())
+                            };
+                            return match tokio::time::timeout(MAX_DRAIN_WAIT, drain).await {
+                                Ok(result) => result,
+                                Err(_) => {
+                                    tracing::warn!("Left envelopes unacknowledged on disconnect");
+                                    Ok(())
+                                }
+                            };
+                        }
+                        _ => {
+                            let Some(envelope_id) = envelope.envelope_id.clone() else {
+                                continue;
+                            };
+                            let request = match make_request(&envelope) {
+                                Ok(Some(request)) => Some(request),
+                                Ok(None) => {
+                                    tracing::info!("Ignored envelope of type {:?}", envelope.kind);
+                                    None
+                                }
+                                Err(e) => {
+                                    tracing::warn!("Failed to make request from envelope: {:?}", e);
+                                    None
+                                }
+                            };
+                            let router = self.router.clone();
+                            if envelope.accepts_response_payload {
+                                let sender = sender.clone();
+                                tokio::spawn(async move {
+                                    let payload = match request {
+                                        Some(request) => dispatch(router, request).await,
+                                        None => None,
+                                    };
+                                    let _ = sender.send(make_ack(&envelope_id, payload));
+                                });
+                            } else {
+                                sink.send(Message::text(make_ack(&envelope_id, None))).await?;
+                                if let Some(request) = request {
+                                    tokio::spawn(dispatch(router, request));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the acknowledgement of an envelope, carrying the handler's response if any.
+fn make_ack(envelope_id: &str, payload: Option<Value>) -> String {
+    let mut ack = serde_json::json!({ "envelope_id": envelope_id });
+    if let Some(payload) = payload {
+        ack["payload"] = payload;
+    }
+    ack.to_string()
+}
+
+/// Returns the request Slack would send in HTTP mode for the envelope, or `None` for
+/// envelopes this application does not handle.
+fn make_request(envelope: &Envelope) -> Result<Option<Request<Body>>, Error> {
+    let form = "application/x-www-form-urlencoded";
+    let (path, content_type, body) = match envelope.kind {
+        EnvelopeType::Interactive => (
+            "/feedback",
+            form,
+            serde_urlencoded::to_string([("payload", envelope.payload.to_string())])?,
+        ),
+        EnvelopeType::SlashCommands => {
+            let fields: Vec<(&String, String)> = envelope
+                .payload
+                .as_object()
+                .ok_or("slash command payload is not an object")?
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::String(value) => (key, value.clone()),
+                    value => (key, value.to_string()),
+                })
+                .collect();
+            ("/command", form, serde_urlencoded::to_string(fields)?)
+        }
+        EnvelopeType::EventsApi => ("/events", "application/json", envelope.payload.to_string()),
+        _ => return Ok(None),
+    };
+
+    let mut request = Request::post(path).header(CONTENT_TYPE, content_type);
+    // Redelivered events carry the headers of retried HTTP requests, so that they are
+    // recognized the same way.
+    if envelope.retry_attempt > 0 {
+        request = request.header("X-Slack-Retry-Num", envelope.retry_attempt);
+        if let Some(reason) = &envelope.retry_reason {
+            request = request.header("X-Slack-Retry-Reason", reason);
+        }
+    }
+    Ok(Some(request.body(Body::from(body))?))
+}
+
+/// Passes the request to the router and returns its JSON response, if any.
+async fn dispatch(router: Router, request: Request<Body>) -> Option<Value> {
+    let path = request.uri().path().to_string();
+    let response = router.oneshot(request).await.ok()?;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .ok()?;
+    if !status.is_success() {
+        tracing::warn!("Handler of {} failed with {}", path, status);
+        return None;
+    }
+
+    serde_json::from_slice(&body).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_server;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Form, Json};
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    /// Stands in for `apps.connections.open`, handing out the URL of the local WebSocket
+    /// server.
+    async fn serve_connections_open(ws_url: String) -> String {
+        let app = Router::new().route(
+            "/apps.connections.open",
+            post(move || async move { Json(serde_json::json!({ "ok": true, "url": ws_url })) }),
+        );
+        test_server(app).await
+    }
+
+    /// Echoes what the handlers received, so the test can check the requests.
+    fn make_router() -> Router {
+        Router::new()
+            .route(
+                "/feedback",
+                post(|Form(form): Form<HashMap<String, String>>| async move {
+                    Json(serde_json::json!({ "received": form["payload"] }))
+                }),
+            )
+            .route(
+                "/command",
+                post(|Form(form): Form<HashMap<String, String>>| async move {
+                    Json(serde_json::json!({ "text": form["text"] }))
+                }),
+            )
+            .route(
+                "/events",
+                post(|headers: HeaderMap, body: String| async move {
+                    assert_eq!(headers[CONTENT_TYPE], "application/json");
+                    assert!(body.contains("app_mention"));
+                    ""
+                }),
+            )
+    }
+
+    async fn receive_ack(
+        socket: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    ) -> Value {
+        loop {
+            if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_socket_mode() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+        let client = SocketModeClient::builder()
+            .slack_client(
+                slack::Client::builder()
+                    .inner_client(reqwest::Client::new())
+                    .token("xoxb-token".into())
+                    .base_url(serve_connections_open(ws_url.clone()).await)
+                    .build(),
+            )
+            .app_token("xapp-token".into())
+            .router(make_router())
+            .build();
+        let connection = tokio::spawn({
+            let client = client.clone();
+            async move { client.connect().await.map_err(|e| e.to_string()) }
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        socket
+            .send(Message::text(r#"{"type":"hello"}"#))
+            .await
+            .unwrap();
+        socket
+            .send(Message::text(
+                serde_json::json!({
+                    "type": "interactive",
+                    "envelope_id": "e1",
+                    "payload": { "type": "block_actions" },
+                    "accepts_response_payload": true
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            receive_ack(&mut socket).await,
+            serde_json::json!({
+                "envelope_id": "e1",
+                "payload": { "received": r#"{"type":"block_actions"}"# }
+            })
+        );
+        socket
+            .send(Message::text(
+                serde_json::json!({
+                    "type": "events_api",
+                    "envelope_id": "e2",
+                    "payload": { "type": "event_callback", "event": { "type": "app_mention" } },
+                    "accepts_response_payload": false
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            receive_ack(&mut socket).await,
+            serde_json::json!({ "envelope_id": "e2" })
+        );
+
+        // A disconnect ends the connection cleanly, so that `run` opens a new one.
+        socket
+            .send(Message::text(
+                r#"{"type":"disconnect","reason":"refresh_requested"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(connection.await.unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_socket_mode_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+        let client = SocketModeClient::builder()
+            .slack_client(
+                slack::Client::builder()
+                    .inner_client(reqwest::Client::new())
+                    .token("xoxb-token".into())
+                    .build(),
+            )
+            .app_token("xapp-token".into())
+            .router(make_router())
+            .build();
+        let connection =
+            tokio::spawn(async move { client.serve(&ws_url).await.map_err(|e| e.to_string()) });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        socket
+            .send(Message::text(
+                serde_json::json!({
+                    "type": "slash_commands",
+                    "envelope_id": "e3",
+                    "payload": { "command": "/alert-filter", "text": "stats" },
+                    "accepts_response_payload": true
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            receive_ack(&mut socket).await,
+            serde_json::json!({ "envelope_id": "e3", "payload": { "text": "stats" } })
+        );
+
+        // A connection closed by Slack also ends cleanly.
+        socket.close(None).await.unwrap();
+        assert_eq!(connection.await.unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_socket_mode_ack_timing() {
+        // Events are held until the test lets them go, and interactions take a while.
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let released = std::sync::Arc::new(tokio::sync::Mutex::new(Some(released)));
+        let router = Router::new()
+            .route(
+                "/events",
+                post(move || async move {
+                    if let Some(released) = released.lock().await.take() {
+                        released.await.unwrap();
+                    }
+                    ""
+                }),
+            )
+            .route(
+                "/feedback",
+                post(|| async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    Json(serde_json::json!({ "response_action": "clear" }))
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+        let client = SocketModeClient::builder()
+            .slack_client(
+                slack::Client::builder()
+                    .inner_client(reqwest::Client::new())
+                    .token("xoxb-token".into())
+                    .build(),
+            )
+            .app_token("xapp-token".into())
+            .router(router)
+            .build();
+        let connection =
+            tokio::spawn(async move { client.serve(&ws_url).await.map_err(|e| e.to_string()) });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        socket
+            .send(Message::text(
+                serde_json::json!({
+                    "type": "events_api",
+                    "envelope_id": "e1",
+                    "payload": { "type": "event_callback" },
+                    "accepts_response_payload": false
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+        // Acknowledged while the handler is still waiting.
+        assert_eq!(
+            receive_ack(&mut socket).await,
+            serde_json::json!({ "envelope_id": "e1" })
+        );
+        release.send(()).unwrap();
+
+        socket
+            .send(Message::text(
+                serde_json::json!({
+                    "type": "interactive",
+                    "envelope_id": "e2",
+                    "payload": { "type": "view_submission" },
+                    "accepts_response_payload": true
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+        socket
+            .send(Message::text(
+                r#"{"type":"disconnect","reason":"refresh_requested"}"#,
+            ))
+            .await
+            .unwrap();
+        // The envelope being handled is still acknowledged before the connection is left.
+        assert_eq!(
+            receive_ack(&mut socket).await,
+            serde_json::json!({ "envelope_id": "e2", "payload": { "response_action": "clear" } })
+        );
+        assert_eq!(connection.await.unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn test_make_request_retry() {
+        let envelope: Envelope = serde_json::from_value(serde_json::json!({
+            "type": "events_api",
+            "envelope_id": "e1",
+            "payload": { "type": "event_callback" },
+            "retry_attempt": 1,
+            "retry_reason": "timeout"
+        }))
+        .unwrap();
+        let request = make_request(&envelope).unwrap().unwrap();
+        assert_eq!(request.headers()["X-Slack-Retry-Num"], "1");
+        assert_eq!(request.headers()["X-Slack-Retry-Reason"], "timeout");
+
+        let envelope: Envelope = serde_json::from_value(serde_json::json!({
+            "type": "events_api",
+            "envelope_id": "e2",
+            "payload": { "type": "event_callback" },
+            "retry_attempt": 0
+        }))
+        .unwrap();
+        let request = make_request(&envelope).unwrap().unwrap();
+        assert!(!request.headers().contains_key("X-Slack-Retry-Num"));
+    }
+}
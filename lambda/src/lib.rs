@@ -1,4 +1,5 @@
 pub mod application;
+pub mod config;
 pub(crate) mod domain;
 pub mod infrastructure;
 pub mod interface;
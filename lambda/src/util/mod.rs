@@ -1,18 +1,8 @@
 use chrono::SecondsFormat;
-use serde::de;
-
-pub(crate) fn deserialize_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
-where
-    D: de::Deserializer<'de>,
-{
-    let s: &str = de::Deserialize::deserialize(deserializer)?;
-
-    match s {
-        "true" => Ok(true),
-        "false" => Ok(false),
-        _ => Err(de::Error::unknown_variant(s, &["true", "false"])),
-    }
-}
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::borrow::Cow;
+use std::collections::HashMap;
 
 pub(crate) fn now_timestamp() -> i64 {
     chrono::Utc::now().timestamp()
@@ -21,3 +11,164 @@ pub(crate) fn now_timestamp() -> i64 {
 pub(crate) fn now_rfc3339() -> String {
     chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
 }
+
+/// Returns the signature Slack gives a request sent at `timestamp`, the HMAC-SHA256 of
+/// `v0:<timestamp>:<body>` under the signing secret.
+pub(crate) fn slack_signature(signing_secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes()).expect("HMAC takes any key");
+    mac.update(format!("v0:{}:", timestamp).as_bytes());
+    mac.update(body);
+    format!("v0={:x}", mac.finalize().into_bytes())
+}
+
+/// Characters of each text compared by `similarity`. Log messages can be long, and their
+/// beginning tells them apart well enough.
+const MAX_SIMILARITY_CHARS: usize = 1000;
+
+/// Returns the Sørensen–Dice coefficient of the character bigrams of `a` and `b`,
+/// ignoring case and surrounding whitespace, and the characters after the first
+/// `MAX_SIMILARITY_CHARS`. The result is in the range `0.0..=1.0`.
+pub(crate) fn similarity(a: &str, b: &str) -> f64 {
+    fn normalize(s: &str) -> Vec<char> {
+        s.trim()
+            .to_lowercase()
+            .chars()
+            .take(MAX_SIMILARITY_CHARS)
+            .collect()
+    }
+
+    let a = normalize(a);
+    let b = normalize(b);
+    if a == b {
+        return 1.0;
+    }
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+
+    let mut counts: HashMap<(char, char), usize> = HashMap::new();
+    for w in b.windows(2) {
+        *counts.entry((w[0], w[1])).or_default() += 1;
+    }
+    let mut matches = 0;
+    for w in a.windows(2) {
+        if let Some(count) = counts.get_mut(&(w[0], w[1])).filter(|v| **v > 0) {
+            *count -= 1;
+            matches += 1;
+        }
+    }
+
+    let total = (a.len() - 1) + (b.len() - 1);
+    (2 * matches) as f64 / total as f64
+}
+
+/// Shortens `text` to at most `max_chars` characters, replacing the end with `marker`
+/// when it does not fit.
+pub(crate) fn truncate<'a>(text: &'a str, max_chars: usize, marker: &str) -> Cow<'a, str> {
+    if text.chars().count() <= max_chars {
+        return Cow::Borrowed(text);
+    }
+
+    let keep = max_chars.saturating_sub(marker.chars().count());
+    let mut truncated: String = text.chars().take(keep).collect();
+    truncated.push_str(marker);
+    Cow::Owned(truncated)
+}
+
+/// Parses a positive duration written as a number and a unit of `s`, `m`, `h` or `d`,
+/// such as `30m` or `7d`.
+pub(crate) fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let value = value.trim();
+    let (index, unit) = value.char_indices().last()?;
+    let number: i64 = value[..index].parse().ok().filter(|v| *v > 0)?;
+    match unit {
+        's' => chrono::Duration::try_seconds(number),
+        'm' => chrono::Duration::try_minutes(number),
+        'h' => chrono::Duration::try_hours(number),
+        'd' => chrono::Duration::try_days(number),
+        _ => None,
+    }
+}
+
+/// Formats a number of seconds with its two largest units, such as `2h 5m`.
+pub(crate) fn format_duration(secs: i64) -> String {
+    let units = [("d", 86_400), ("h", 3_600), ("m", 60), ("s", 1)];
+    let parts: Vec<String> = units
+        .iter()
+        .scan(secs.max(0), |rest, (unit, size)| {
+            let value = *rest / size;
+            *rest %= size;
+            Some((value, unit))
+        })
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Serves the router on a free local port and returns its base URL, standing in for the
+/// APIs the clients call in tests.
+#[cfg(test)]
+pub(crate) async fn test_server(router: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("ERROR: timeout", "error: timeout "), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert!(similarity("ERROR: request 1234 failed", "ERROR: request 5678 failed") > 0.7);
+        assert!(similarity("ERROR: request failed", "WARN: disk is full") < 0.3);
+        assert_eq!(similarity("aaaa", "aa"), 0.5);
+        assert_eq!(similarity("a", "b"), 0.0);
+        // Only the beginning of long messages is compared.
+        let long = "x".repeat(MAX_SIMILARITY_CHARS);
+        assert_eq!(
+            similarity(&format!("{}a", long), &format!("{}b", long)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10, "…"), "short");
+        assert_eq!(truncate("ログメッセージ", 5, "…"), "ログメッ…");
+        assert_eq!(truncate("0123456789abc", 10, "[cut]"), "01234[cut]");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(7_500), "2h 5m");
+        assert_eq!(format_duration(90_000), "1d 1h");
+        assert_eq!(format_duration(86_430), "1d");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Some(chrono::Duration::seconds(90)));
+        assert_eq!(parse_duration("30m"), Some(chrono::Duration::minutes(30)));
+        assert_eq!(parse_duration("4h"), Some(chrono::Duration::hours(4)));
+        assert_eq!(parse_duration("7d"), Some(chrono::Duration::days(7)));
+        assert_eq!(parse_duration("0h"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("3w"), None);
+        assert_eq!(parse_duration("3時"), None);
+    }
+}